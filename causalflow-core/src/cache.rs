@@ -0,0 +1,100 @@
+use crate::errors::Result;
+use ndarray::{ArrayView1, ArrayView2};
+use std::any::Any;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+/// Fitted nuisances kept at once; inserting past this drops the oldest entry.
+pub const MAX_CACHE_ENTRIES: usize = 64;
+
+/// Process-wide store of fitted nuisance models (propensity scores, outcome
+/// regressions), keyed on a fingerprint of what was fit and the data it was fit on.
+///
+/// `validate`, `refute` and the negative-control check refit models many times; the
+/// nuisances inside those fits are often fit on exactly the same rows again (repeated
+/// k-fold runs, the propensity model of a negative-control refit), and are then
+/// reused from here instead of refit.
+#[derive(Default)]
+struct NuisanceCache {
+    entries: HashMap<u64, Box<dyn Any + Send>>,
+    order: VecDeque<u64>,
+}
+
+static CACHE: OnceLock<Mutex<NuisanceCache>> = OnceLock::new();
+
+fn cache() -> MutexGuard<'static, NuisanceCache> {
+    CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Fingerprint of a nuisance fit: `kind` names what is fit (and must change with any
+/// configuration that changes the fit), followed by every value of `x` and `target`.
+pub fn nuisance_key(kind: &str, x: ArrayView2<f64>, target: ArrayView1<f64>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    kind.hash(&mut hasher);
+    x.dim().hash(&mut hasher);
+    for v in x.iter().chain(target.iter()) {
+        v.to_bits().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// The cached value for `key`, or the result of `fit`, which is cached on success.
+/// The cache is not locked while `fit` runs, so fits may run in parallel and nest.
+///
+/// ```
+/// use causalflow_core::cache::{clear_cache, get_or_fit, nuisance_key};
+/// use ndarray::array;
+///
+/// let x = array![[1.0], [2.0]];
+/// let key = nuisance_key("example", x.view(), array![0.0, 1.0].view());
+/// let mut n_fits = 0;
+/// for _ in 0..3 {
+///     let value: f64 = get_or_fit(key, || {
+///         n_fits += 1;
+///         Ok(42.0)
+///     })
+///     .unwrap();
+///     assert_eq!(value, 42.0);
+/// }
+/// assert_eq!(n_fits, 1);
+/// assert_eq!(clear_cache(), 1);
+/// assert_eq!(clear_cache(), 0);
+/// ```
+pub fn get_or_fit<T: Clone + Send + 'static>(
+    key: u64,
+    fit: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    if let Some(value) = cache()
+        .entries
+        .get(&key)
+        .and_then(|entry| entry.downcast_ref::<T>())
+    {
+        return Ok(value.clone());
+    }
+    let value = fit()?;
+
+    let mut cache = cache();
+    if cache.entries.insert(key, Box::new(value.clone())).is_none() {
+        cache.order.push_back(key);
+        while cache.order.len() > MAX_CACHE_ENTRIES {
+            if let Some(oldest) = cache.order.pop_front() {
+                cache.entries.remove(&oldest);
+            }
+        }
+    }
+    Ok(value)
+}
+
+/// Drops every cached nuisance fit, returning how many there were.
+pub fn clear_cache() -> usize {
+    let mut cache = cache();
+    cache.order.clear();
+    let n_entries = cache.entries.len();
+    cache.entries.clear();
+    n_entries
+}
@@ -0,0 +1,121 @@
+use crate::errors::{CausalFlowError, Result};
+use crate::forest::difference_in_means;
+use ndarray::ArrayView1;
+
+/// Monotone non-decreasing map from predicted to observed effects, fitted by isotonic
+/// regression and evaluated by linear interpolation between knots.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct IsotonicMap {
+    pub knots: Vec<f64>,
+    pub values: Vec<f64>,
+}
+
+impl IsotonicMap {
+    /// Pool-adjacent-violators fit of `targets` against increasing `knots`.
+    pub fn fit(knots: &[f64], targets: &[f64], weights: &[f64]) -> Self {
+        // Each block: (weighted mean, total weight, number of knots pooled)
+        let mut blocks: Vec<(f64, f64, usize)> = Vec::with_capacity(knots.len());
+        for (&target, &weight) in targets.iter().zip(weights) {
+            blocks.push((target, weight, 1));
+            while blocks.len() > 1 && blocks[blocks.len() - 2].0 > blocks[blocks.len() - 1].0 {
+                let (m2, w2, c2) = blocks.pop().unwrap();
+                let (m1, w1, c1) = blocks.pop().unwrap();
+                let w = w1 + w2;
+                blocks.push(((m1 * w1 + m2 * w2) / w, w, c1 + c2));
+            }
+        }
+
+        // `repeat_n` would need Rust 1.82; the crates declare no minimum version
+        #[allow(clippy::manual_repeat_n)]
+        let values = blocks
+            .iter()
+            .flat_map(|&(mean, _, count)| std::iter::repeat(mean).take(count))
+            .collect();
+        Self {
+            knots: knots.to_vec(),
+            values,
+        }
+    }
+
+    /// Calibrated value for `v`, clamped to the end knots outside their range.
+    pub fn apply(&self, v: f64) -> f64 {
+        let n = self.knots.len();
+        if n == 0 || !v.is_finite() {
+            return v;
+        }
+        if v <= self.knots[0] {
+            return self.values[0];
+        }
+        if v >= self.knots[n - 1] {
+            return self.values[n - 1];
+        }
+        let hi = self.knots.partition_point(|&k| k < v);
+        let lo = hi - 1;
+        let span = self.knots[hi] - self.knots[lo];
+        if span <= 0.0 {
+            return self.values[hi];
+        }
+        let w = (v - self.knots[lo]) / span;
+        self.values[lo] + w * (self.values[hi] - self.values[lo])
+    }
+}
+
+/// Fits an isotonic map from predicted effects to observed effects on held-out data.
+///
+/// Units are sorted by predicted effect and split into `n_bins` equal-count bins; each
+/// bin's observed effect is its treated/control difference in means. Bins missing either
+/// arm are skipped.
+pub fn fit_isotonic_calibration(
+    predicted: ArrayView1<f64>,
+    t: ArrayView1<f64>,
+    y: ArrayView1<f64>,
+    n_bins: usize,
+) -> Result<IsotonicMap> {
+    let n = predicted.len();
+    if n == 0 {
+        return Err(CausalFlowError::EmptyData);
+    }
+    if t.len() != n || y.len() != n {
+        return Err(CausalFlowError::InvalidParameter(format!(
+            "calibration lengths differ: {} predictions, {} treatments, {} outcomes",
+            n,
+            t.len(),
+            y.len()
+        )));
+    }
+
+    if n_bins < 2 {
+        return Err(CausalFlowError::InvalidParameter(
+            "n_bins must be at least 2".to_string(),
+        ));
+    }
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| predicted[a].total_cmp(&predicted[b]));
+
+    let n_bins = n_bins.min(n);
+    let (mut knots, mut targets, mut weights) = (Vec::new(), Vec::new(), Vec::new());
+    for b in 0..n_bins {
+        let bin = &order[b * n / n_bins..(b + 1) * n / n_bins];
+        let bin_t: Vec<f64> = bin.iter().map(|&i| t[i]).collect();
+        let n_treated = bin_t.iter().filter(|&&ti| ti > 0.5).count();
+        if n_treated == 0 || n_treated == bin.len() {
+            continue;
+        }
+        let bin_y: Vec<f64> = bin.iter().map(|&i| y[i]).collect();
+        knots.push(bin.iter().map(|&i| predicted[i]).sum::<f64>() / bin.len() as f64);
+        targets.push(difference_in_means(
+            ArrayView1::from(&bin_t),
+            ArrayView1::from(&bin_y),
+        ));
+        weights.push(bin.len() as f64);
+    }
+
+    if knots.len() < 2 {
+        return Err(CausalFlowError::Calculation(
+            "not enough bins with both treated and control units to calibrate".to_string(),
+        ));
+    }
+
+    Ok(IsotonicMap::fit(&knots, &targets, &weights))
+}
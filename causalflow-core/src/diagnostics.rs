@@ -0,0 +1,486 @@
+use crate::errors::{CausalFlowError, Result};
+use crate::model::CausalModel;
+use crate::stats::{normal_quantile, sorted_quantile};
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2, Axis};
+
+/// Basic treatment/outcome sanity statistics to check before trusting any estimate.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct DataSummary {
+    pub n_samples: usize,
+    pub n_treated: usize,
+    pub n_control: usize,
+    pub treatment_prevalence: f64,
+    pub outcome_mean: f64,
+    pub outcome_std: f64,
+    pub treated_outcome_mean: f64,
+    pub treated_outcome_std: f64,
+    pub control_outcome_mean: f64,
+    pub control_outcome_std: f64,
+}
+
+fn mean_std(values: &[f64]) -> (f64, f64) {
+    if values.is_empty() {
+        return (f64::NAN, f64::NAN);
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let var = if values.len() > 1 {
+        values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)
+    } else {
+        0.0
+    };
+    (mean, var.sqrt())
+}
+
+/// Weighted mean and standard deviation with reliability weights; equal weights give
+/// the same values as `mean_std`.
+fn weighted_mean_std(values: &[f64], weights: &[f64]) -> (f64, f64) {
+    let total: f64 = weights.iter().sum();
+    if values.is_empty() || total <= 0.0 {
+        return (f64::NAN, f64::NAN);
+    }
+    let mean = values.iter().zip(weights).map(|(v, w)| v * w).sum::<f64>() / total;
+    let denominator = total - weights.iter().map(|w| w * w).sum::<f64>() / total;
+    let var = if denominator > 0.0 {
+        values
+            .iter()
+            .zip(weights)
+            .map(|(v, w)| w * (v - mean).powi(2))
+            .sum::<f64>()
+            / denominator
+    } else {
+        0.0
+    };
+    (mean, var.sqrt())
+}
+
+/// Kish effective sample size `(sum w)^2 / sum w^2` of `weights`.
+fn effective_size(weights: impl Iterator<Item = f64> + Clone) -> f64 {
+    let total: f64 = weights.clone().sum();
+    let squares: f64 = weights.map(|w| w * w).sum();
+    if squares > 0.0 {
+        total * total / squares
+    } else {
+        0.0
+    }
+}
+
+pub fn data_summary(t: ArrayView1<f64>, y: ArrayView1<f64>) -> Result<DataSummary> {
+    if t.is_empty() {
+        return Err(CausalFlowError::EmptyData);
+    }
+    if t.len() != y.len() {
+        return Err(CausalFlowError::InvalidParameter(format!(
+            "treatment has {} rows but outcome has {}",
+            t.len(),
+            y.len()
+        )));
+    }
+
+    let mut treated = Vec::new();
+    let mut control = Vec::new();
+    for (&ti, &yi) in t.iter().zip(y.iter()) {
+        if ti > 0.5 {
+            treated.push(yi);
+        } else {
+            control.push(yi);
+        }
+    }
+
+    let (outcome_mean, outcome_std) = mean_std(&y.to_vec());
+    let (treated_outcome_mean, treated_outcome_std) = mean_std(&treated);
+    let (control_outcome_mean, control_outcome_std) = mean_std(&control);
+
+    Ok(DataSummary {
+        n_samples: t.len(),
+        n_treated: treated.len(),
+        n_control: control.len(),
+        treatment_prevalence: treated.len() as f64 / t.len() as f64,
+        outcome_mean,
+        outcome_std,
+        treated_outcome_mean,
+        treated_outcome_std,
+        control_outcome_mean,
+        control_outcome_std,
+    })
+}
+
+/// Per-arm sample sizes seen by `fit` and what they imply for honest leaves.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct FitReport {
+    pub n_treated: usize,
+    pub n_control: usize,
+    /// Kish effective sizes of the arms under the fit's sample weights; equal to the
+    /// unit counts for an unweighted fit.
+    #[serde(default)]
+    pub effective_n_treated: f64,
+    #[serde(default)]
+    pub effective_n_control: f64,
+    pub min_leaf_size: usize,
+    /// Most leaves a tree can form on its honest estimation half.
+    pub max_honest_leaves: usize,
+    /// Expected count of the smaller arm in a leaf of exactly `min_leaf_size` units.
+    pub expected_min_arm_per_leaf: f64,
+    pub warning: Option<String>,
+}
+
+/// Builds the fit-time arm report. Warns when the smaller arm is small relative to
+/// `min_leaf_size * n_estimators`, where honest leaves often miss one arm entirely.
+/// With `sample_weight` the arm sizes behind the warning and the expected leaf
+/// composition are Kish effective sizes, since a few heavy units carry the estimate.
+pub fn fit_report(
+    t: ArrayView1<f64>,
+    sample_weight: Option<ArrayView1<f64>>,
+    min_leaf_size: usize,
+    n_estimators: usize,
+) -> FitReport {
+    let n_treated = t.iter().filter(|&&ti| ti > 0.5).count();
+    let n_control = t.len() - n_treated;
+    let weight = |i: usize| sample_weight.map_or(1.0, |w| w[i]);
+    let arm_size = |treated: bool| {
+        effective_size(
+            (0..t.len())
+                .filter(move |&i| (t[i] > 0.5) == treated)
+                .map(weight),
+        )
+    };
+    let (effective_n_treated, effective_n_control) = (arm_size(true), arm_size(false));
+    let n_minority = effective_n_treated.min(effective_n_control);
+    let minority_share = if t.is_empty() {
+        0.0
+    } else {
+        n_minority / (effective_n_treated + effective_n_control)
+    };
+
+    let threshold = min_leaf_size * n_estimators;
+    let warning = if n_minority < threshold as f64 {
+        let size = match sample_weight {
+            Some(_) => format!("an effective size of only {:.1} units", n_minority),
+            None => format!("only {} units", n_minority),
+        };
+        Some(format!(
+            "Smaller treatment arm has {} (min_leaf_size * n_estimators = {}); \
+             leaves may lack treated or control units. Consider lowering min_leaf_size.",
+            size, threshold
+        ))
+    } else {
+        None
+    };
+
+    FitReport {
+        n_treated,
+        n_control,
+        effective_n_treated,
+        effective_n_control,
+        min_leaf_size,
+        max_honest_leaves: (t.len() - t.len() / 2) / min_leaf_size.max(1),
+        expected_min_arm_per_leaf: min_leaf_size as f64 * minority_share,
+        warning,
+    }
+}
+
+/// Smallest ATE a two-sided difference-in-means test at level `alpha` detects with
+/// probability `power`, given the arm sizes and outcome standard deviation.
+pub fn min_detectable_effect(
+    n_treated: usize,
+    n_control: usize,
+    outcome_std: f64,
+    power: f64,
+    alpha: f64,
+) -> Result<f64> {
+    if n_treated == 0 || n_control == 0 {
+        return Err(CausalFlowError::InvalidParameter(
+            "both treatment arms need at least one unit".to_string(),
+        ));
+    }
+    if !outcome_std.is_finite() || outcome_std < 0.0 {
+        return Err(CausalFlowError::InvalidParameter(format!(
+            "outcome_std must be finite and non-negative, got {}",
+            outcome_std
+        )));
+    }
+    if !(power > 0.0 && power < 1.0 && alpha > 0.0 && alpha < 1.0) {
+        return Err(CausalFlowError::InvalidParameter(format!(
+            "power and alpha must be in (0, 1), got power={} alpha={}",
+            power, alpha
+        )));
+    }
+
+    let se = outcome_std * (1.0 / n_treated as f64 + 1.0 / n_control as f64).sqrt();
+    Ok((normal_quantile(1.0 - alpha / 2.0) + normal_quantile(power)) * se)
+}
+
+/// CUPED control-variate adjustment: `y - theta * (pre - mean(pre))`.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct CupedAdjustment {
+    pub theta: f64,
+    pub covariate_mean: f64,
+    /// Share of outcome variance removed, `1 - var(y_adj) / var(y)`.
+    pub variance_reduction: f64,
+}
+
+/// Regresses a pre-treatment covariate out of the outcome. The covariate must not be
+/// affected by treatment, otherwise the adjustment biases the effect.
+pub fn cuped_adjust(
+    y: ArrayView1<f64>,
+    pre_covariate: ArrayView1<f64>,
+) -> Result<(Array1<f64>, CupedAdjustment)> {
+    if y.len() != pre_covariate.len() {
+        return Err(CausalFlowError::InvalidParameter(format!(
+            "pre_covariate has {} rows but outcome has {}",
+            pre_covariate.len(),
+            y.len()
+        )));
+    }
+    if y.len() < 2 {
+        return Err(CausalFlowError::EmptyData);
+    }
+    if pre_covariate.iter().any(|v| !v.is_finite()) {
+        return Err(CausalFlowError::InvalidData);
+    }
+
+    let covariate_mean = pre_covariate.mean().unwrap_or(0.0);
+    let y_mean = y.mean().unwrap_or(0.0);
+    let var_pre = pre_covariate.var(1.0);
+    if var_pre <= 0.0 {
+        return Err(CausalFlowError::InvalidParameter(
+            "pre_covariate is constant and cannot reduce variance".to_string(),
+        ));
+    }
+    let cov = pre_covariate
+        .iter()
+        .zip(y.iter())
+        .map(|(p, v)| (p - covariate_mean) * (v - y_mean))
+        .sum::<f64>()
+        / (y.len() - 1) as f64;
+    let theta = cov / var_pre;
+
+    let adjusted: Array1<f64> = y
+        .iter()
+        .zip(pre_covariate.iter())
+        .map(|(v, p)| v - theta * (p - covariate_mean))
+        .collect();
+    let var_y = y.var(1.0);
+    let variance_reduction = if var_y > 0.0 {
+        1.0 - adjusted.var(1.0) / var_y
+    } else {
+        0.0
+    };
+
+    Ok((
+        adjusted,
+        CupedAdjustment {
+            theta,
+            covariate_mean,
+            variance_reduction,
+        },
+    ))
+}
+
+/// How much better heterogeneous effects predict the transformed outcome than a single
+/// constant effect.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct HeterogeneityGain {
+    /// `1 - mse_heterogeneous / mse_constant`. Near zero (or negative) means the
+    /// per-unit effects add nothing over the constant-effect estimate.
+    pub heterogeneity_gain: f64,
+    pub constant_effect: f64,
+    pub mse_constant: f64,
+    pub mse_heterogeneous: f64,
+}
+
+/// Scores per-unit effects `cate` against a constant effect using the transformed
+/// outcome `y * (t - e) / (e * (1 - e))`, whose conditional mean is the CATE when
+/// treatment is randomized with propensity `e = mean(t)`.
+pub fn heterogeneity_gain(
+    cate: ArrayView1<f64>,
+    constant_effect: f64,
+    t: ArrayView1<f64>,
+    y: ArrayView1<f64>,
+) -> Result<HeterogeneityGain> {
+    if cate.len() != t.len() || t.len() != y.len() {
+        return Err(CausalFlowError::InvalidParameter(format!(
+            "effects, treatment and outcome lengths differ: {}, {}, {}",
+            cate.len(),
+            t.len(),
+            y.len()
+        )));
+    }
+    if t.is_empty() {
+        return Err(CausalFlowError::EmptyData);
+    }
+    let e = t.mean().unwrap_or(0.0);
+    if e <= 0.0 || e >= 1.0 {
+        return Err(CausalFlowError::InvalidParameter(
+            "both treatment arms need at least one unit".to_string(),
+        ));
+    }
+
+    let n = t.len() as f64;
+    let (mut mse_constant, mut mse_heterogeneous) = (0.0, 0.0);
+    for ((&ti, &yi), &tau) in t.iter().zip(y.iter()).zip(cate.iter()) {
+        let y_star = yi * (ti - e) / (e * (1.0 - e));
+        mse_constant += (y_star - constant_effect).powi(2) / n;
+        mse_heterogeneous += (y_star - tau).powi(2) / n;
+    }
+
+    Ok(HeterogeneityGain {
+        heterogeneity_gain: if mse_constant > 0.0 {
+            1.0 - mse_heterogeneous / mse_constant
+        } else {
+            0.0
+        },
+        constant_effect,
+        mse_constant,
+        mse_heterogeneous,
+    })
+}
+
+/// A feature whose distribution differs between treatment arms.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct FeatureImbalance {
+    pub feature_idx: usize,
+    /// Treated minus control mean over the pooled standard deviation.
+    pub standardized_difference: f64,
+}
+
+/// Features whose absolute standardized mean difference between arms exceeds
+/// `threshold`, largest first. With `sample_weight` the arm means and standard
+/// deviations are weighted, so balance is judged in the weighted population.
+///
+/// Under randomization pre-treatment features are balanced, so a strong imbalance
+/// suggests the feature was measured after treatment (a mediator or collider) or is a
+/// strong confounder. Either way it deserves a look before trusting the effects.
+pub fn post_treatment_suspects(
+    x: ArrayView2<f64>,
+    t: ArrayView1<f64>,
+    sample_weight: Option<ArrayView1<f64>>,
+    threshold: f64,
+) -> Vec<FeatureImbalance> {
+    let treated: Vec<usize> = (0..t.len()).filter(|&i| t[i] > 0.5).collect();
+    let control: Vec<usize> = (0..t.len()).filter(|&i| t[i] <= 0.5).collect();
+    if treated.len() < 2 || control.len() < 2 {
+        return Vec::new();
+    }
+    let weights = |rows: &[usize]| -> Vec<f64> {
+        rows.iter()
+            .map(|&i| sample_weight.map_or(1.0, |w| w[i]))
+            .collect()
+    };
+    let (treated_weights, control_weights) = (weights(&treated), weights(&control));
+
+    let mut suspects: Vec<FeatureImbalance> = x
+        .columns()
+        .into_iter()
+        .enumerate()
+        .filter_map(|(feature_idx, column)| {
+            let values = |rows: &[usize]| rows.iter().map(|&i| column[i]).collect::<Vec<_>>();
+            let (m1, s1) = weighted_mean_std(&values(&treated), &treated_weights);
+            let (m0, s0) = weighted_mean_std(&values(&control), &control_weights);
+            let pooled = ((s1 * s1 + s0 * s0) / 2.0).sqrt();
+            if pooled <= 0.0 {
+                return None;
+            }
+            let standardized_difference = (m1 - m0) / pooled;
+            (standardized_difference.abs() > threshold).then_some(FeatureImbalance {
+                feature_idx,
+                standardized_difference,
+            })
+        })
+        .collect();
+    suspects.sort_by(|a, b| {
+        b.standardized_difference
+            .abs()
+            .total_cmp(&a.standardized_difference.abs())
+    });
+    suspects
+}
+
+/// Partial dependence of the effect on one feature: `feature_idx` is swept over
+/// `n_grid` evenly spaced percentiles (5th to 95th) of its values in `x` while every
+/// other feature is held at its column mean. Returns the grid (repeated values from
+/// discrete features merged) and the predicted effect at each point.
+pub fn partial_dependence(
+    model: &dyn CausalModel,
+    x: ArrayView2<f64>,
+    feature_idx: usize,
+    n_grid: usize,
+) -> Result<(Vec<f64>, Vec<f64>)> {
+    if feature_idx >= x.ncols() {
+        return Err(CausalFlowError::FeatureOutOfBounds(feature_idx));
+    }
+    if x.nrows() == 0 {
+        return Err(CausalFlowError::EmptyData);
+    }
+    if n_grid < 2 {
+        return Err(CausalFlowError::InvalidParameter(
+            "n_grid must be at least 2".to_string(),
+        ));
+    }
+
+    let mut sorted = x.column(feature_idx).to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mut grid: Vec<f64> = (0..n_grid)
+        .map(|g| sorted_quantile(&sorted, 0.05 + 0.9 * g as f64 / (n_grid - 1) as f64))
+        .collect();
+    grid.dedup();
+
+    let means = x.mean_axis(Axis(0)).expect("x has rows");
+    let mut rows = Array2::from_shape_fn((grid.len(), x.ncols()), |(_, j)| means[j]);
+    rows.column_mut(feature_idx)
+        .assign(&Array1::from(grid.clone()));
+    let effects = model.predict(rows.view())?.predictions.to_vec();
+    Ok((grid, effects))
+}
+
+/// Mean predicted effect of the rows whose feature value lies in `(lower, upper]`.
+/// `None` bounds are open; empty subgroups have a NaN mean.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct SubgroupEffect {
+    pub lower: Option<f64>,
+    pub upper: Option<f64>,
+    pub mean_effect: f64,
+    pub count: usize,
+}
+
+/// Buckets rows by `values` at the strictly increasing `cut_points` (k cuts give k + 1
+/// subgroups, each closed on the right) and averages `effects` within each.
+pub fn subgroup_effects(
+    values: ArrayView1<f64>,
+    effects: ArrayView1<f64>,
+    cut_points: &[f64],
+) -> Result<Vec<SubgroupEffect>> {
+    if values.len() != effects.len() {
+        return Err(CausalFlowError::InvalidParameter(format!(
+            "{} feature values but {} effects",
+            values.len(),
+            effects.len()
+        )));
+    }
+    if cut_points.iter().any(|c| !c.is_finite()) || cut_points.windows(2).any(|w| w[0] >= w[1]) {
+        return Err(CausalFlowError::InvalidParameter(
+            "thresholds must be finite and strictly increasing".to_string(),
+        ));
+    }
+
+    let mut sums = vec![(0.0, 0usize); cut_points.len() + 1];
+    for (&v, &effect) in values.iter().zip(effects.iter()) {
+        let bucket = &mut sums[cut_points.partition_point(|&c| c < v)];
+        bucket.0 += effect;
+        bucket.1 += 1;
+    }
+    Ok(sums
+        .into_iter()
+        .enumerate()
+        .map(|(k, (sum, count))| SubgroupEffect {
+            lower: k.checked_sub(1).map(|j| cut_points[j]),
+            upper: cut_points.get(k).copied(),
+            mean_effect: if count > 0 {
+                sum / count as f64
+            } else {
+                f64::NAN
+            },
+            count,
+        })
+        .collect())
+}
@@ -0,0 +1,101 @@
+use crate::errors::{CausalFlowError, Result};
+use crate::forest::CausalForest;
+use ndarray::{Array1, ArrayView1, ArrayView2, Axis};
+
+/// Per-unit effects of a 2x2 factorial design.
+#[derive(Clone, Debug)]
+pub struct FactorialEffects {
+    /// Main effect of treatment A, averaged over the B arms.
+    pub effect_a: Array1<f64>,
+    /// Main effect of treatment B, averaged over the A arms.
+    pub effect_b: Array1<f64>,
+    /// `(Y11 - Y01) - (Y10 - Y00)`: how much B changes the effect of A.
+    pub interaction: Array1<f64>,
+}
+
+/// Causal forests for two simultaneous binary treatments (A/B/AB experiments).
+///
+/// Main effects come from forests on each treatment column over all rows, which is
+/// unbiased when both treatments are randomized independently. The interaction is the
+/// difference between the effect of A among B-treated and among B-control units, each
+/// estimated by its own forest. Unlike multi-arm designs, arms are not exclusive.
+#[derive(Clone)]
+pub struct FactorialForest {
+    pub effect_a: CausalForest,
+    pub effect_b: CausalForest,
+    pub a_with_b: CausalForest,
+    pub a_without_b: CausalForest,
+}
+
+impl FactorialForest {
+    /// Uses `template`'s configuration for all four component forests.
+    pub fn new(template: CausalForest) -> Self {
+        Self {
+            effect_a: template.clone(),
+            effect_b: template.clone(),
+            a_with_b: template.clone(),
+            a_without_b: template,
+        }
+    }
+
+    /// Fits on a treatment matrix with exactly two binary columns, A then B.
+    pub fn fit(
+        &mut self,
+        x: ArrayView2<f64>,
+        t: ArrayView2<f64>,
+        y: ArrayView1<f64>,
+    ) -> Result<()> {
+        if t.ncols() != 2 {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "factorial designs need exactly 2 treatment columns, got {}",
+                t.ncols()
+            )));
+        }
+        if t.nrows() != x.nrows() || y.len() != x.nrows() {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "x has {} rows but treatment has {} and outcome {}",
+                x.nrows(),
+                t.nrows(),
+                y.len()
+            )));
+        }
+
+        let t_a = t.column(0);
+        let t_b = t.column(1);
+        self.effect_a.fit_result(x, t_a, y)?;
+        self.effect_b.fit_result(x, t_b, y)?;
+
+        let (with_b, without_b): (Vec<usize>, Vec<usize>) =
+            (0..t.nrows()).partition(|&i| t_b[i] > 0.5);
+        for (forest, rows) in [
+            (&mut self.a_with_b, with_b),
+            (&mut self.a_without_b, without_b),
+        ] {
+            if rows.is_empty() {
+                return Err(CausalFlowError::InvalidParameter(
+                    "treatment B has an empty arm, so the interaction is not identified"
+                        .to_string(),
+                ));
+            }
+            forest.fit_result(
+                x.select(Axis(0), &rows).view(),
+                t_a.select(Axis(0), &rows).view(),
+                y.select(Axis(0), &rows).view(),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn predict(&self, x: ArrayView2<f64>) -> Result<FactorialEffects> {
+        let predict = |forest: &CausalForest| {
+            forest
+                .predict_with_level(x, forest.confidence_level)
+                .map(|res| res.predictions)
+        };
+        Ok(FactorialEffects {
+            effect_a: predict(&self.effect_a)?,
+            effect_b: predict(&self.effect_b)?,
+            interaction: predict(&self.a_with_b)? - predict(&self.a_without_b)?,
+        })
+    }
+}
@@ -1,195 +1,2145 @@
+use crate::calibration::{fit_isotonic_calibration, IsotonicMap};
+use crate::diagnostics::{
+    cuped_adjust, fit_report, heterogeneity_gain, post_treatment_suspects, CupedAdjustment,
+    FeatureImbalance, FitReport, HeterogeneityGain,
+};
 use crate::errors::{CausalFlowError, Result};
-use crate::model::CausalModel;
-use ndarray::{Array1, ArrayView1, ArrayView2};
+use crate::linear::LinearCausalModel;
+use crate::model::{
+    validate_binary_treatment, validate_categorical_treatment, validate_sample_weight,
+    validate_training_data, CausalModel,
+};
+use crate::parallel::thread_ceiling;
+use crate::policy::{calibrate_threshold, ThresholdMetric, ThresholdResult};
+use crate::propensity::estimate_propensity;
+use crate::rng::SplitMix64;
+use crate::rules::{mine_rules, EffectRule};
+use crate::stats::{
+    normal_quantile, sorted_quantile, student_t_quantile, validate_confidence_level,
+};
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2, Axis};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
 use rand::thread_rng;
-use rand::Rng;
+use rand::{Rng, RngCore, SeedableRng};
 use rayon::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Instant;
 
-#[derive(Clone)]
+/// Which spread of the per-tree predictions the confidence interval is built from.
+///
+/// `TreeStd` describes how much individual trees disagree about a unit's effect and
+/// suits individual-level statements. `StandardError` divides by `sqrt(n_trees)` and
+/// describes the uncertainty of the averaged forest prediction, which is what
+/// population-level (averaged) inference needs. `TreePercentile` takes the empirical
+/// lower/upper percentiles of the tree predictions directly, so skewed tree
+/// distributions give asymmetric intervals; it ignores `IntervalDistribution`.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum IntervalSpread {
+    TreeStd,
+    StandardError,
+    TreePercentile,
+}
+
+/// Distribution used to turn the confidence level into an interval multiplier.
+///
+/// `StudentT` uses `n_trees - 1` degrees of freedom, widening intervals for small forests.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum IntervalDistribution {
+    Normal,
+    StudentT,
+}
+
+/// What `fit` does when the outcome has zero variance and every effect is trivially zero.
+///
+/// `ZeroEffect` skips tree growing and stores a forest of zero-effect leaves;
+/// `Error` returns `CausalFlowError::Calculation` instead.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConstantOutcome {
+    ZeroEffect,
+    Error,
+}
+
+/// Leaf estimate used when the honest sample of a leaf lacks treated or control units.
+///
+/// `ZeroEffect` reports 0.0, `ParentEffect` falls back to the nearest ancestor with
+/// both arms present, and `NaN` marks the leaf undefined so that predictions routed
+/// through it are NaN rather than silently shrunk toward zero.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum DegenerateLeafPolicy {
+    ZeroEffect,
+    ParentEffect,
+    NaN,
+}
+
+/// Whether treatment is a 0/1 assignment, a dose or one of several arms.
+///
+/// For `Continuous` treatment a leaf's effect is the least-squares slope of the outcome
+/// on the treatment (the effect of one extra unit of dose) instead of the treated minus
+/// control mean. `Categorical(n_arms)` treatment takes integer arms `0..n_arms` with
+/// arm 0 as the baseline: each leaf holds one contrast per other arm (its mean outcome
+/// minus the baseline's), splits maximize the summed squared contrast differences,
+/// and the leaf's single effect is the arm 1 contrast.
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, Default,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TreatmentKind {
+    #[default]
+    Binary,
+    Continuous,
+    Categorical(usize),
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
 pub struct CausalForest {
     pub n_estimators: usize,
     pub max_depth: usize,
     pub min_leaf_size: usize,
+    /// Minimum treated and control units in each child of a split (on the split half).
+    /// `min_leaf_size` alone allows e.g. 9 treated and 1 control; these bound each arm.
+    pub min_leaf_treated: usize,
+    pub min_leaf_control: usize,
+    /// Share of rows each tree draws (without replacement) before its honest split.
+    /// `1.0` gives every tree all rows; smaller values decorrelate trees and leave
+    /// out-of-bag rows on each tree.
+    pub subsample_ratio: f64,
     pub trees: Vec<CausalTree>,
     pub n_features: usize,
+    pub confidence_level: f64,
+    pub interval_spread: IntervalSpread,
+    pub interval_distribution: IntervalDistribution,
+    pub split_strategy: SplitStrategy,
+    /// Relative probability of each feature being sampled as a split candidate.
+    /// `None` samples uniformly.
+    pub feature_sample_weights: Option<Vec<f64>>,
+    /// Un-normalized split-gain importance summed over all trees.
+    pub importance_sum: Vec<f64>,
+    pub constant_outcome: ConstantOutcome,
+    /// Set by `fit` when the training outcome was constant and tree growing was skipped.
+    pub outcome_is_constant: bool,
+    /// Number of threads used to grow trees. `None` uses the global rayon pool; values
+    /// above the `CAUSALFLOW_NUM_THREADS` ceiling are clamped to it.
+    pub n_jobs: Option<usize>,
+    /// Isotonic map applied to predictions and interval bounds, set by `calibrate`.
+    pub calibration: Option<IsotonicMap>,
+    /// Treatment-arm sample sizes from the last `fit`.
+    pub fit_report: Option<FitReport>,
+    /// Exponent on the `nl * nr / n^2` balance term of the split gain. `1.0` is the
+    /// standard criterion, `0.0` ignores balance (pure heterogeneity) and larger values
+    /// penalize unbalanced splits harder.
+    pub gain_balance_penalty: f64,
+    /// Smallest split gain that still creates an internal node; weaker best splits
+    /// become leaves. `0.0` accepts every split.
+    #[serde(default)]
+    pub min_gain: f64,
+    /// Relative stopping tolerance: a node becomes a leaf when its best split gains
+    /// less than `relative_gain_tol * tau^2`, with `tau` the node's own effect on its
+    /// splitting rows. `0.0` disables the check.
+    #[serde(default)]
+    pub relative_gain_tol: f64,
+    pub outcome_transform: OutcomeTransform,
+    /// Control-variate adjustment from the last `fit_cuped`, `None` after a plain `fit`.
+    pub cuped: Option<CupedAdjustment>,
+    pub degenerate_leaf: DegenerateLeafPolicy,
+    /// Column names (e.g. a CSV header) used when reporting importances and rules.
+    pub feature_names: Option<Vec<String>>,
+    /// Standardized arm difference above which `fit` flags a feature as possibly
+    /// post-treatment. `None` disables the check.
+    pub post_treatment_threshold: Option<f64>,
+    /// Features flagged by the last `fit`, most imbalanced first.
+    pub post_treatment_suspects: Vec<FeatureImbalance>,
+    /// Makes training deterministic: tree `i` is grown from a sub-seed derived from
+    /// `seed` and `i`, so results do not depend on rayon scheduling. `None` draws fresh entropy per tree.
+    pub seed: Option<u64>,
+    /// Known sign or range of the effect, applied to predictions and interval bounds as
+    /// a post-hoc prior. `InferenceResult::n_clipped` reports how often it binds.
+    #[serde(default)]
+    pub effect_floor: Option<f64>,
+    #[serde(default)]
+    pub effect_ceil: Option<f64>,
+    /// Arm-based diagnostics (`fit_report`, post-treatment checks, `min_leaf_treated`
+    /// and `min_leaf_control`) only apply to `Binary` treatment.
+    #[serde(default)]
+    pub treatment_kind: TreatmentKind,
+    /// Estimate leaf effects by inverse propensity weighting, with `P(T = 1 | x)` from a
+    /// logistic regression on the training features, to reduce confounding bias in
+    /// observational data. Binary treatment only.
+    #[serde(default)]
+    pub use_propensity: bool,
+    /// Thresholds drawn per sampled feature at each node under `BestRandom`.
+    #[serde(default = "default_split_candidates")]
+    pub n_split_candidates: usize,
+    /// Features sampled as split candidates at each node.
+    #[serde(default)]
+    pub max_features: MaxFeatures,
+}
+
+/// Least-squares slope of `y` on `t` over `indices`, `None` when `t` does not vary.
+pub fn treatment_slope(t: ArrayView1<f64>, y: ArrayView1<f64>, indices: &[usize]) -> Option<f64> {
+    weighted_treatment_slope(t, y, indices, |_| 1.0)
+}
+
+/// Weighted least-squares slope of `y` on `t` over `indices`, with `weight(i)` per row.
+fn weighted_treatment_slope(
+    t: ArrayView1<f64>,
+    y: ArrayView1<f64>,
+    indices: &[usize],
+    weight: impl Fn(usize) -> f64,
+) -> Option<f64> {
+    if indices.len() < 2 {
+        return None;
+    }
+    let n = indices.iter().map(|&i| weight(i)).sum::<f64>();
+    if n <= 0.0 {
+        return None;
+    }
+    let t_mean = indices.iter().map(|&i| weight(i) * t[i]).sum::<f64>() / n;
+    let y_mean = indices.iter().map(|&i| weight(i) * y[i]).sum::<f64>() / n;
+    let (cov, var) = indices.iter().fold((0.0, 0.0), |(cov, var), &i| {
+        let dt = t[i] - t_mean;
+        (
+            cov + weight(i) * dt * (y[i] - y_mean),
+            var + weight(i) * dt * dt,
+        )
+    });
+    (var > 0.0).then(|| cov / var)
+}
+
+/// Mean outcome of each arm `1..n_arms` minus the arm 0 mean over `indices`, with
+/// `weight(i)` per row; a contrast is `None` when either of its arms is empty.
+fn arm_contrasts(
+    t: ArrayView1<f64>,
+    y: ArrayView1<f64>,
+    indices: &[usize],
+    n_arms: usize,
+    weight: impl Fn(usize) -> f64,
+) -> Vec<Option<f64>> {
+    let mut sums = vec![0.0; n_arms];
+    let mut weights = vec![0.0; n_arms];
+    for &i in indices {
+        let arm = t[i] as usize;
+        if arm < n_arms {
+            sums[arm] += weight(i) * y[i];
+            weights[arm] += weight(i);
+        }
+    }
+    let mean = |arm: usize| (weights[arm] > 0.0).then(|| sums[arm] / weights[arm]);
+    (1..n_arms).map(|arm| Some(mean(arm)? - mean(0)?)).collect()
+}
+
+/// Mean predicted effect over the rows whose feature value falls in `[lower, upper]`.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct EffectBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub mean_effect: f64,
+    pub count: usize,
+}
+
+/// Wall-clock timing of repeated `predict` calls on one matrix.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct PredictBenchmark {
+    pub n_rows: usize,
+    pub n_iters: usize,
+    pub mean_latency_ms: f64,
+    pub median_latency_ms: f64,
+    pub p99_latency_ms: f64,
+    /// Rows scored per second at the mean latency.
+    pub rows_per_second: f64,
+}
+
+/// How candidate split thresholds are chosen for each sampled feature.
+///
+/// `BestRandom` evaluates up to `n_split_candidates` thresholds drawn from observed
+/// values and keeps the best. `ExtraRandom` draws a single uniform threshold between
+/// the node's feature bounds (extremely randomized trees), which fits faster and
+/// decorrelates trees. `Quantile(k)` evaluates `k` evenly spaced quantiles of the
+/// node's feature values, a deterministic and more stable search.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitStrategy {
+    BestRandom,
+    ExtraRandom,
+    Quantile(usize),
+}
+
+/// Threshold draws per sampled feature for `SplitStrategy::BestRandom`.
+pub const DEFAULT_SPLIT_CANDIDATES: usize = 10;
+
+fn default_split_candidates() -> usize {
+    DEFAULT_SPLIT_CANDIDATES
 }
 
+/// How many features each split samples from the `n` available (mtry).
+///
+/// `Fraction` takes a share of the features and `Count` a fixed number; every rule
+/// samples at least one feature and never more than `n`.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MaxFeatures {
+    #[default]
+    Sqrt,
+    Log2,
+    Fraction(f64),
+    Count(usize),
+}
+
+impl MaxFeatures {
+    pub fn resolve(&self, n_features: usize) -> usize {
+        let n = n_features as f64;
+        let count = match *self {
+            MaxFeatures::Sqrt => n.sqrt() as usize,
+            MaxFeatures::Log2 => n.log2() as usize,
+            MaxFeatures::Fraction(share) => (share * n) as usize,
+            MaxFeatures::Count(count) => count,
+        };
+        count.clamp(1, n_features.max(1))
+    }
+}
+
+/// Hyperparameters a single tree needs while growing.
+#[derive(Clone, Debug)]
+pub struct TreeParams {
+    pub max_depth: usize,
+    pub min_leaf_size: usize,
+    pub min_leaf_treated: usize,
+    pub min_leaf_control: usize,
+    pub subsample_ratio: f64,
+    pub split_strategy: SplitStrategy,
+    pub n_split_candidates: usize,
+    pub max_features: MaxFeatures,
+    pub feature_sample_weights: Option<Vec<f64>>,
+    pub gain_balance_penalty: f64,
+    pub min_gain: f64,
+    pub relative_gain_tol: f64,
+    pub degenerate_leaf: DegenerateLeafPolicy,
+    pub treatment_kind: TreatmentKind,
+    /// Clipped `P(T = 1 | x)` of every training row when leaf effects are
+    /// propensity-weighted.
+    pub propensity: Option<Array1<f64>>,
+    /// Observation weight of every training row for weighted fits.
+    pub sample_weight: Option<Array1<f64>>,
+}
+
+/// Transform applied to the outcome before fitting.
+///
+/// With `Log`, trees model `ln(y)` and effects are reported back on the original scale
+/// as relative changes, `exp(tau) - 1` (0.10 means +10%). Outcomes must be positive.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum OutcomeTransform {
+    None,
+    Log,
+}
+
+impl OutcomeTransform {
+    pub fn apply(&self, y: ArrayView1<f64>) -> Array1<f64> {
+        match self {
+            OutcomeTransform::None => y.to_owned(),
+            OutcomeTransform::Log => y.mapv(f64::ln),
+        }
+    }
+
+    /// Maps an effect on the transformed scale back to the reported scale.
+    pub fn back_transform(&self, effect: f64) -> f64 {
+        match self {
+            OutcomeTransform::None => effect,
+            OutcomeTransform::Log => effect.exp() - 1.0,
+        }
+    }
+
+    pub fn effect_scale(&self) -> EffectScale {
+        match self {
+            OutcomeTransform::None => EffectScale::Absolute,
+            OutcomeTransform::Log => EffectScale::RelativeChange,
+        }
+    }
+}
+
+/// Units of reported effects: outcome differences, or relative changes for log outcomes.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EffectScale {
+    #[default]
+    Absolute,
+    RelativeChange,
+}
+
+/// Per-unit effects with their intervals, plus derived views of them.
+///
+/// ```
+/// use causalflow_core::forest::{EffectScale, InferenceResult};
+/// use ndarray::array;
+///
+/// let res = InferenceResult {
+///     predictions: array![0.5, -1.0, 2.0],
+///     mean_effect: 0.5,
+///     confidence_intervals: vec![(-0.25, 1.25), (-1.5, -0.5), (1.0, 3.5)],
+///     feature_importance: vec![],
+///     effect_scale: EffectScale::default(),
+///     n_clipped: 0,
+/// };
+/// assert_eq!(res.ci_width(), vec![1.5, 1.0, 2.5]);
+/// assert_eq!(res.significant_mask(), vec![false, true, true]);
+/// assert_eq!(res.effect_range(), (-1.0, 2.0));
+/// ```
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
 pub struct InferenceResult {
     pub predictions: Array1<f64>,
     pub mean_effect: f64,
     pub confidence_intervals: Vec<(f64, f64)>,
     pub feature_importance: Vec<f64>,
+    #[serde(default)]
+    pub effect_scale: EffectScale,
+    /// Predictions moved by the model's effect floor/ceiling.
+    #[serde(default)]
+    pub n_clipped: usize,
+}
+
+impl InferenceResult {
+    /// Width of each unit's confidence interval.
+    pub fn ci_width(&self) -> Vec<f64> {
+        self.confidence_intervals
+            .iter()
+            .map(|&(lower, upper)| upper - lower)
+            .collect()
+    }
+
+    /// Whether each unit's confidence interval excludes zero.
+    pub fn significant_mask(&self) -> Vec<bool> {
+        self.confidence_intervals
+            .iter()
+            .map(|&(lower, upper)| lower > 0.0 || upper < 0.0)
+            .collect()
+    }
+
+    /// Smallest and largest predicted effect, or `(NaN, NaN)` when there are no predictions.
+    pub fn effect_range(&self) -> (f64, f64) {
+        if self.predictions.is_empty() {
+            return (f64::NAN, f64::NAN);
+        }
+        self.predictions
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &p| {
+                (lo.min(p), hi.max(p))
+            })
+    }
+}
+
+/// Unadjusted vs model-adjusted ATE, showing how much confounding the model corrected for.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct AteComparison {
+    pub naive_ate: f64,
+    pub adjusted_ate: f64,
+    pub adjustment: f64,
+}
+
+impl AteComparison {
+    pub fn new(naive_ate: f64, adjusted_ate: f64) -> Self {
+        Self {
+            naive_ate,
+            adjusted_ate,
+            adjustment: adjusted_ate - naive_ate,
+        }
+    }
 }
 
-#[derive(Clone)]
+/// Raw difference of outcome means between treated and control units.
+pub fn difference_in_means(t: ArrayView1<f64>, y: ArrayView1<f64>) -> f64 {
+    let mut y1_sum = 0.0;
+    let mut y1_count = 0;
+    let mut y0_sum = 0.0;
+    let mut y0_count = 0;
+
+    for (&ti, &yi) in t.iter().zip(y.iter()) {
+        if ti > 0.5 {
+            y1_sum += yi;
+            y1_count += 1;
+        } else {
+            y0_sum += yi;
+            y0_count += 1;
+        }
+    }
+
+    if y1_count > 0 && y0_count > 0 {
+        (y1_sum / y1_count as f64) - (y0_sum / y0_count as f64)
+    } else {
+        0.0
+    }
+}
+
+/// Groups `effects` into up to `n_bins` quantile bins of `values`.
+fn effect_bins(
+    values: ArrayView1<f64>,
+    effects: ArrayView1<f64>,
+    n_bins: usize,
+) -> Result<Vec<EffectBin>> {
+    if n_bins == 0 {
+        return Err(CausalFlowError::InvalidParameter(
+            "n_bins must be at least 1".to_string(),
+        ));
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mut edges: Vec<f64> = (1..n_bins)
+        .map(|k| sorted_quantile(&sorted, k as f64 / n_bins as f64))
+        .collect();
+    edges.dedup();
+
+    let mut bins: Vec<Option<EffectBin>> = vec![None; edges.len() + 1];
+    for (&v, &effect) in values.iter().zip(effects.iter()) {
+        let slot = &mut bins[edges.partition_point(|&e| e < v)];
+        let bin = slot.get_or_insert(EffectBin {
+            lower: v,
+            upper: v,
+            mean_effect: 0.0,
+            count: 0,
+        });
+        bin.lower = bin.lower.min(v);
+        bin.upper = bin.upper.max(v);
+        bin.mean_effect += effect;
+        bin.count += 1;
+    }
+    Ok(bins
+        .into_iter()
+        .flatten()
+        .map(|mut bin| {
+            bin.mean_effect /= bin.count as f64;
+            bin
+        })
+        .collect())
+}
+
+/// Winning split of a node: the feature/threshold and the resulting index partition.
+struct NodeSplit {
+    gain: f64,
+    feature_idx: usize,
+    threshold: f64,
+    left: Vec<usize>,
+    right: Vec<usize>,
+}
+
+/// Serialized as a flat node list (see `SerializedTree`) so deep trees do not hit
+/// serializer recursion limits.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(
+    into = "crate::persist::SerializedTree",
+    try_from = "crate::persist::SerializedTree"
+)]
 pub struct CausalTree {
     pub root: Option<Box<Node>>,
     pub feature_importance: Vec<f64>,
+    /// Training rows this tree did not sample, in ascending order.
+    pub oob_indices: Vec<usize>,
 }
 
-#[derive(Clone)]
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
 pub enum Node {
     Leaf {
         treatment_effect: f64,
         size: usize,
+        /// Contrast of each non-baseline arm for `Categorical` treatment, else empty.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        arm_effects: Vec<f64>,
     },
     Internal {
         feature_idx: usize,
         threshold: f64,
         left: Box<Node>,
         right: Box<Node>,
+        /// Where rows with a missing (NaN) split feature go: the child that received
+        /// more estimation rows while growing. Trees saved without it send them right.
+        #[serde(default)]
+        default_left: bool,
     },
 }
 
-impl CausalForest {
-    pub fn new(n_estimators: usize, max_depth: usize, min_leaf_size: usize) -> Self {
+/// Named hyperparameters for a `CausalForest`; every other setting starts at the
+/// same defaults as `CausalForest::new`.
+///
+/// ```
+/// use causalflow_core::forest::{CausalForestConfig, MaxFeatures};
+///
+/// let forest = CausalForestConfig::default()
+///     .n_estimators(50)
+///     .max_depth(4)
+///     .min_leaf_size(10)
+///     .max_features(MaxFeatures::Log2)
+///     .seed(7)
+///     .subsample_ratio(0.5)
+///     .build();
+/// assert_eq!((forest.n_estimators, forest.max_depth, forest.min_leaf_size), (50, 4, 10));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CausalForestConfig {
+    pub n_estimators: usize,
+    pub max_depth: usize,
+    pub min_leaf_size: usize,
+    pub max_features: MaxFeatures,
+    pub seed: Option<u64>,
+    pub subsample_ratio: f64,
+}
+
+impl Default for CausalForestConfig {
+    fn default() -> Self {
         Self {
-            n_estimators,
-            max_depth,
-            min_leaf_size,
+            n_estimators: 10,
+            max_depth: 5,
+            min_leaf_size: 5,
+            max_features: MaxFeatures::Sqrt,
+            seed: None,
+            subsample_ratio: 1.0,
+        }
+    }
+}
+
+impl CausalForestConfig {
+    pub fn n_estimators(mut self, n_estimators: usize) -> Self {
+        self.n_estimators = n_estimators;
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn min_leaf_size(mut self, min_leaf_size: usize) -> Self {
+        self.min_leaf_size = min_leaf_size;
+        self
+    }
+
+    pub fn max_features(mut self, max_features: MaxFeatures) -> Self {
+        self.max_features = max_features;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn subsample_ratio(mut self, subsample_ratio: f64) -> Self {
+        self.subsample_ratio = subsample_ratio;
+        self
+    }
+
+    /// An unfitted forest; the hyperparameters are validated when it is fit.
+    pub fn build(self) -> CausalForest {
+        CausalForest {
+            n_estimators: self.n_estimators,
+            max_depth: self.max_depth,
+            min_leaf_size: self.min_leaf_size,
+            min_leaf_treated: 0,
+            min_leaf_control: 0,
+            subsample_ratio: self.subsample_ratio,
             trees: Vec::new(),
             n_features: 0,
+            confidence_level: 0.95,
+            interval_spread: IntervalSpread::TreeStd,
+            interval_distribution: IntervalDistribution::Normal,
+            split_strategy: SplitStrategy::BestRandom,
+            feature_sample_weights: None,
+            importance_sum: Vec::new(),
+            constant_outcome: ConstantOutcome::ZeroEffect,
+            outcome_is_constant: false,
+            n_jobs: None,
+            calibration: None,
+            fit_report: None,
+            gain_balance_penalty: 1.0,
+            min_gain: 0.0,
+            relative_gain_tol: 0.0,
+            outcome_transform: OutcomeTransform::None,
+            cuped: None,
+            degenerate_leaf: DegenerateLeafPolicy::ZeroEffect,
+            feature_names: None,
+            post_treatment_threshold: Some(0.5),
+            post_treatment_suspects: Vec::new(),
+            seed: self.seed,
+            effect_floor: None,
+            effect_ceil: None,
+            treatment_kind: TreatmentKind::Binary,
+            use_propensity: false,
+            n_split_candidates: DEFAULT_SPLIT_CANDIDATES,
+            max_features: self.max_features,
+        }
+    }
+}
+
+impl CausalForest {
+    /// Positional shorthand for `CausalForestConfig`; prefer the config's named
+    /// setters when more than these three settings change.
+    pub fn new(n_estimators: usize, max_depth: usize, min_leaf_size: usize) -> Self {
+        CausalForestConfig::default()
+            .n_estimators(n_estimators)
+            .max_depth(max_depth)
+            .min_leaf_size(min_leaf_size)
+            .build()
+    }
+
+    /// Like `new`, with training made reproducible by `seed`.
+    pub fn with_seed(
+        n_estimators: usize,
+        max_depth: usize,
+        min_leaf_size: usize,
+        seed: u64,
+    ) -> Self {
+        Self {
+            seed: Some(seed),
+            ..Self::new(n_estimators, max_depth, min_leaf_size)
+        }
+    }
+
+    /// Like `new`, with the split search configured: `split_strategy` picks how
+    /// thresholds are chosen and `n_split_candidates` how many random draws
+    /// `BestRandom` evaluates per feature.
+    pub fn with_split_search(
+        n_estimators: usize,
+        max_depth: usize,
+        min_leaf_size: usize,
+        split_strategy: SplitStrategy,
+        n_split_candidates: usize,
+    ) -> Self {
+        Self {
+            split_strategy,
+            n_split_candidates,
+            ..Self::new(n_estimators, max_depth, min_leaf_size)
+        }
+    }
+
+    /// Like `new`, sampling `max_features` candidate features at each split.
+    pub fn with_max_features(
+        n_estimators: usize,
+        max_depth: usize,
+        min_leaf_size: usize,
+        max_features: MaxFeatures,
+    ) -> Self {
+        Self {
+            max_features,
+            ..Self::new(n_estimators, max_depth, min_leaf_size)
+        }
+    }
+
+    /// Configured feature names, or `Feature i` placeholders when none were given.
+    pub fn feature_labels(&self) -> Vec<String> {
+        match &self.feature_names {
+            Some(names) => names.clone(),
+            None => (0..self.n_features)
+                .map(|i| format!("Feature {}", i))
+                .collect(),
         }
     }
 
     fn validate_data(&self, x: ArrayView2<f64>, t: ArrayView1<f64>, y: ArrayView1<f64>) -> Result<()> {
-        if x.is_empty() {
+        validate_training_data(x, t, y)?;
+        match self.treatment_kind {
+            TreatmentKind::Binary => validate_binary_treatment(t)?,
+            TreatmentKind::Categorical(n_arms) => validate_categorical_treatment(t, n_arms)?,
+            TreatmentKind::Continuous => {}
+        }
+
+        if let Some(names) = &self.feature_names {
+            if names.len() != x.ncols() {
+                return Err(CausalFlowError::InvalidParameter(format!(
+                    "feature_names has {} entries but x has {} columns",
+                    names.len(),
+                    x.ncols()
+                )));
+            }
+        }
+
+        if let Some(threshold) = self.post_treatment_threshold {
+            if !threshold.is_finite() || threshold <= 0.0 {
+                return Err(CausalFlowError::InvalidParameter(format!(
+                    "post_treatment_threshold must be finite and positive, got {}",
+                    threshold
+                )));
+            }
+        }
+
+        if let (Some(floor), Some(ceil)) = (self.effect_floor, self.effect_ceil) {
+            if floor > ceil {
+                return Err(CausalFlowError::InvalidParameter(format!(
+                    "effect_floor ({}) must not exceed effect_ceil ({})",
+                    floor, ceil
+                )));
+            }
+        }
+
+        if self.use_propensity && self.treatment_kind != TreatmentKind::Binary {
+            return Err(CausalFlowError::InvalidParameter(
+                "use_propensity requires a binary treatment".to_string(),
+            ));
+        }
+
+        if self.n_split_candidates == 0 || self.split_strategy == SplitStrategy::Quantile(0) {
+            return Err(CausalFlowError::InvalidParameter(
+                "the split search needs at least one candidate threshold".to_string(),
+            ));
+        }
+
+        match self.max_features {
+            MaxFeatures::Fraction(share) if !(share > 0.0 && share <= 1.0) => {
+                return Err(CausalFlowError::InvalidParameter(format!(
+                    "max_features fraction must be in (0, 1], got {}",
+                    share
+                )));
+            }
+            MaxFeatures::Count(0) => {
+                return Err(CausalFlowError::InvalidParameter(
+                    "max_features must sample at least one feature".to_string(),
+                ));
+            }
+            _ => {}
+        }
+
+        if !(self.subsample_ratio > 0.0 && self.subsample_ratio <= 1.0) {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "subsample_ratio must be in (0, 1], got {}",
+                self.subsample_ratio
+            )));
+        }
+
+        if !self.min_gain.is_finite() || self.min_gain < 0.0 {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "min_gain must be finite and non-negative, got {}",
+                self.min_gain
+            )));
+        }
+
+        if !self.relative_gain_tol.is_finite() || self.relative_gain_tol < 0.0 {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "relative_gain_tol must be finite and non-negative, got {}",
+                self.relative_gain_tol
+            )));
+        }
+
+        if !self.gain_balance_penalty.is_finite() || self.gain_balance_penalty < 0.0 {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "gain_balance_penalty must be finite and non-negative, got {}",
+                self.gain_balance_penalty
+            )));
+        }
+
+        if self.outcome_transform == OutcomeTransform::Log && y.iter().any(|&v| v <= 0.0) {
+            return Err(CausalFlowError::InvalidParameter(
+                "outcome_transform='log' requires strictly positive outcomes".to_string(),
+            ));
+        }
+
+        if let Some(weights) = &self.feature_sample_weights {
+            if weights.len() != x.ncols() {
+                return Err(CausalFlowError::InvalidParameter(format!(
+                    "feature_sample_weights has {} entries but data has {} features",
+                    weights.len(),
+                    x.ncols()
+                )));
+            }
+            if weights.iter().any(|&w| !w.is_finite() || w < 0.0) {
+                return Err(CausalFlowError::InvalidParameter(
+                    "feature_sample_weights must be finite and non-negative".to_string(),
+                ));
+            }
+            if weights.iter().all(|&w| w == 0.0) {
+                return Err(CausalFlowError::InvalidParameter(
+                    "feature_sample_weights must contain at least one positive weight".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn fit(
+        &mut self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+    ) {
+        let _ = self.fit_result(x, t, y);
+    }
+
+    pub fn fit_result(
+        &mut self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+    ) -> Result<()> {
+        self.fit_with_weights(x, t, y, None)
+    }
+
+    /// Fits with a non-negative observation weight per row: leaf effects become
+    /// weighted arm means (or weighted slopes) and split gains use weighted child
+    /// sizes. The honest split/estimation partition is unchanged.
+    pub fn fit_weighted(
+        &mut self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+        sample_weight: ArrayView1<f64>,
+    ) -> Result<()> {
+        validate_sample_weight(sample_weight, x.nrows())?;
+        self.fit_with_weights(x, t, y, Some(sample_weight))
+    }
+
+    fn fit_with_weights(
+        &mut self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+        sample_weight: Option<ArrayView1<f64>>,
+    ) -> Result<()> {
+        self.validate_data(x, t, y)?;
+
+        self.cuped = None;
+        self.n_features = x.ncols();
+        self.importance_sum = vec![0.0; self.n_features];
+        self.calibration = None;
+        let binary = self.treatment_kind == TreatmentKind::Binary;
+        self.fit_report =
+            binary.then(|| fit_report(t, sample_weight, self.min_leaf_size, self.n_estimators));
+        self.post_treatment_suspects = match self.post_treatment_threshold {
+            Some(threshold) if binary => post_treatment_suspects(x, t, sample_weight, threshold),
+            _ => Vec::new(),
+        };
+        let y = self.outcome_transform.apply(y);
+        let y = y.view();
+        self.outcome_is_constant = y.iter().all(|&v| v == y[0]);
+        if self.outcome_is_constant {
+            if self.constant_outcome == ConstantOutcome::Error {
+                self.trees.clear();
+                return Err(CausalFlowError::Calculation(
+                    "outcome is constant, so every treatment effect is zero".to_string(),
+                ));
+            }
+            let tree = CausalTree::constant(self.n_features, 0.0, y.len());
+            self.trees = vec![tree; self.n_estimators];
+            return Ok(());
+        }
+
+        self.trees = self.grow_trees(x, t, y, sample_weight, 0, self.n_estimators)?;
+        for tree in &self.trees {
+            for (i, &imp) in tree.feature_importance.iter().enumerate() {
+                self.importance_sum[i] += imp;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fits on a CUPED-adjusted outcome: a pre-treatment covariate correlated with `y`
+    /// is regressed out first, which lowers outcome variance (and so effect variance)
+    /// without biasing effects. Returns the adjustment, including the variance reduction.
+    pub fn fit_cuped(
+        &mut self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+        pre_covariate: ArrayView1<f64>,
+    ) -> Result<CupedAdjustment> {
+        let (adjusted, adjustment) = cuped_adjust(y, pre_covariate)?;
+        self.fit_result(x, t, adjusted.view())?;
+        self.cuped = Some(adjustment.clone());
+        Ok(adjustment)
+    }
+
+    /// Fits on `y - baseline_predictions`, so the forest only explains what an external
+    /// outcome model leaves unexplained. A baseline that does not depend on treatment
+    /// leaves effects unbiased while removing outcome variance.
+    pub fn fit_on_residuals(
+        &mut self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+        baseline_predictions: ArrayView1<f64>,
+    ) -> Result<()> {
+        if baseline_predictions.len() != y.len() {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "baseline_predictions has {} entries but y has {}",
+                baseline_predictions.len(),
+                y.len()
+            )));
+        }
+        if self.outcome_transform == OutcomeTransform::Log {
+            return Err(CausalFlowError::InvalidParameter(
+                "fit_on_residuals does not support outcome_transform='log'".to_string(),
+            ));
+        }
+        let residuals = &y - &baseline_predictions;
+        self.fit_result(x, t, residuals.view())
+    }
+
+    /// Grows `n_new` additional trees on `x` and appends them to a fitted forest. Pass
+    /// the `sample_weight` of the original fit, if any, so the new trees estimate the
+    /// same weighted effects.
+    ///
+    /// The running importance sum is only updated with the new trees, so repeated
+    /// growth costs O(new trees). The calibration map was fit to the old ensemble's
+    /// predictions, so it is cleared.
+    pub fn add_trees(
+        &mut self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+        sample_weight: Option<ArrayView1<f64>>,
+        n_new: usize,
+    ) -> Result<()> {
+        if self.trees.is_empty() {
+            return Err(CausalFlowError::ModelNotFitted);
+        }
+        self.check_feature_count(x)?;
+        self.validate_data(x, t, y)?;
+        if let Some(w) = sample_weight {
+            validate_sample_weight(w, x.nrows())?;
+        }
+
+        let y = self.outcome_transform.apply(y);
+        let new_trees = self.grow_trees(x, t, y.view(), sample_weight, self.trees.len(), n_new)?;
+        for tree in &new_trees {
+            for (i, &imp) in tree.feature_importance.iter().enumerate() {
+                self.importance_sum[i] += imp;
+            }
+        }
+        self.trees.extend(new_trees);
+        self.n_estimators = self.trees.len();
+        self.calibration = None;
+
+        Ok(())
+    }
+
+    /// Grows trees `first_tree..first_tree + n_trees`; the index picks each tree's
+    /// sub-seed, so trees added later do not repeat the streams of earlier ones.
+    fn grow_trees(
+        &self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+        sample_weight: Option<ArrayView1<f64>>,
+        first_tree: usize,
+        n_trees: usize,
+    ) -> Result<Vec<CausalTree>> {
+        let n_features = x.ncols();
+        let mut params = self.tree_params();
+        if self.use_propensity {
+            params.propensity = Some(estimate_propensity(x, t)?);
+        }
+        params.sample_weight = sample_weight.map(|w| w.to_owned());
+        // Mix the seed before combining it with the tree index: a plain `seed ^ idx`
+        // gives neighbouring seeds (e.g. 6 and 7) the same set of sub-seeds
+        let mixed = self
+            .seed
+            .map(|seed| SplitMix64::seed_from_u64(seed).next_u64());
+        let seeds: Vec<u64> = (first_tree..first_tree + n_trees)
+            .map(|idx| match mixed {
+                Some(mixed) => mixed ^ idx as u64,
+                None => thread_rng().gen(),
+            })
+            .collect();
+        let grow = || {
+            seeds
+                .par_iter()
+                .map(|&seed| {
+                    let mut tree = CausalTree::new(n_features);
+                    tree.fit(x, t, y, &params, seed);
+                    tree
+                })
+                .collect()
+        };
+
+        match self.n_jobs {
+            None => Ok(grow()),
+            Some(0) => Err(CausalFlowError::InvalidParameter(
+                "n_jobs must be at least 1".to_string(),
+            )),
+            Some(n_jobs) => {
+                let n_jobs = thread_ceiling().map_or(n_jobs, |ceiling| n_jobs.min(ceiling));
+                let pool = rayon::ThreadPoolBuilder::new()
+                    .num_threads(n_jobs)
+                    .build()
+                    .map_err(|e| CausalFlowError::Internal(e.to_string()))?;
+                Ok(pool.install(grow))
+            }
+        }
+    }
+
+    /// Refits the forest's configuration on `n_boot` bootstrap resamples of the data
+    /// and returns the mean and standard deviation of each feature's normalized
+    /// importance. `seed` fixes the resampled rows; resampled rows keep their
+    /// `sample_weight`, if any.
+    pub fn importance_bootstrap(
+        &self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+        sample_weight: Option<ArrayView1<f64>>,
+        n_boot: usize,
+        seed: Option<u64>,
+    ) -> Result<(Vec<f64>, Vec<f64>)> {
+        if n_boot == 0 {
+            return Err(CausalFlowError::InvalidParameter(
+                "n_boot must be at least 1".to_string(),
+            ));
+        }
+        let n = x.nrows();
+        if n == 0 {
+            return Err(CausalFlowError::EmptyData);
+        }
+
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let mut replicates = Array2::zeros((n_boot, x.ncols()));
+        for b in 0..n_boot {
+            let rows: Vec<usize> = (0..n).map(|_| rng.gen_range(0..n)).collect();
+            let mut forest = self.clone();
+            let (x_b, t_b, y_b) = (
+                x.select(Axis(0), &rows),
+                t.select(Axis(0), &rows),
+                y.select(Axis(0), &rows),
+            );
+            match sample_weight {
+                Some(w) => forest.fit_weighted(
+                    x_b.view(),
+                    t_b.view(),
+                    y_b.view(),
+                    w.select(Axis(0), &rows).view(),
+                )?,
+                None => forest.fit_result(x_b.view(), t_b.view(), y_b.view())?,
+            }
+            replicates
+                .row_mut(b)
+                .assign(&Array1::from(forest.feature_importance()));
+        }
+
+        let mean = replicates.mean_axis(Axis(0)).unwrap().to_vec();
+        let std = if n_boot > 1 {
+            replicates.std_axis(Axis(0), 1.0).to_vec()
+        } else {
+            vec![0.0; x.ncols()]
+        };
+        Ok((mean, std))
+    }
+
+    /// Permutation importance: how much the transformed-outcome error of the CATEs on
+    /// `(x, t, y)` grows when one feature column is shuffled, as the mean and standard
+    /// deviation over `n_repeats` shuffles. Unlike split gain it is not biased toward
+    /// high-cardinality features; pass held-out data, as in-sample scores are
+    /// optimistic. `seed` fixes the shuffles.
+    pub fn permutation_importance(
+        &self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+        n_repeats: usize,
+        seed: Option<u64>,
+    ) -> Result<(Vec<f64>, Vec<f64>)> {
+        if n_repeats == 0 {
+            return Err(CausalFlowError::InvalidParameter(
+                "n_repeats must be at least 1".to_string(),
+            ));
+        }
+        if self.trees.is_empty() {
+            return Err(CausalFlowError::ModelNotFitted);
+        }
+        self.check_feature_count(x)?;
+        if self.treatment_kind != TreatmentKind::Binary {
+            return Err(CausalFlowError::InvalidParameter(
+                "permutation importance requires a binary treatment".to_string(),
+            ));
+        }
+        let y = self.outcome_transform.apply(y);
+        let score = |x: ArrayView2<f64>| {
+            let cate = self.tree_predictions(x).sum_axis(Axis(1)) / self.trees.len() as f64;
+            heterogeneity_gain(cate.view(), 0.0, t, y.view()).map(|g| g.mse_heterogeneous)
+        };
+        let baseline = score(x)?;
+
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let mut increases = Array2::zeros((n_repeats, x.ncols()));
+        let mut shuffled = x.to_owned();
+        let mut order: Vec<usize> = (0..x.nrows()).collect();
+        for j in 0..x.ncols() {
+            for r in 0..n_repeats {
+                order.shuffle(&mut rng);
+                shuffled
+                    .column_mut(j)
+                    .assign(&x.column(j).select(Axis(0), &order));
+                increases[[r, j]] = score(shuffled.view())? - baseline;
+            }
+            shuffled.column_mut(j).assign(&x.column(j));
+        }
+
+        let mean = increases.mean_axis(Axis(0)).unwrap().to_vec();
+        let std = if n_repeats > 1 {
+            increases.std_axis(Axis(0), 1.0).to_vec()
+        } else {
+            vec![0.0; x.ncols()]
+        };
+        Ok((mean, std))
+    }
+
+    /// Content hash of everything that affects `predict`: tree structures, interval
+    /// settings, effect bounds and calibration. Forests that predict identically share a fingerprint;
+    /// values are stable within a build, not across Rust versions.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.n_features.hash(&mut hasher);
+        self.confidence_level.to_bits().hash(&mut hasher);
+        self.interval_spread.hash(&mut hasher);
+        self.interval_distribution.hash(&mut hasher);
+        self.outcome_transform.hash(&mut hasher);
+        self.treatment_kind.hash(&mut hasher);
+        self.effect_floor.map(f64::to_bits).hash(&mut hasher);
+        self.effect_ceil.map(f64::to_bits).hash(&mut hasher);
+        if let Some(map) = &self.calibration {
+            for (k, v) in map.knots.iter().zip(&map.values) {
+                k.to_bits().hash(&mut hasher);
+                v.to_bits().hash(&mut hasher);
+            }
+        }
+        self.trees.len().hash(&mut hasher);
+        for tree in &self.trees {
+            if let Some(root) = &tree.root {
+                root.hash_structure(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
+    /// The `top_k` highest-effect leaf rules across all trees.
+    pub fn mine_rules(&self, top_k: usize) -> Result<Vec<EffectRule>> {
+        if self.trees.is_empty() {
+            return Err(CausalFlowError::ModelNotFitted);
+        }
+        Ok(mine_rules(&self.trees, self.n_features, top_k))
+    }
+
+    /// Split-gain importance normalized to sum to one.
+    pub fn feature_importance(&self) -> Vec<f64> {
+        let sum: f64 = self.importance_sum.iter().sum();
+        if sum > 0.0 {
+            self.importance_sum.iter().map(|imp| imp / sum).collect()
+        } else {
+            self.importance_sum.clone()
+        }
+    }
+
+    /// The `k` most important features as `(feature_idx, importance)`, highest first.
+    pub fn top_features(&self, k: usize) -> Vec<(usize, f64)> {
+        let mut ranked: Vec<(usize, f64)> =
+            self.feature_importance().into_iter().enumerate().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked.truncate(k);
+        ranked
+    }
+
+    pub fn fit_placebo(
+        &mut self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+    ) {
+        let _ = self.fit_placebo_result(x, t, y);
+    }
+
+    pub fn fit_placebo_result(
+        &mut self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+    ) -> Result<()> {
+        let mut t_shuffled = t.to_owned();
+        let mut rng = thread_rng();
+        let mut indices: Vec<usize> = (0..t.len()).collect();
+        indices.shuffle(&mut rng);
+        
+        // Reorder t_shuffled based on shuffled indices
+        let t_orig = t.to_owned();
+        for (i, &idx) in indices.iter().enumerate() {
+            t_shuffled[i] = t_orig[idx];
+        }
+
+        self.fit_result(x, t_shuffled.view(), y)
+    }
+
+    pub fn predict(&self, x: ArrayView2<f64>) -> InferenceResult {
+        self.predict_result(x).unwrap_or_else(|_| InferenceResult {
+            predictions: Array1::zeros(x.nrows()),
+            mean_effect: 0.0,
+            confidence_intervals: vec![(0.0, 0.0); x.nrows()],
+            feature_importance: vec![0.0; self.n_features],
+            effect_scale: self.outcome_transform.effect_scale(),
+            n_clipped: 0,
+        })
+    }
+
+    pub fn predict_result(&self, x: ArrayView2<f64>) -> Result<InferenceResult> {
+        self.predict_with_level(x, self.confidence_level)
+    }
+
+    pub fn predict_with_level(
+        &self,
+        x: ArrayView2<f64>,
+        confidence_level: f64,
+    ) -> Result<InferenceResult> {
+        let (predictions, mut intervals, n_clipped) =
+            self.predict_levels(x, &[confidence_level])?;
+        let mean_effect = predictions.mean().unwrap_or(0.0);
+        let confidence_intervals = intervals.pop().expect("one interval set per level");
+        let feature_importance = self.feature_importance();
+
+        Ok(InferenceResult {
+            predictions,
+            mean_effect,
+            confidence_intervals,
+            feature_importance,
+            effect_scale: self.outcome_transform.effect_scale(),
+            n_clipped,
+        })
+    }
+
+    /// Predictions plus intervals at every level in `levels` (e.g. 0.9 and 0.95 for a
+    /// fan chart). Each row's tree spread or sorted tree predictions are computed once
+    /// and reused for all levels; `intervals[k]` holds the per-row bounds at `levels[k]`.
+    #[allow(clippy::type_complexity)]
+    pub fn predict_multi_ci(
+        &self,
+        x: ArrayView2<f64>,
+        levels: &[f64],
+    ) -> Result<(Array1<f64>, Vec<Vec<(f64, f64)>>)> {
+        let (predictions, intervals, _) = self.predict_levels(x, levels)?;
+        Ok((predictions, intervals))
+    }
+
+    /// `predict_multi_ci` plus the number of predictions moved by the effect bounds.
+    #[allow(clippy::type_complexity)]
+    fn predict_levels(
+        &self,
+        x: ArrayView2<f64>,
+        levels: &[f64],
+    ) -> Result<(Array1<f64>, Vec<Vec<(f64, f64)>>, usize)> {
+        for &level in levels {
+            validate_confidence_level(level)?;
+        }
+        if self.trees.is_empty() {
+            return Err(CausalFlowError::ModelNotFitted);
+        }
+        self.check_feature_count(x)?;
+
+        let n_samples = x.nrows();
+        if n_samples == 0 {
+            return Err(CausalFlowError::EmptyData);
+        }
+
+        let tree_preds = self.tree_predictions(x);
+        let raw_predictions = tree_preds.sum_axis(Axis(1)) / self.trees.len() as f64;
+        let (predictions, n_clipped) = self.report_predictions(&raw_predictions);
+
+        let multipliers: Vec<f64> = levels
+            .iter()
+            .map(|&level| self.interval_multiplier(level))
+            .collect();
+        let mut intervals = vec![Vec::with_capacity(n_samples); levels.len()];
+        for (&p, row) in raw_predictions.iter().zip(tree_preds.outer_iter()) {
+            self.push_row_intervals(p, &row.to_vec(), levels, &multipliers, &mut intervals);
+        }
+
+        Ok((predictions, intervals, n_clipped))
+    }
+
+    /// Calibrates fitted-scale effects, reports them on the original outcome scale and
+    /// applies the effect bounds, counting the predictions the bounds moved.
+    fn report_predictions(&self, raw: &Array1<f64>) -> (Array1<f64>, usize) {
+        let unclipped = raw.mapv(|v| self.report_effect(v));
+        let n_clipped = unclipped
+            .iter()
+            .filter(|&&v| !v.is_nan() && self.clip_effect(v) != v)
+            .count();
+        (unclipped.mapv(|v| self.clip_effect(v)), n_clipped)
+    }
+
+    fn report_effect(&self, v: f64) -> f64 {
+        self.outcome_transform
+            .back_transform(self.calibrated_effect(v))
+    }
+
+    /// Passes a fitted-scale effect through the calibration map, if any.
+    fn calibrated_effect(&self, v: f64) -> f64 {
+        match &self.calibration {
+            Some(map) => map.apply(v),
+            None => v,
+        }
+    }
+
+    /// Appends one row's bounds at every level, from the raw mean `p` and the raw
+    /// predictions of the trees that scored the row.
+    fn push_row_intervals(
+        &self,
+        p: f64,
+        tree_values: &[f64],
+        levels: &[f64],
+        multipliers: &[f64],
+        intervals: &mut [Vec<(f64, f64)>],
+    ) {
+        let calibrate = |v: f64| self.clip_effect(self.report_effect(v));
+        if self.interval_spread == IntervalSpread::TreePercentile {
+            let mut sorted = tree_values.to_vec();
+            sorted.sort_by(|a, b| a.total_cmp(b));
+            for (bounds, &level) in intervals.iter_mut().zip(levels) {
+                let alpha = 1.0 - level;
+                bounds.push((
+                    calibrate(sorted_quantile(&sorted, alpha / 2.0)),
+                    calibrate(sorted_quantile(&sorted, 1.0 - alpha / 2.0)),
+                ));
+            }
+            return;
+        }
+        // Sample variance of the tree predictions around their mean
+        let n_trees = tree_values.len() as f64;
+        let var = if n_trees > 1.0 {
+            tree_values.iter().map(|v| (v - p).powi(2)).sum::<f64>() / (n_trees - 1.0)
+        } else {
+            0.0
+        };
+        let spread = match self.interval_spread {
+            IntervalSpread::StandardError => (var / n_trees).sqrt(),
+            _ => var.sqrt(),
+        };
+        for (bounds, &multiplier) in intervals.iter_mut().zip(multipliers) {
+            bounds.push((
+                calibrate(p - multiplier * spread),
+                calibrate(p + multiplier * spread),
+            ));
+        }
+    }
+
+    /// Out-of-bag CATEs for the training matrix `x` (rows in training order): each row
+    /// averages only the trees that did not sample it, an honest in-sample estimate that
+    /// needs no holdout set. Rows every tree sampled are NaN and left out of
+    /// `mean_effect`; fit with `subsample_ratio` below 1.0 to leave rows out-of-bag.
+    pub fn predict_oob(&self, x: ArrayView2<f64>) -> Result<InferenceResult> {
+        self.predict_oob_with_level(x, self.confidence_level)
+    }
+
+    pub fn predict_oob_with_level(
+        &self,
+        x: ArrayView2<f64>,
+        confidence_level: f64,
+    ) -> Result<InferenceResult> {
+        validate_confidence_level(confidence_level)?;
+        if self.trees.is_empty() {
+            return Err(CausalFlowError::ModelNotFitted);
+        }
+        self.check_feature_count(x)?;
+        let n_samples = x.nrows();
+        if n_samples == 0 {
+            return Err(CausalFlowError::EmptyData);
+        }
+
+        let mut row_trees: Vec<Vec<usize>> = vec![Vec::new(); n_samples];
+        for (j, tree) in self.trees.iter().enumerate() {
+            for &i in &tree.oob_indices {
+                row_trees
+                    .get_mut(i)
+                    .ok_or_else(|| {
+                        CausalFlowError::InvalidParameter(format!(
+                            "x has {} rows but the forest was trained on more; pass the training matrix",
+                            n_samples
+                        ))
+                    })?
+                    .push(j);
+            }
+        }
+        if row_trees.iter().all(|trees| trees.is_empty()) {
+            return Err(CausalFlowError::InvalidParameter(
+                "no row is out-of-bag for any tree; fit with subsample_ratio below 1.0".to_string(),
+            ));
+        }
+
+        let levels = [confidence_level];
+        let multipliers = [self.interval_multiplier(confidence_level)];
+        let mut intervals = vec![Vec::with_capacity(n_samples)];
+        let mut raw = Array1::from_elem(n_samples, f64::NAN);
+        for (i, trees) in row_trees.iter().enumerate() {
+            if trees.is_empty() {
+                intervals[0].push((f64::NAN, f64::NAN));
+                continue;
+            }
+            let values: Vec<f64> = trees
+                .iter()
+                .filter_map(|&j| self.trees[j].root.as_ref())
+                .map(|root| root.leaf(x.row(i)).0)
+                .collect();
+            raw[i] = values.iter().sum::<f64>() / values.len() as f64;
+            self.push_row_intervals(raw[i], &values, &levels, &multipliers, &mut intervals);
+        }
+
+        let (predictions, n_clipped) = self.report_predictions(&raw);
+        let covered: Vec<f64> = predictions
+            .iter()
+            .copied()
+            .filter(|v| !v.is_nan())
+            .collect();
+        Ok(InferenceResult {
+            mean_effect: covered.iter().sum::<f64>() / covered.len().max(1) as f64,
+            predictions,
+            confidence_intervals: intervals.pop().expect("one interval set per level"),
+            feature_importance: self.feature_importance(),
+            effect_scale: self.outcome_transform.effect_scale(),
+            n_clipped,
+        })
+    }
+
+    /// Applies `effect_floor` and `effect_ceil`; undefined (NaN) effects pass through.
+    fn clip_effect(&self, v: f64) -> f64 {
+        if v.is_nan() {
+            return v;
+        }
+        let v = self.effect_floor.map_or(v, |floor| v.max(floor));
+        self.effect_ceil.map_or(v, |ceil| v.min(ceil))
+    }
+
+    /// Raw per-tree predictions as an (n_samples x n_trees) matrix, before averaging.
+    /// Trees are scored in parallel; columns keep tree order, so averages over a row
+    /// sum in the same order as a sequential pass.
+    pub fn tree_predictions(&self, x: ArrayView2<f64>) -> Array2<f64> {
+        let columns: Vec<Array1<f64>> = self.trees.par_iter().map(|tree| tree.predict(x)).collect();
+        let mut preds = Array2::zeros((x.nrows(), self.trees.len()));
+        for (j, column) in columns.iter().enumerate() {
+            preds.column_mut(j).assign(column);
+        }
+        preds
+    }
+
+    /// Per-arm effects of a `Categorical` treatment as an (n_samples x (n_arms - 1))
+    /// matrix: column `k` is arm `k + 1` minus the baseline arm 0, averaged over the
+    /// trees and reported on the outcome scale within the effect bounds. Trees without
+    /// per-arm leaves (the constant trees of a constant outcome) count their single
+    /// effect for every arm.
+    pub fn predict_arm_effects(&self, x: ArrayView2<f64>) -> Result<Array2<f64>> {
+        let TreatmentKind::Categorical(n_arms) = self.treatment_kind else {
+            return Err(CausalFlowError::InvalidParameter(
+                "arm effects require a categorical treatment".to_string(),
+            ));
+        };
+        if self.trees.is_empty() {
+            return Err(CausalFlowError::ModelNotFitted);
+        }
+        self.check_feature_count(x)?;
+
+        let n_contrasts = n_arms.saturating_sub(1);
+        let rows: Vec<Vec<f64>> = (0..x.nrows())
+            .into_par_iter()
+            .map(|i| {
+                let mut sums = vec![0.0; n_contrasts];
+                for root in self.trees.iter().filter_map(|tree| tree.root.as_ref()) {
+                    let (effect, _, arms) = root.leaf_values(x.row(i));
+                    for (k, sum) in sums.iter_mut().enumerate() {
+                        *sum += arms.get(k).copied().unwrap_or(effect);
+                    }
+                }
+                sums
+            })
+            .collect();
+        let n_trees = self.trees.len() as f64;
+        Ok(Array2::from_shape_fn((x.nrows(), n_contrasts), |(i, k)| {
+            self.clip_effect(self.outcome_transform.back_transform(rows[i][k] / n_trees))
+        }))
+    }
+
+    /// Pairwise contrast of a `Categorical` treatment: arm `arm_b` minus arm `arm_a` for
+    /// every row of `x`, recombined from the stored per-arm leaf contrasts without
+    /// refitting and reported on the outcome scale. The effect bounds describe effects
+    /// against the baseline arm, so they are not applied to contrasts between two arms.
+    pub fn predict_contrast(
+        &self,
+        x: ArrayView2<f64>,
+        arm_a: usize,
+        arm_b: usize,
+    ) -> Result<Array1<f64>> {
+        let TreatmentKind::Categorical(n_arms) = self.treatment_kind else {
+            return Err(CausalFlowError::InvalidParameter(
+                "arm contrasts require a categorical treatment".to_string(),
+            ));
+        };
+        if arm_a >= n_arms || arm_b >= n_arms {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "arms must be in [0, {}), got {} and {}",
+                n_arms, arm_a, arm_b
+            )));
+        }
+        if self.trees.is_empty() {
+            return Err(CausalFlowError::ModelNotFitted);
+        }
+        self.check_feature_count(x)?;
+
+        // Arm 0 is the baseline; trees without per-arm leaves use their single effect
+        let arm_effect = |effect: f64, arms: &[f64], arm: usize| match arm {
+            0 => 0.0,
+            _ => arms.get(arm - 1).copied().unwrap_or(effect),
+        };
+        let n_trees = self.trees.len() as f64;
+        let contrasts: Vec<f64> = (0..x.nrows())
+            .into_par_iter()
+            .map(|i| {
+                let sum: f64 = self
+                    .trees
+                    .iter()
+                    .filter_map(|tree| tree.root.as_ref())
+                    .map(|root| {
+                        let (effect, _, arms) = root.leaf_values(x.row(i));
+                        arm_effect(effect, arms, arm_b) - arm_effect(effect, arms, arm_a)
+                    })
+                    .sum();
+                self.outcome_transform.back_transform(sum / n_trees)
+            })
+            .collect();
+        Ok(Array1::from(contrasts))
+    }
+
+    /// The `(leaf_effect, leaf_size)` that `row` lands in for every tree, showing the
+    /// spread and support behind one averaged prediction. Effects are on the fitted
+    /// scale, like `tree_predictions`.
+    pub fn explain_one(&self, row: &[f64]) -> Result<Vec<(f64, usize)>> {
+        if self.trees.is_empty() {
+            return Err(CausalFlowError::ModelNotFitted);
+        }
+        if row.len() != self.n_features {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "expected {} features, got {}",
+                self.n_features,
+                row.len()
+            )));
+        }
+        let row = ArrayView1::from(row);
+        Ok(self
+            .trees
+            .iter()
+            .filter_map(|tree| tree.root.as_ref().map(|root| root.leaf(row)))
+            .collect())
+    }
+
+    /// Scores `x` `n_iters` times and reports latency percentiles and throughput, for
+    /// sizing deployments and catching regressions after model changes.
+    pub fn benchmark_predict(
+        &self,
+        x: ArrayView2<f64>,
+        n_iters: usize,
+    ) -> Result<PredictBenchmark> {
+        if n_iters == 0 {
+            return Err(CausalFlowError::InvalidParameter(
+                "n_iters must be at least 1".to_string(),
+            ));
+        }
+        if self.trees.is_empty() {
+            return Err(CausalFlowError::ModelNotFitted);
+        }
+        self.check_feature_count(x)?;
+
+        let mut latencies = Vec::with_capacity(n_iters);
+        for _ in 0..n_iters {
+            let start = Instant::now();
+            std::hint::black_box(self.predict_result(x)?);
+            latencies.push(start.elapsed().as_secs_f64() * 1000.0);
+        }
+        latencies.sort_by(|a, b| a.total_cmp(b));
+
+        let mean = latencies.iter().sum::<f64>() / n_iters as f64;
+        Ok(PredictBenchmark {
+            n_rows: x.nrows(),
+            n_iters,
+            mean_latency_ms: mean,
+            median_latency_ms: sorted_quantile(&latencies, 0.5),
+            p99_latency_ms: sorted_quantile(&latencies, 0.99),
+            rows_per_second: if mean > 0.0 {
+                x.nrows() as f64 / (mean / 1000.0)
+            } else {
+                f64::INFINITY
+            },
+        })
+    }
+
+    /// Mean pairwise Pearson correlation of per-tree predictions on `x`.
+    ///
+    /// Values near 1 mean the trees barely differ, so adding trees stops reducing
+    /// variance; more randomization (fewer sampled features, `ExtraRandom` splits)
+    /// helps. Trees with constant predictions on `x` are left out of the average.
+    pub fn tree_correlation(&self, x: ArrayView2<f64>) -> Result<f64> {
+        if self.trees.is_empty() {
+            return Err(CausalFlowError::ModelNotFitted);
+        }
+        self.check_feature_count(x)?;
+        if x.nrows() < 2 {
             return Err(CausalFlowError::EmptyData);
         }
 
-        if x.iter().any(|&v| v.is_nan() || v.is_infinite()) ||
-           t.iter().any(|&v| v.is_nan() || v.is_infinite()) ||
-           y.iter().any(|&v| v.is_nan() || v.is_infinite()) {
-            return Err(CausalFlowError::InvalidData);
+        // Standardize each tree's predictions; correlations are then dot products / n
+        let standardized: Vec<Array1<f64>> = self
+            .tree_predictions(x)
+            .columns()
+            .into_iter()
+            .filter_map(|col| {
+                let std = col.std(0.0);
+                (std > 0.0).then(|| (&col - col.mean().unwrap_or(0.0)) / std)
+            })
+            .collect();
+        if standardized.len() < 2 {
+            return Err(CausalFlowError::Calculation(
+                "fewer than two trees vary on x, so correlation is undefined".to_string(),
+            ));
+        }
+
+        let n = x.nrows() as f64;
+        let (mut total, mut pairs) = (0.0, 0usize);
+        for i in 0..standardized.len() {
+            for j in i + 1..standardized.len() {
+                total += standardized[i].dot(&standardized[j]) / n;
+                pairs += 1;
+            }
+        }
+        Ok(total / pairs as f64)
+    }
+
+    /// Rejects prediction data whose column count differs from training. Column order
+    /// cannot be checked here; callers with feature names should align by name first.
+    pub fn check_feature_count(&self, x: ArrayView2<f64>) -> Result<()> {
+        if x.ncols() != self.n_features {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "expected {} features, got {}",
+                self.n_features,
+                x.ncols()
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn tree_params(&self) -> TreeParams {
+        TreeParams {
+            max_depth: self.max_depth,
+            min_leaf_size: self.min_leaf_size,
+            min_leaf_treated: self.min_leaf_treated,
+            min_leaf_control: self.min_leaf_control,
+            subsample_ratio: self.subsample_ratio,
+            split_strategy: self.split_strategy,
+            n_split_candidates: self.n_split_candidates,
+            max_features: self.max_features,
+            feature_sample_weights: self.feature_sample_weights.clone(),
+            gain_balance_penalty: self.gain_balance_penalty,
+            min_gain: self.min_gain,
+            relative_gain_tol: self.relative_gain_tol,
+            degenerate_leaf: self.degenerate_leaf,
+            treatment_kind: self.treatment_kind,
+            // Data-dependent; filled in when trees are grown
+            propensity: None,
+            sample_weight: None,
         }
+    }
 
-        for &v in t.iter() {
-            if (v - 0.0).abs() > f64::EPSILON && (v - 1.0).abs() > f64::EPSILON {
-                return Err(CausalFlowError::InvalidTreatment(v));
+    fn interval_multiplier(&self, confidence_level: f64) -> f64 {
+        let p = 1.0 - (1.0 - confidence_level) / 2.0;
+        match self.interval_distribution {
+            IntervalDistribution::StudentT if self.trees.len() > 1 => {
+                student_t_quantile(p, (self.trees.len() - 1) as f64)
             }
+            _ => normal_quantile(p),
         }
-
-        Ok(())
     }
 
-    pub fn fit(
+    /// Fits an isotonic map from predicted to observed (binned) effects on held-out
+    /// data. Later `predict` calls pass predictions and interval bounds through it;
+    /// refitting the forest or adding trees clears it. The observed effects are
+    /// treated-minus-control differences, so the treatment must be binary: continuous
+    /// slopes are on another scale, and one map cannot calibrate every categorical arm.
+    pub fn calibrate(
         &mut self,
         x: ArrayView2<f64>,
         t: ArrayView1<f64>,
         y: ArrayView1<f64>,
-    ) {
-        let _ = self.fit_result(x, t, y);
+    ) -> Result<()> {
+        if self.trees.is_empty() {
+            return Err(CausalFlowError::ModelNotFitted);
+        }
+        if self.treatment_kind != TreatmentKind::Binary {
+            return Err(CausalFlowError::InvalidParameter(
+                "calibrate requires a binary treatment".to_string(),
+            ));
+        }
+        self.check_feature_count(x)?;
+        if self.outcome_transform == OutcomeTransform::Log && y.iter().any(|&v| v <= 0.0) {
+            return Err(CausalFlowError::InvalidParameter(
+                "outcome_transform='log' requires strictly positive outcomes".to_string(),
+            ));
+        }
+        let raw = self.tree_predictions(x).sum_axis(Axis(1)) / self.trees.len() as f64;
+        let y = self.outcome_transform.apply(y);
+        self.calibration = Some(fit_isotonic_calibration(raw.view(), t, y.view(), 10)?);
+        Ok(())
     }
 
-    pub fn fit_result(
-        &mut self,
-        x: ArrayView2<f64>,
-        t: ArrayView1<f64>,
-        y: ArrayView1<f64>,
-    ) -> Result<()> {
-        self.validate_data(x, t, y)?;
+    /// Share of the variance in predicted CATEs attributable to each feature.
+    ///
+    /// Each feature's partial-dependence curve is evaluated on up to `n_grid` quantiles
+    /// of its observed values in `x`; the variance of that curve is its first-order
+    /// (functional ANOVA) contribution. Shares are normalized to sum to one, or are all
+    /// zero when the effect surface is flat.
+    pub fn effect_variance_shares(&self, x: ArrayView2<f64>, n_grid: usize) -> Result<Vec<f64>> {
+        if self.trees.is_empty() {
+            return Err(CausalFlowError::ModelNotFitted);
+        }
+        self.check_feature_count(x)?;
+        if x.nrows() == 0 {
+            return Err(CausalFlowError::EmptyData);
+        }
+        if n_grid < 2 {
+            return Err(CausalFlowError::InvalidParameter(
+                "n_grid must be at least 2".to_string(),
+            ));
+        }
 
-        let n_features = x.ncols();
-        self.n_features = n_features;
-        self.trees = (0..self.n_estimators)
+        let n_trees = self.trees.len() as f64;
+        let variances: Vec<f64> = (0..x.ncols())
             .into_par_iter()
-            .map(|_| {
-                let mut tree = CausalTree::new(n_features);
-                tree.fit(
-                    x,
-                    t,
-                    y,
-                    self.max_depth,
-                    self.min_leaf_size,
-                );
-                tree
+            .map(|j| {
+                let mut values = x.column(j).to_vec();
+                values.sort_by(|a, b| a.total_cmp(b));
+                let n = values.len();
+                let k = n_grid.min(n);
+                let grid: Vec<f64> = (0..k)
+                    .map(|g| values[g * (n - 1) / (k - 1).max(1)])
+                    .collect();
+
+                let mut x_mod = x.to_owned();
+                let curve: Array1<f64> = grid
+                    .iter()
+                    .map(|&v| {
+                        x_mod.column_mut(j).fill(v);
+                        let preds = self.tree_predictions(x_mod.view()).sum_axis(Axis(1)) / n_trees;
+                        preds.mean().unwrap_or(0.0)
+                    })
+                    .collect();
+                if curve.len() > 1 {
+                    curve.var(0.0)
+                } else {
+                    0.0
+                }
             })
             .collect();
-        
-        Ok(())
+
+        let total: f64 = variances.iter().sum();
+        if total > 0.0 {
+            Ok(variances.iter().map(|v| v / total).collect())
+        } else {
+            Ok(vec![0.0; variances.len()])
+        }
     }
 
-    pub fn fit_placebo(
-        &mut self,
+    /// Each row's predicted effect minus the predicted effect of `reference_row`, e.g. an
+    /// average customer profile, so heterogeneity reads as "+0.2 above baseline".
+    pub fn effect_relative_to(
+        &self,
         x: ArrayView2<f64>,
-        t: ArrayView1<f64>,
-        y: ArrayView1<f64>,
-    ) {
-        let _ = self.fit_placebo_result(x, t, y);
+        reference_row: &[f64],
+    ) -> Result<Array1<f64>> {
+        if self.trees.is_empty() {
+            return Err(CausalFlowError::ModelNotFitted);
+        }
+        if reference_row.len() != self.n_features {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "reference_row has {} values but the model has {} features",
+                reference_row.len(),
+                self.n_features
+            )));
+        }
+        if reference_row.iter().any(|v| !v.is_finite()) {
+            return Err(CausalFlowError::InvalidData);
+        }
+
+        let reference = Array2::from_shape_vec((1, self.n_features), reference_row.to_vec())
+            .expect("reference row length was checked");
+        let baseline = self.predict_with_level(reference.view(), self.confidence_level)?;
+        let predictions = self
+            .predict_with_level(x, self.confidence_level)?
+            .predictions;
+        Ok(predictions - baseline.predictions[0])
     }
 
-    pub fn fit_placebo_result(
-        &mut self,
+    /// Mean predicted effect within percentile bands of one feature.
+    ///
+    /// `percentiles` are increasing upper band edges in (0, 100]: `[50, 90, 100]` gives the
+    /// bands (0, 50], (50, 90] and (90, 100], so the last entry is "the top 10%". Each
+    /// entry returns the feature value at that percentile and the band's mean effect
+    /// (NaN when no row falls in the band).
+    pub fn effect_at_percentiles(
+        &self,
         x: ArrayView2<f64>,
-        t: ArrayView1<f64>,
-        y: ArrayView1<f64>,
-    ) -> Result<()> {
-        let mut t_shuffled = t.to_owned();
-        let mut rng = thread_rng();
-        let mut indices: Vec<usize> = (0..t.len()).collect();
-        indices.shuffle(&mut rng);
-        
-        // Reorder t_shuffled based on shuffled indices
-        let t_orig = t.to_owned();
-        for (i, &idx) in indices.iter().enumerate() {
-            t_shuffled[i] = t_orig[idx];
+        feature_idx: usize,
+        percentiles: &[f64],
+    ) -> Result<Vec<(f64, f64)>> {
+        if feature_idx >= self.n_features {
+            return Err(CausalFlowError::FeatureOutOfBounds(feature_idx));
+        }
+        if percentiles.is_empty()
+            || percentiles.iter().any(|&p| !(p > 0.0 && p <= 100.0))
+            || percentiles.windows(2).any(|w| w[0] >= w[1])
+        {
+            return Err(CausalFlowError::InvalidParameter(
+                "percentiles must be strictly increasing values in (0, 100]".to_string(),
+            ));
         }
+        let effects = self
+            .predict_with_level(x, self.confidence_level)?
+            .predictions;
 
-        self.fit_result(x, t_shuffled.view(), y)
+        let column = x.column(feature_idx);
+        let mut order: Vec<usize> = (0..x.nrows()).collect();
+        order.sort_by(|&a, &b| column[a].total_cmp(&column[b]));
+        let sorted: Vec<f64> = order.iter().map(|&i| column[i]).collect();
+
+        let n = order.len() as f64;
+        let mut start = 0;
+        Ok(percentiles
+            .iter()
+            .map(|&p| {
+                let end = ((p / 100.0 * n).round() as usize).max(start);
+                let band = &order[start..end];
+                start = end;
+                let mean_effect = if band.is_empty() {
+                    f64::NAN
+                } else {
+                    band.iter().map(|&i| effects[i]).sum::<f64>() / band.len() as f64
+                };
+                (sorted_quantile(&sorted, p / 100.0), mean_effect)
+            })
+            .collect())
     }
 
-    pub fn predict(&self, x: ArrayView2<f64>) -> InferenceResult {
-        self.predict_result(x).unwrap_or_else(|_| InferenceResult {
-            predictions: Array1::zeros(x.nrows()),
-            mean_effect: 0.0,
-            confidence_intervals: vec![(0.0, 0.0); x.nrows()],
-            feature_importance: vec![0.0; self.n_features],
-        })
+    /// Mean effect within up to `n_bins` quantile bins of one feature. Ties never
+    /// straddle bins, so low-cardinality features yield fewer, non-empty bins.
+    pub fn effect_by_bins(
+        &self,
+        x: ArrayView2<f64>,
+        feature_idx: usize,
+        n_bins: usize,
+    ) -> Result<Vec<EffectBin>> {
+        if feature_idx >= self.n_features {
+            return Err(CausalFlowError::FeatureOutOfBounds(feature_idx));
+        }
+        let effects = self
+            .predict_with_level(x, self.confidence_level)?
+            .predictions;
+        effect_bins(x.column(feature_idx), effects.view(), n_bins)
     }
 
-    pub fn predict_result(&self, x: ArrayView2<f64>) -> Result<InferenceResult> {
+    /// `effect_by_bins` for every feature, predicting only once.
+    pub fn effect_profiles(
+        &self,
+        x: ArrayView2<f64>,
+        n_bins: usize,
+    ) -> Result<Vec<Vec<EffectBin>>> {
+        let effects = self
+            .predict_with_level(x, self.confidence_level)?
+            .predictions;
+        x.columns()
+            .into_iter()
+            .map(|column| effect_bins(column, effects.view(), n_bins))
+            .collect()
+    }
+
+    /// Compares the forest's (calibrated) CATEs against the constant-effect (linear model)
+    /// ATE on `(x, t, y)`. Scores are on the fitted outcome scale and are optimistic when
+    /// `(x, t, y)` is the training data; pass held-out data for an honest comparison.
+    /// The transformed-outcome score needs a binary treatment.
+    pub fn heterogeneity_gain(
+        &self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+    ) -> Result<HeterogeneityGain> {
         if self.trees.is_empty() {
             return Err(CausalFlowError::ModelNotFitted);
         }
+        self.check_feature_count(x)?;
+        if self.treatment_kind != TreatmentKind::Binary {
+            return Err(CausalFlowError::InvalidParameter(
+                "heterogeneity_gain requires a binary treatment".to_string(),
+            ));
+        }
+        let y = self.outcome_transform.apply(y);
 
-        let n_samples = x.nrows();
-        if n_samples == 0 {
+        let mut constant = LinearCausalModel::new();
+        constant.fit(x, t, y.view())?;
+        let cate = (self.tree_predictions(x).sum_axis(Axis(1)) / self.trees.len() as f64)
+            .mapv(|v| self.calibrated_effect(v));
+        heterogeneity_gain(cate.view(), constant.coef, t, y.view())
+    }
+
+    /// ATE and confidence interval over the rows of `x` selected by `mask`. The ATE is
+    /// the mean of the selected rows' `predict` effects (calibrated, on the outcome scale
+    /// and within the effect bounds), so an all-true mask gives `predict`'s mean effect.
+    ///
+    /// The standard error combines the spread of the selected CATEs (shrinking with the
+    /// subgroup size) and the disagreement between trees on the subgroup mean.
+    pub fn conditional_ate(
+        &self,
+        x: ArrayView2<f64>,
+        mask: &Array1<bool>,
+    ) -> Result<(f64, (f64, f64))> {
+        if self.trees.is_empty() {
+            return Err(CausalFlowError::ModelNotFitted);
+        }
+        self.check_feature_count(x)?;
+        if mask.len() != x.nrows() {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "mask has {} entries but x has {} rows",
+                mask.len(),
+                x.nrows()
+            )));
+        }
+        let rows: Vec<usize> = (0..mask.len()).filter(|&i| mask[i]).collect();
+        if rows.is_empty() {
             return Err(CausalFlowError::EmptyData);
         }
 
-        let mut predictions = Array1::zeros(n_samples);
+        let tree_preds = self.tree_predictions(x.select(Axis(0), &rows).view());
+        let n = rows.len() as f64;
+        let n_trees = self.trees.len() as f64;
+        let (cate, _) = self.report_predictions(&(tree_preds.sum_axis(Axis(1)) / n_trees));
+        let ate = cate.mean().unwrap_or(0.0);
 
-        for tree in &self.trees {
-            predictions += &tree.predict(x);
+        let row_var = if rows.len() > 1 { cate.var(1.0) } else { 0.0 };
+        let tree_means = tree_preds
+            .mapv(|v| self.report_effect(v))
+            .mean_axis(Axis(0))
+            .unwrap();
+        let tree_var = if self.trees.len() > 1 {
+            tree_means.var(1.0)
+        } else {
+            0.0
+        };
+        let se = (row_var / n + tree_var / n_trees).sqrt();
+        let half_width = self.interval_multiplier(self.confidence_level) * se;
+
+        Ok((
+            ate,
+            (
+                self.clip_effect(ate - half_width),
+                self.clip_effect(ate + half_width),
+            ),
+        ))
+    }
+
+    /// ATE and confidence interval for a population reweighted by per-row `weights`
+    /// (e.g. importance weights toward a target covariate mix): the weighted mean of the
+    /// rows' `predict` effects, so calibration and the effect bounds apply as in `predict`.
+    ///
+    /// Like `conditional_ate`, the standard error combines the weighted spread of the
+    /// CATEs and the disagreement between trees on the weighted mean; the row term uses
+    /// the Kish effective sample size, so uneven weights widen the interval.
+    pub fn weighted_ate(
+        &self,
+        x: ArrayView2<f64>,
+        weights: &Array1<f64>,
+    ) -> Result<(f64, (f64, f64))> {
+        if self.trees.is_empty() {
+            return Err(CausalFlowError::ModelNotFitted);
+        }
+        self.check_feature_count(x)?;
+        if weights.len() != x.nrows() {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "weights has {} entries but x has {} rows",
+                weights.len(),
+                x.nrows()
+            )));
+        }
+        if weights.iter().any(|&w| !w.is_finite() || w < 0.0) {
+            return Err(CausalFlowError::InvalidParameter(
+                "weights must be finite and non-negative".to_string(),
+            ));
+        }
+        let total: f64 = weights.sum();
+        if total <= 0.0 {
+            return Err(CausalFlowError::InvalidParameter(
+                "weights must not all be zero".to_string(),
+            ));
         }
+        let w = weights / total;
 
-        predictions /= self.trees.len() as f64;
+        let tree_preds = self.tree_predictions(x);
+        let n_trees = self.trees.len() as f64;
+        let (cate, _) = self.report_predictions(&(tree_preds.sum_axis(Axis(1)) / n_trees));
+        let ate = w.dot(&cate);
 
-        let mean_effect = predictions.mean().unwrap_or(0.0);
-        let confidence_intervals = predictions.iter().map(|&p| (p - 0.1, p + 0.1)).collect();
+        let n_eff = 1.0 / w.dot(&w);
+        let row_var = if n_eff > 1.0 {
+            w.dot(&cate.mapv(|c| (c - ate).powi(2))) * n_eff / (n_eff - 1.0)
+        } else {
+            0.0
+        };
+        let tree_means = w.dot(&tree_preds.mapv(|v| self.report_effect(v)));
+        let tree_var = if self.trees.len() > 1 {
+            tree_means.var(1.0)
+        } else {
+            0.0
+        };
+        let se = (row_var / n_eff + tree_var / n_trees).sqrt();
+        let half_width = self.interval_multiplier(self.confidence_level) * se;
 
-        // Aggregate feature importance
-        let mut feature_importance = vec![0.0; self.n_features];
-        for tree in &self.trees {
-            for (i, &imp) in tree.feature_importance.iter().enumerate() {
-                feature_importance[i] += imp;
-            }
-        }
-        let sum: f64 = feature_importance.iter().sum();
-        if sum > 0.0 {
-            for imp in feature_importance.iter_mut() {
-                *imp /= sum;
-            }
+        Ok((
+            ate,
+            (
+                self.clip_effect(ate - half_width),
+                self.clip_effect(ate + half_width),
+            ),
+        ))
+    }
+
+    /// Picks the predicted-effect cutoff that maximizes uplift on a held-out set.
+    pub fn calibrate_threshold(
+        &self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+        metric: ThresholdMetric,
+    ) -> Result<ThresholdResult> {
+        let predicted = self.predict_result(x)?.predictions;
+        calibrate_threshold(predicted.view(), t, y, metric)
+    }
+
+    /// Unadjusted difference of means, ignoring all covariates.
+    /// On the same scale as the forest's effects, so the treatment must be binary.
+    pub fn naive_ate(&self, t: ArrayView1<f64>, y: ArrayView1<f64>) -> Result<f64> {
+        if self.treatment_kind != TreatmentKind::Binary {
+            return Err(CausalFlowError::InvalidParameter(
+                "the naive ATE requires a binary treatment".to_string(),
+            ));
         }
+        let y = self.outcome_transform.apply(y);
+        Ok(self
+            .outcome_transform
+            .back_transform(difference_in_means(t, y.view())))
+    }
 
-        Ok(InferenceResult {
-            predictions,
-            mean_effect,
-            confidence_intervals,
-            feature_importance,
-        })
+    /// Compares the naive ATE with the forest's covariate-adjusted ATE on `x`.
+    pub fn compare_naive_ate(
+        &self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+    ) -> Result<AteComparison> {
+        let naive = self.naive_ate(t, y)?;
+        let adjusted = self.predict_result(x)?.mean_effect;
+        Ok(AteComparison::new(naive, adjusted))
     }
 }
 
@@ -198,9 +2148,41 @@ impl CausalModel for CausalForest {
         self.fit_result(x, t, y)
     }
 
+    fn fit_weighted(
+        &mut self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+        sample_weight: ArrayView1<f64>,
+    ) -> Result<()> {
+        CausalForest::fit_weighted(self, x, t, y, sample_weight)
+    }
+
     fn predict(&self, x: ArrayView2<f64>) -> Result<InferenceResult> {
         self.predict_result(x)
     }
+
+    fn predict_with_level(
+        &self,
+        x: ArrayView2<f64>,
+        confidence_level: f64,
+    ) -> Result<InferenceResult> {
+        CausalForest::predict_with_level(self, x, confidence_level)
+    }
+
+    /// Appends `name` to `feature_names` and gives the new column the mean of
+    /// `feature_sample_weights`, so it is sampled like an average feature.
+    fn box_clone_with_feature(&self, name: &str) -> Box<dyn CausalModel> {
+        let mut forest = self.clone();
+        if let Some(names) = &mut forest.feature_names {
+            names.push(name.to_string());
+        }
+        if let Some(weights) = &mut forest.feature_sample_weights {
+            let mean = weights.iter().sum::<f64>() / weights.len().max(1) as f64;
+            weights.push(mean);
+        }
+        Box::new(forest)
+    }
 }
 
 impl CausalTree {
@@ -208,6 +2190,20 @@ impl CausalTree {
         Self {
             root: None,
             feature_importance: vec![0.0; n_features],
+            oob_indices: Vec::new(),
+        }
+    }
+
+    /// A single-leaf tree predicting `treatment_effect` everywhere.
+    pub fn constant(n_features: usize, treatment_effect: f64, size: usize) -> Self {
+        Self {
+            root: Some(Box::new(Node::Leaf {
+                treatment_effect,
+                size,
+                arm_effects: Vec::new(),
+            })),
+            feature_importance: vec![0.0; n_features],
+            oob_indices: Vec::new(),
         }
     }
 
@@ -216,31 +2212,34 @@ impl CausalTree {
         x: ArrayView2<f64>,
         t: ArrayView1<f64>,
         y: ArrayView1<f64>,
-        max_depth: usize,
-        min_leaf_size: usize,
+        params: &TreeParams,
+        seed: u64,
     ) {
         let n_samples = x.nrows();
-        let mut rng = thread_rng();
+        // One generator per tree, reused for every node instead of re-acquiring thread_rng
+        let mut rng = SplitMix64::seed_from_u64(seed);
 
         let mut indices: Vec<usize> = (0..n_samples).collect();
         indices.shuffle(&mut rng);
 
-        let split_size = n_samples / 2;
-        let split_indices = &indices[..split_size];
-        let estimation_indices = &indices[split_size..];
-
-        self.root = Some(self.build_tree(
-            x,
-            t,
-            y,
-            split_indices,
-            estimation_indices,
-            0,
-            max_depth,
-            min_leaf_size,
-        ));
+        // Subsample first, then split the sampled rows into the honest halves
+        let n_sampled = ((params.subsample_ratio * n_samples as f64).ceil() as usize)
+            .clamp(1, n_samples.max(1));
+        let (sampled, oob) = indices.split_at(n_sampled);
+        self.oob_indices = oob.to_vec();
+        self.oob_indices.sort_unstable();
+
+        let split_size = sampled.len() / 2;
+        let split_indices = &sampled[..split_size];
+        let estimation_indices = &sampled[split_size..];
+
+        self.root =
+            Some(self.build_tree(x, t, y, split_indices, estimation_indices, params, &mut rng));
     }
 
+    /// Grows the tree with an explicit work stack so deep trees cannot overflow the
+    /// call stack. Nodes are laid out in an arena (children always after their
+    /// parent) and boxed bottom-up once growing is done.
     #[allow(clippy::too_many_arguments)]
     fn build_tree(
         &mut self,
@@ -249,49 +2248,210 @@ impl CausalTree {
         y: ArrayView1<f64>,
         split_idx: &[usize],
         est_idx: &[usize],
-        depth: usize,
-        max_depth: usize,
-        min_leaf_size: usize,
+        params: &TreeParams,
+        rng: &mut SplitMix64,
     ) -> Box<Node> {
-        if depth >= max_depth
-            || split_idx.len() < min_leaf_size * 2
-            || est_idx.len() < min_leaf_size
-        {
-            return Box::new(Node::Leaf {
-                treatment_effect: self.estimate_effect(t, y, est_idx),
-                size: est_idx.len(),
-            });
+        enum Pending {
+            Leaf(f64, usize, Vec<f64>),
+            Internal(usize, f64, usize, usize, bool),
+        }
+
+        let min_leaf_size = params.min_leaf_size;
+        let mut arena: Vec<Option<Pending>> = vec![None];
+        // Each entry carries the nearest ancestor's defined estimate for `ParentEffect`
+        let mut stack = vec![(0, split_idx.to_vec(), est_idx.to_vec(), 0, None)];
+
+        while let Some((slot, split_idx, est_idx, depth, inherited)) = stack.pop() {
+            let split = if depth >= params.max_depth
+                || split_idx.len() < min_leaf_size * 2
+                || est_idx.len() < min_leaf_size
+            {
+                None
+            } else {
+                self.find_split(x, t, y, &split_idx, params, rng)
+                    .filter(|split| split.gain >= params.min_gain)
+                    .filter(|split| {
+                        // The node's effect is already stable relative to its size
+                        params.relative_gain_tol == 0.0
+                            || !matches!(
+                                self.leaf_effect(t, y, &split_idx, params),
+                                Some(tau) if split.gain < params.relative_gain_tol * tau * tau
+                            )
+                    })
+            };
+
+            match split {
+                Some(split) => {
+                    // Track importance
+                    self.feature_importance[split.feature_idx] += split.gain;
+
+                    let inherited = match params.degenerate_leaf {
+                        DegenerateLeafPolicy::ParentEffect => {
+                            self.leaf_effect(t, y, &est_idx, params).or(inherited)
+                        }
+                        _ => None,
+                    };
+
+                    let (left_est, right_est): (Vec<usize>, Vec<usize>) = est_idx
+                        .iter()
+                        .partition(|&&i| x[[i, split.feature_idx]] <= split.threshold);
+
+                    let left = arena.len();
+                    let right = left + 1;
+                    arena.push(None);
+                    arena.push(None);
+                    arena[slot] = Some(Pending::Internal(
+                        split.feature_idx,
+                        split.threshold,
+                        left,
+                        right,
+                        left_est.len() >= right_est.len(),
+                    ));
+                    stack.push((right, split.right, right_est, depth + 1, inherited));
+                    stack.push((left, split.left, left_est, depth + 1, inherited));
+                }
+                None => {
+                    let effect = match self.leaf_effect(t, y, &est_idx, params) {
+                        Some(effect) => effect,
+                        None => match params.degenerate_leaf {
+                            DegenerateLeafPolicy::ZeroEffect => 0.0,
+                            DegenerateLeafPolicy::ParentEffect => inherited.unwrap_or(0.0),
+                            DegenerateLeafPolicy::NaN => f64::NAN,
+                        },
+                    };
+                    let arm_effects = match params.treatment_kind {
+                        TreatmentKind::Categorical(n_arms) => {
+                            let undefined = match params.degenerate_leaf {
+                                DegenerateLeafPolicy::NaN => f64::NAN,
+                                _ => 0.0,
+                            };
+                            self.arm_contrasts(t, y, &est_idx, n_arms, params)
+                                .into_iter()
+                                .map(|contrast| contrast.unwrap_or(undefined))
+                                .collect()
+                        }
+                        _ => Vec::new(),
+                    };
+                    arena[slot] = Some(Pending::Leaf(effect, est_idx.len(), arm_effects));
+                }
+            }
+        }
+
+        let mut built: Vec<Option<Box<Node>>> = (0..arena.len()).map(|_| None).collect();
+        for slot in (0..arena.len()).rev() {
+            let node = match arena[slot].take().expect("every arena slot is filled") {
+                Pending::Leaf(treatment_effect, size, arm_effects) => Node::Leaf {
+                    treatment_effect,
+                    size,
+                    arm_effects,
+                },
+                Pending::Internal(feature_idx, threshold, left, right, default_left) => {
+                    Node::Internal {
+                        feature_idx,
+                        threshold,
+                        left: built[left].take().expect("children are built first"),
+                        right: built[right].take().expect("children are built first"),
+                        default_left,
+                    }
+                }
+            };
+            built[slot] = Some(Box::new(node));
         }
+        built[0].take().expect("root is always built")
+    }
 
+    /// Best split of `split_idx` over a random subset of features.
+    fn find_split(
+        &self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+        split_idx: &[usize],
+        params: &TreeParams,
+        rng: &mut SplitMix64,
+    ) -> Option<NodeSplit> {
+        let min_leaf_size = params.min_leaf_size;
         let n_features = x.ncols();
-        let mut rng = thread_rng();
 
-        let n_sub_features = (n_features as f64).sqrt() as usize;
+        let n_sub_features = params.max_features.resolve(n_features);
         let mut sampled_features: Vec<usize> = (0..n_features).collect();
-        sampled_features.shuffle(&mut rng);
+        match &params.feature_sample_weights {
+            Some(weights) => {
+                // Weighted sampling without replacement (Efraimidis-Spirakis): keep the
+                // features with the largest u^(1/w) keys.
+                let keys: Vec<f64> = weights
+                    .iter()
+                    .map(|&w| {
+                        if w > 0.0 {
+                            rng.gen::<f64>().powf(1.0 / w)
+                        } else {
+                            -1.0
+                        }
+                    })
+                    .collect();
+                sampled_features.sort_by(|&a, &b| keys[b].total_cmp(&keys[a]));
+            }
+            None => sampled_features.shuffle(rng),
+        }
         let sampled_features = &sampled_features[..n_sub_features];
+        // Each feature gets its own stream so the parallel search stays independent
+        let feature_seeds: Vec<u64> = sampled_features.iter().map(|_| rng.next_u64()).collect();
 
         let best_split = sampled_features
             .par_iter()
-            .map(|&f_idx| {
-                let mut local_rng = thread_rng();
+            .zip(feature_seeds.par_iter())
+            .map(|(&f_idx, &seed)| {
+                let mut local_rng = SplitMix64::seed_from_u64(seed);
                 let mut local_best_gain = -1.0;
                 let mut local_best_split = None;
 
-                // Instead of collecting all values, just sample indices to pick thresholds
-                let n_candidates = 10.min(split_idx.len());
-                for _ in 0..n_candidates {
-                    let random_idx = split_idx[local_rng.gen_range(0..split_idx.len())];
-                    let threshold = x[[random_idx, f_idx]];
+                let thresholds: Vec<f64> = match params.split_strategy {
+                    SplitStrategy::BestRandom => {
+                        // Instead of collecting all values, just sample indices to pick thresholds
+                        let n_candidates = params.n_split_candidates.min(split_idx.len());
+                        (0..n_candidates)
+                            .map(|_| x[[split_idx[local_rng.gen_range(0..split_idx.len())], f_idx]])
+                            .collect()
+                    }
+                    SplitStrategy::ExtraRandom => {
+                        // A single uniform draw between the node's feature bounds
+                        let (lo, hi) = split_idx
+                            .iter()
+                            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &i| {
+                                (lo.min(x[[i, f_idx]]), hi.max(x[[i, f_idx]]))
+                            });
+                        if hi > lo {
+                            vec![local_rng.gen_range(lo..hi)]
+                        } else {
+                            Vec::new()
+                        }
+                    }
+                    SplitStrategy::Quantile(k) => {
+                        let mut values: Vec<f64> =
+                            split_idx.iter().map(|&i| x[[i, f_idx]]).collect();
+                        values.sort_by(|a, b| a.total_cmp(b));
+                        let mut thresholds: Vec<f64> = (1..=k)
+                            .map(|q| sorted_quantile(&values, q as f64 / (k + 1) as f64))
+                            .collect();
+                        thresholds.dedup();
+                        thresholds
+                    }
+                };
 
+                for threshold in thresholds {
                     let (left_idx, right_idx): (Vec<usize>, Vec<usize>) =
                         split_idx.iter().cloned().partition(|&i| x[[i, f_idx]] <= threshold);
 
                     if left_idx.len() < min_leaf_size || right_idx.len() < min_leaf_size {
                         continue;
                     }
+                    if !self.arms_large_enough(t, &left_idx, params)
+                        || !self.arms_large_enough(t, &right_idx, params)
+                    {
+                        continue;
+                    }
 
-                    let gain = self.calculate_causal_gain(t, y, &left_idx, &right_idx);
+                    let gain = self.calculate_causal_gain(t, y, &left_idx, &right_idx, params);
                     if gain > local_best_gain {
                         local_best_gain = gain;
                         local_best_split = Some((f_idx, threshold, left_idx, right_idx));
@@ -301,43 +2461,14 @@ impl CausalTree {
             })
             .reduce(|| (-1.0, None), |a, b| if a.0 > b.0 { a } else { b });
 
-        if let Some((gain, Some((f_idx, threshold, left_split, right_split)))) = Some(best_split) {
-            // Track importance
-            self.feature_importance[f_idx] += gain;
-
-            let (left_est, right_est): (Vec<usize>, Vec<usize>) =
-                est_idx.iter().partition(|&&i| x[[i, f_idx]] <= threshold);
-
-            Box::new(Node::Internal {
-                feature_idx: f_idx,
-                threshold,
-                left: self.build_tree(
-                    x,
-                    t,
-                    y,
-                    &left_split,
-                    &left_est,
-                    depth + 1,
-                    max_depth,
-                    min_leaf_size,
-                ),
-                right: self.build_tree(
-                    x,
-                    t,
-                    y,
-                    &right_split,
-                    &right_est,
-                    depth + 1,
-                    max_depth,
-                    min_leaf_size,
-                ),
-            })
-        } else {
-            Box::new(Node::Leaf {
-                treatment_effect: self.estimate_effect(t, y, est_idx),
-                size: est_idx.len(),
-            })
-        }
+        let (gain, split) = best_split;
+        split.map(|(feature_idx, threshold, left, right)| NodeSplit {
+            gain,
+            feature_idx,
+            threshold,
+            left,
+            right,
+        })
     }
 
     fn calculate_causal_gain(
@@ -346,67 +2477,223 @@ impl CausalTree {
         y: ArrayView1<f64>,
         left: &[usize],
         right: &[usize],
+        params: &TreeParams,
     ) -> f64 {
-        let tau_l = self.estimate_effect(t, y, left);
-        let tau_r = self.estimate_effect(t, y, right);
-        let nl = left.len() as f64;
-        let nr = right.len() as f64;
+        let contrast = match params.treatment_kind {
+            // Summed over the arm contrasts; an undefined contrast counts as zero
+            TreatmentKind::Categorical(n_arms) => self
+                .arm_contrasts(t, y, left, n_arms, params)
+                .into_iter()
+                .zip(self.arm_contrasts(t, y, right, n_arms, params))
+                .map(|(l, r)| (l.unwrap_or(0.0) - r.unwrap_or(0.0)).powi(2))
+                .sum::<f64>(),
+            _ => {
+                let tau_l = self.estimate_effect(t, y, left, params);
+                let tau_r = self.estimate_effect(t, y, right, params);
+                (tau_l - tau_r).powi(2)
+            }
+        };
+        let size = |indices: &[usize]| match &params.sample_weight {
+            Some(w) => indices.iter().map(|&i| w[i]).sum(),
+            None => indices.len() as f64,
+        };
+        let nl = size(left);
+        let nr = size(right);
         let n = nl + nr;
 
-        (nl * nr / (n * n)) * (tau_l - tau_r).powi(2)
+        (nl * nr / (n * n)).powf(params.gain_balance_penalty) * contrast
+    }
+
+    /// Whether `indices` has at least the configured minimum of each treatment arm.
+    fn arms_large_enough(
+        &self,
+        t: ArrayView1<f64>,
+        indices: &[usize],
+        params: &TreeParams,
+    ) -> bool {
+        if params.treatment_kind == TreatmentKind::Continuous
+            || (params.min_leaf_treated == 0 && params.min_leaf_control == 0)
+        {
+            return true;
+        }
+        let n_treated = indices.iter().filter(|&&i| t[i] > 0.5).count();
+        n_treated >= params.min_leaf_treated && indices.len() - n_treated >= params.min_leaf_control
+    }
+
+    fn estimate_effect(
+        &self,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+        indices: &[usize],
+        params: &TreeParams,
+    ) -> f64 {
+        self.leaf_effect(t, y, indices, params).unwrap_or(0.0)
+    }
+
+    /// Effect over `indices` for the configured treatment kind, `None` when undefined.
+    fn leaf_effect(
+        &self,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+        indices: &[usize],
+        params: &TreeParams,
+    ) -> Option<f64> {
+        match params.treatment_kind {
+            TreatmentKind::Binary => self.arm_difference(t, y, indices, params),
+            TreatmentKind::Continuous => match &params.sample_weight {
+                Some(w) => weighted_treatment_slope(t, y, indices, |i| w[i]),
+                None => treatment_slope(t, y, indices),
+            },
+            TreatmentKind::Categorical(n_arms) => self
+                .arm_contrasts(t, y, indices, n_arms, params)
+                .first()
+                .copied()
+                .flatten(),
+        }
+    }
+
+    /// `arm_contrasts` over `indices`, weighted by the sample weights of weighted fits.
+    fn arm_contrasts(
+        &self,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+        indices: &[usize],
+        n_arms: usize,
+        params: &TreeParams,
+    ) -> Vec<Option<f64>> {
+        match &params.sample_weight {
+            Some(w) => arm_contrasts(t, y, indices, n_arms, |i| w[i]),
+            None => arm_contrasts(t, y, indices, n_arms, |_| 1.0),
+        }
     }
 
-    fn estimate_effect(&self, t: ArrayView1<f64>, y: ArrayView1<f64>, indices: &[usize]) -> f64 {
+    /// Treated minus control mean over `indices`, `None` when either arm is empty.
+    ///
+    /// With propensities each treated unit is weighted by `1 / e` and each control by
+    /// `1 / (1 - e)`, and each arm's weighted mean is normalized by its total weight.
+    /// Sample weights multiply into these weights.
+    fn arm_difference(
+        &self,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+        indices: &[usize],
+        params: &TreeParams,
+    ) -> Option<f64> {
         let mut y1_sum = 0.0;
-        let mut y1_count = 0;
+        let mut y1_weight = 0.0;
         let mut y0_sum = 0.0;
-        let mut y0_count = 0;
+        let mut y0_weight = 0.0;
 
         for &i in indices {
+            let e = params.propensity.as_ref().map(|p| p[i]);
+            let sample_weight = params.sample_weight.as_ref().map_or(1.0, |w| w[i]);
             if t[i] > 0.5 {
-                y1_sum += y[i];
-                y1_count += 1;
+                let w = sample_weight * e.map_or(1.0, |e| 1.0 / e);
+                y1_sum += w * y[i];
+                y1_weight += w;
             } else {
-                y0_sum += y[i];
-                y0_count += 1;
+                let w = sample_weight * e.map_or(1.0, |e| 1.0 / (1.0 - e));
+                y0_sum += w * y[i];
+                y0_weight += w;
             }
         }
 
-        if y1_count > 0 && y0_count > 0 {
-            (y1_sum / y1_count as f64) - (y0_sum / y0_count as f64)
+        if y1_weight > 0.0 && y0_weight > 0.0 {
+            Some((y1_sum / y1_weight) - (y0_sum / y0_weight))
         } else {
-            0.0
+            None
         }
     }
 
     pub fn predict(&self, x: ArrayView2<f64>) -> Array1<f64> {
-        let n_samples = x.nrows();
-        let mut preds = Array1::zeros(n_samples);
-        if let Some(ref root) = self.root {
-            for i in 0..n_samples {
-                preds[i] = root.predict(x.row(i));
-            }
+        match self.root {
+            Some(ref root) => (0..x.nrows())
+                .into_par_iter()
+                .map(|i| root.predict(x.row(i)))
+                .collect::<Vec<f64>>()
+                .into(),
+            None => Array1::zeros(x.nrows()),
         }
-        preds
     }
 }
 
 impl Node {
+    /// Feeds the split/leaf structure (not leaf sizes) into `state`, depth first.
+    fn hash_structure<H: Hasher>(&self, state: &mut H) {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            match node {
+                Node::Leaf {
+                    treatment_effect,
+                    arm_effects,
+                    ..
+                } => {
+                    0u8.hash(state);
+                    treatment_effect.to_bits().hash(state);
+                    arm_effects.len().hash(state);
+                    for effect in arm_effects {
+                        effect.to_bits().hash(state);
+                    }
+                }
+                Node::Internal {
+                    feature_idx,
+                    threshold,
+                    left,
+                    right,
+                    default_left,
+                } => {
+                    1u8.hash(state);
+                    feature_idx.hash(state);
+                    threshold.to_bits().hash(state);
+                    default_left.hash(state);
+                    stack.push(right);
+                    stack.push(left);
+                }
+            }
+        }
+    }
+
     pub fn predict(&self, x: ArrayView1<f64>) -> f64 {
-        match self {
-            Node::Leaf {
-                treatment_effect, ..
-            } => *treatment_effect,
-            Node::Internal {
-                feature_idx,
-                threshold,
-                left,
-                right,
-            } => {
-                if x[*feature_idx] <= *threshold {
-                    left.predict(x)
-                } else {
-                    right.predict(x)
+        self.leaf(x).0
+    }
+
+    /// `(treatment_effect, size)` of the leaf `x` lands in.
+    pub fn leaf(&self, x: ArrayView1<f64>) -> (f64, usize) {
+        let (treatment_effect, size, _) = self.leaf_values(x);
+        (treatment_effect, size)
+    }
+
+    /// Per-arm contrasts of the leaf `x` lands in; empty unless the treatment is
+    /// `Categorical`.
+    pub fn arm_effects(&self, x: ArrayView1<f64>) -> &[f64] {
+        self.leaf_values(x).2
+    }
+
+    /// Effect, size and arm contrasts of the leaf `x` lands in. A NaN split feature
+    /// follows the node's default branch.
+    fn leaf_values(&self, x: ArrayView1<f64>) -> (f64, usize, &[f64]) {
+        let mut node = self;
+        loop {
+            match node {
+                Node::Leaf {
+                    treatment_effect,
+                    size,
+                    arm_effects,
+                } => return (*treatment_effect, *size, arm_effects),
+                Node::Internal {
+                    feature_idx,
+                    threshold,
+                    left,
+                    right,
+                    default_left,
+                } => {
+                    let value = x[*feature_idx];
+                    let go_left = if value.is_nan() {
+                        *default_left
+                    } else {
+                        value <= *threshold
+                    };
+                    node = if go_left { left } else { right };
                 }
             }
         }
@@ -1,9 +1,21 @@
 pub mod forest;
+pub mod diagnostics;
 pub mod validation;
 pub mod visualization;
 pub mod errors;
 pub mod model;
 pub mod linear;
+pub mod policy;
+pub mod stats;
+pub mod calibration;
+pub mod parallel;
+pub mod rules;
+pub mod factorial;
+pub mod rng;
+pub mod persist;
+pub mod meta;
+pub mod propensity;
+pub mod cache;
 
 pub fn analyze_flow() {
     println!("Analyzing flow...");
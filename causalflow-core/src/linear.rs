@@ -1,55 +1,295 @@
-use crate::model::CausalModel;
-use crate::forest::InferenceResult;
-use crate::errors::Result;
-use ndarray::{Array1, ArrayView1, ArrayView2};
+use crate::errors::{CausalFlowError, Result};
+use crate::forest::{EffectScale, InferenceResult, TreatmentKind};
+use crate::model::{
+    validate_binary_treatment, validate_sample_weight, validate_training_data, CausalModel,
+};
+use crate::stats::{student_t_quantile, validate_confidence_level};
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2, Axis};
 
-#[derive(Clone)]
+/// Relative pivot below which a design column counts as collinear with earlier ones.
+const ALIAS_TOLERANCE: f64 = 1e-10;
+
+/// Index of the treatment column in the design `[1, t, x_1..x_k]`.
+const TREATMENT_COLUMN: usize = 1;
+
+/// OLS regression of `y` on `[1, t, x_1..x_k]`; the coefficient on `t` is the
+/// (constant) treatment effect, adjusted for the covariates.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
 pub struct LinearCausalModel {
+    /// Coefficient on the treatment column.
     pub coef: f64,
+    /// Coefficients for `[1, t, x_1..x_k]`. Columns collinear with earlier ones are
+    /// dropped from the solve and get a zero coefficient.
+    #[serde(default)]
+    pub coefficients: Vec<f64>,
+    /// Standard error of `coef`; `None` when the fit leaves no residual degrees of freedom.
+    #[serde(default)]
+    pub std_error: Option<f64>,
+    /// Residual degrees of freedom of the fit.
+    #[serde(default)]
+    pub df_resid: usize,
+    /// Absolute standardized covariate coefficients (`|b_j| * sd(x_j)`), summing to one.
+    #[serde(default)]
+    pub feature_importance: Vec<f64>,
+    #[serde(default)]
+    pub treatment_kind: TreatmentKind,
+    /// L2 penalty added to the Gram diagonal of every term but the intercept; zero is
+    /// plain OLS.
+    #[serde(default)]
+    pub ridge_lambda: f64,
 }
 
 impl LinearCausalModel {
     pub fn new() -> Self {
-        Self { coef: 0.0 }
+        Self {
+            coef: 0.0,
+            coefficients: Vec::new(),
+            std_error: None,
+            df_resid: 0,
+            feature_importance: Vec::new(),
+            treatment_kind: TreatmentKind::Binary,
+            ridge_lambda: 0.0,
+        }
+    }
+
+    /// Ridge regression: `lambda * I` is added to the Gram matrix (intercept excluded)
+    /// before solving, which keeps the fit stable when covariates are collinear or
+    /// outnumber the rows. The penalty also shrinks the treatment coefficient.
+    pub fn ridge(lambda: f64) -> Self {
+        Self {
+            ridge_lambda: lambda,
+            ..Self::new()
+        }
     }
 }
 
-impl CausalModel for LinearCausalModel {
-    fn fit(&mut self, _x: ArrayView2<f64>, t: ArrayView1<f64>, y: ArrayView1<f64>) -> Result<()> {
-        // Dummy implementation: ATE estimation
-        let mut y1_sum = 0.0;
-        let mut y1_count = 0;
-        let mut y0_sum = 0.0;
-        let mut y0_count = 0;
-
-        for (i, &val) in t.iter().enumerate() {
-            if val > 0.5 {
-                y1_sum += y[i];
-                y1_count += 1;
-            } else {
-                y0_sum += y[i];
-                y0_count += 1;
+impl Default for LinearCausalModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Cholesky factor of the Gram matrix, taken column by column in design order.
+pub(crate) struct GramFactor {
+    /// Lower-triangular factor; rows and columns of aliased terms stay zero.
+    lower: Array2<f64>,
+    pub(crate) aliased: Vec<bool>,
+}
+
+impl GramFactor {
+    fn new(gram: &Array2<f64>) -> Self {
+        let p = gram.nrows();
+        let mut lower = Array2::<f64>::zeros((p, p));
+        let mut aliased = vec![false; p];
+        for j in 0..p {
+            let pivot = gram[[j, j]] - (0..j).map(|k| lower[[j, k]].powi(2)).sum::<f64>();
+            if pivot <= ALIAS_TOLERANCE * gram[[j, j]].max(f64::MIN_POSITIVE) {
+                aliased[j] = true;
+                continue;
+            }
+            let diag = pivot.sqrt();
+            lower[[j, j]] = diag;
+            for i in (j + 1)..p {
+                let dot: f64 = (0..j).map(|k| lower[[i, k]] * lower[[j, k]]).sum();
+                lower[[i, j]] = (gram[[i, j]] - dot) / diag;
             }
         }
+        Self { lower, aliased }
+    }
 
-        if y1_count > 0 && y0_count > 0 {
-            self.coef = (y1_sum / y1_count as f64) - (y0_sum / y0_count as f64);
-        } else {
-            self.coef = 0.0;
+    pub(crate) fn rank(&self) -> usize {
+        self.aliased.iter().filter(|&&a| !a).count()
+    }
+
+    /// Solves `L z = b` over the non-aliased terms.
+    fn forward(&self, b: &Array1<f64>) -> Array1<f64> {
+        let p = b.len();
+        let mut z = Array1::<f64>::zeros(p);
+        for i in (0..p).filter(|&i| !self.aliased[i]) {
+            let dot: f64 = (0..i).map(|k| self.lower[[i, k]] * z[k]).sum();
+            z[i] = (b[i] - dot) / self.lower[[i, i]];
+        }
+        z
+    }
+
+    /// Solves `G beta = b`, leaving aliased coefficients at zero.
+    fn solve(&self, b: &Array1<f64>) -> Array1<f64> {
+        let z = self.forward(b);
+        let p = z.len();
+        let mut beta = Array1::<f64>::zeros(p);
+        for i in (0..p).rev().filter(|&i| !self.aliased[i]) {
+            let dot: f64 = ((i + 1)..p).map(|k| self.lower[[k, i]] * beta[k]).sum();
+            beta[i] = (z[i] - dot) / self.lower[[i, i]];
+        }
+        beta
+    }
+
+    /// Diagonal entry `j` of the inverse Gram matrix, i.e. `|L^-1 e_j|^2`.
+    pub(crate) fn inverse_diagonal(&self, j: usize) -> f64 {
+        let mut e = Array1::<f64>::zeros(self.lower.nrows());
+        e[j] = 1.0;
+        self.forward(&e).mapv(|v| v * v).sum()
+    }
+}
+
+/// Least-squares coefficients of `y` on the columns of `design`, via the normal
+/// equations. Columns collinear with earlier ones get a zero coefficient.
+pub(crate) fn least_squares(
+    design: ArrayView2<f64>,
+    y: ArrayView1<f64>,
+) -> Result<(Array1<f64>, GramFactor)> {
+    ridge_least_squares(design, y, 0.0)
+}
+
+/// [`least_squares`] with `lambda` added to every Gram diagonal entry except the
+/// first (intercept) column.
+pub(crate) fn ridge_least_squares(
+    design: ArrayView2<f64>,
+    y: ArrayView1<f64>,
+    lambda: f64,
+) -> Result<(Array1<f64>, GramFactor)> {
+    let mut gram = design.t().dot(&design);
+    gram.diag_mut()
+        .iter_mut()
+        .skip(1)
+        .for_each(|g| *g += lambda);
+    let factor = GramFactor::new(&gram);
+    let beta = factor.solve(&design.t().dot(&y));
+    if beta.iter().any(|b| !b.is_finite()) {
+        return Err(CausalFlowError::Calculation(
+            "OLS solve produced non-finite coefficients".to_string(),
+        ));
+    }
+    Ok((beta, factor))
+}
+
+/// `|b_j| * sd(x_j)` for each column of `x`, normalized to sum to one.
+pub(crate) fn standardized_importance(x: ArrayView2<f64>, coefficients: &[f64]) -> Vec<f64> {
+    let scaled: Vec<f64> = x
+        .columns()
+        .into_iter()
+        .zip(coefficients)
+        .map(|(column, b)| b.abs() * column.std(0.0))
+        .collect();
+    let total: f64 = scaled.iter().sum();
+    if total > 0.0 {
+        scaled.iter().map(|s| s / total).collect()
+    } else {
+        scaled
+    }
+}
+
+impl LinearCausalModel {
+    /// OLS, or weighted least squares when `sample_weight` is given (rows scaled by
+    /// `sqrt(w)`; zero-weight rows do not count toward the residual degrees of freedom).
+    /// With `ridge_lambda > 0` the standard error uses the penalized Gram matrix.
+    fn fit_with_weights(
+        &mut self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+        sample_weight: Option<ArrayView1<f64>>,
+    ) -> Result<()> {
+        validate_training_data(x, t, y)?;
+        if self.treatment_kind == TreatmentKind::Binary {
+            validate_binary_treatment(t)?;
+        }
+        if !self.ridge_lambda.is_finite() || self.ridge_lambda < 0.0 {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "ridge_lambda must be finite and non-negative, got {}",
+                self.ridge_lambda
+            )));
         }
 
+        let (n, k) = x.dim();
+        let mut design = Array2::<f64>::ones((n, k + 2));
+        design.column_mut(TREATMENT_COLUMN).assign(&t);
+        design.slice_mut(ndarray::s![.., 2..]).assign(&x);
+
+        let (design, y, n_obs) = match sample_weight {
+            Some(w) => {
+                let root = w.mapv(f64::sqrt);
+                let n_obs = w.iter().filter(|&&v| v > 0.0).count();
+                (design * root.view().insert_axis(Axis(1)), &y * &root, n_obs)
+            }
+            None => (design, y.to_owned(), n),
+        };
+
+        let (beta, factor) = ridge_least_squares(design.view(), y.view(), self.ridge_lambda)?;
+
+        // An aliased treatment column (e.g. a single arm) leaves the effect at zero
+        self.coef = beta[TREATMENT_COLUMN];
+        self.df_resid = n_obs.saturating_sub(factor.rank());
+        self.std_error = if self.df_resid > 0 && !factor.aliased[TREATMENT_COLUMN] {
+            let residuals = &y - &design.dot(&beta);
+            let sigma2 = residuals.mapv(|r| r * r).sum() / self.df_resid as f64;
+            Some((sigma2 * factor.inverse_diagonal(TREATMENT_COLUMN)).sqrt())
+        } else {
+            None
+        };
+
+        self.feature_importance = standardized_importance(x, &beta.to_vec()[2..]);
+        self.coefficients = beta.to_vec();
         Ok(())
     }
+}
+
+impl CausalModel for LinearCausalModel {
+    fn fit(&mut self, x: ArrayView2<f64>, t: ArrayView1<f64>, y: ArrayView1<f64>) -> Result<()> {
+        self.fit_with_weights(x, t, y, None)
+    }
+
+    fn fit_weighted(
+        &mut self,
+        x: ArrayView2<f64>,
+        t: ArrayView1<f64>,
+        y: ArrayView1<f64>,
+        sample_weight: ArrayView1<f64>,
+    ) -> Result<()> {
+        validate_sample_weight(sample_weight, x.nrows())?;
+        self.fit_with_weights(x, t, y, Some(sample_weight))
+    }
 
     fn predict(&self, x: ArrayView2<f64>) -> Result<InferenceResult> {
+        self.predict_with_level(x, 0.95)
+    }
+
+    /// The model has no treatment-covariate interactions, so every row gets `coef`, with
+    /// the Student-t interval of `coef` at `confidence_level`.
+    fn predict_with_level(
+        &self,
+        x: ArrayView2<f64>,
+        confidence_level: f64,
+    ) -> Result<InferenceResult> {
+        validate_confidence_level(confidence_level)?;
         let n_samples = x.nrows();
+        if !self.coefficients.is_empty() && x.ncols() + 2 != self.coefficients.len() {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "expected {} features, got {}",
+                self.coefficients.len() - 2,
+                x.ncols()
+            )));
+        }
         let predictions = Array1::from_elem(n_samples, self.coef);
-        
+        let half_width = match self.std_error {
+            Some(se) => {
+                student_t_quantile((1.0 + confidence_level) / 2.0, self.df_resid as f64) * se
+            }
+            None => f64::NAN,
+        };
+        let feature_importance = if self.feature_importance.len() == x.ncols() {
+            self.feature_importance.clone()
+        } else {
+            vec![0.0; x.ncols()]
+        };
+
         Ok(InferenceResult {
             predictions,
             mean_effect: self.coef,
-            confidence_intervals: vec![(self.coef - 0.1, self.coef + 0.1); n_samples],
-            feature_importance: vec![0.0; x.ncols()],
+            confidence_intervals: vec![(self.coef - half_width, self.coef + half_width); n_samples],
+            feature_importance,
+            effect_scale: EffectScale::Absolute,
+            n_clipped: 0,
         })
     }
 }
@@ -0,0 +1,289 @@
+use crate::cache::{get_or_fit, nuisance_key};
+use crate::errors::{CausalFlowError, Result};
+use crate::forest::{EffectScale, InferenceResult};
+use crate::linear::{least_squares, standardized_importance};
+use crate::model::{validate_binary_treatment, validate_training_data, CausalModel};
+use crate::propensity::estimate_propensity;
+use crate::stats::{normal_quantile, validate_confidence_level};
+use ndarray::{concatenate, Array1, Array2, ArrayView1, ArrayView2, Axis};
+
+/// Outcome model `E[y | x]` that the meta-learners combine into a CATE estimate.
+///
+/// Fits are cached on the implementing type and the fitting data (see `cache`), so a
+/// fit must be fully determined by them.
+pub trait OutcomeRegressor: Clone + Send + Sync + 'static {
+    fn fit(&mut self, x: ArrayView2<f64>, y: ArrayView1<f64>) -> Result<()>;
+    fn predict(&self, x: ArrayView2<f64>) -> Result<Array1<f64>>;
+    /// Non-negative importance of each column of the fitted `x`, summing to one.
+    fn feature_importance(&self) -> Vec<f64>;
+}
+
+/// OLS regression of `y` on `[1, x_1..x_k]`.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct LinearRegressor {
+    /// Intercept followed by one coefficient per feature; empty until fitted.
+    pub coefficients: Vec<f64>,
+    pub feature_importance: Vec<f64>,
+}
+
+impl OutcomeRegressor for LinearRegressor {
+    fn fit(&mut self, x: ArrayView2<f64>, y: ArrayView1<f64>) -> Result<()> {
+        let design = concatenate![Axis(1), Array2::ones((x.nrows(), 1)), x];
+        let (beta, _) = least_squares(design.view(), y)?;
+        self.feature_importance = standardized_importance(x, &beta.to_vec()[1..]);
+        self.coefficients = beta.to_vec();
+        Ok(())
+    }
+
+    fn predict(&self, x: ArrayView2<f64>) -> Result<Array1<f64>> {
+        let Some((&intercept, slopes)) = self.coefficients.split_first() else {
+            return Err(CausalFlowError::ModelNotFitted);
+        };
+        if slopes.len() != x.ncols() {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "expected {} features, got {}",
+                slopes.len(),
+                x.ncols()
+            )));
+        }
+        Ok(x.dot(&ArrayView1::from(slopes)) + intercept)
+    }
+
+    fn feature_importance(&self) -> Vec<f64> {
+        self.feature_importance.clone()
+    }
+}
+
+/// A copy of `base` fit on `(x, y)`, reused from the nuisance cache when the same
+/// regressor type was already fit on the same data.
+fn fit_cached<R: OutcomeRegressor>(base: &R, x: ArrayView2<f64>, y: ArrayView1<f64>) -> Result<R> {
+    get_or_fit(nuisance_key(std::any::type_name::<R>(), x, y), || {
+        let mut model = base.clone();
+        model.fit(x, y)?;
+        Ok(model)
+    })
+}
+
+/// Per-row CATEs without intervals; the meta-learners do not estimate their variance.
+fn effects_result(predictions: Array1<f64>, feature_importance: Vec<f64>) -> InferenceResult {
+    let n_samples = predictions.len();
+    InferenceResult {
+        mean_effect: predictions.mean().unwrap_or(0.0),
+        predictions,
+        confidence_intervals: vec![(f64::NAN, f64::NAN); n_samples],
+        feature_importance,
+        effect_scale: EffectScale::Absolute,
+        n_clipped: 0,
+    }
+}
+
+/// T-learner: separate outcome models for the treated and control rows; the CATE is
+/// the difference of their predictions.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct TLearner<R> {
+    pub treated: R,
+    pub control: R,
+}
+
+impl<R: OutcomeRegressor> TLearner<R> {
+    pub fn new(base: R) -> Self {
+        Self {
+            treated: base.clone(),
+            control: base,
+        }
+    }
+}
+
+impl<R: OutcomeRegressor> CausalModel for TLearner<R> {
+    fn fit(&mut self, x: ArrayView2<f64>, t: ArrayView1<f64>, y: ArrayView1<f64>) -> Result<()> {
+        validate_training_data(x, t, y)?;
+        validate_binary_treatment(t)?;
+
+        let (treated, control): (Vec<usize>, Vec<usize>) = (0..t.len()).partition(|&i| t[i] > 0.5);
+        if treated.is_empty() || control.is_empty() {
+            return Err(CausalFlowError::InvalidParameter(
+                "the T-learner needs both treated and control rows".to_string(),
+            ));
+        }
+        self.treated = fit_cached(
+            &self.treated,
+            x.select(Axis(0), &treated).view(),
+            y.select(Axis(0), &treated).view(),
+        )?;
+        self.control = fit_cached(
+            &self.control,
+            x.select(Axis(0), &control).view(),
+            y.select(Axis(0), &control).view(),
+        )?;
+        Ok(())
+    }
+
+    fn predict(&self, x: ArrayView2<f64>) -> Result<InferenceResult> {
+        let predictions = self.treated.predict(x)? - self.control.predict(x)?;
+        let feature_importance = self
+            .treated
+            .feature_importance()
+            .iter()
+            .zip(self.control.feature_importance())
+            .map(|(a, b)| (a + b) / 2.0)
+            .collect();
+        Ok(effects_result(predictions, feature_importance))
+    }
+}
+
+/// S-learner: one outcome model with treatment as the last feature; the CATE contrasts
+/// its predictions at `t = 1` and `t = 0`.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct SLearner<R> {
+    pub model: R,
+}
+
+impl<R: OutcomeRegressor> SLearner<R> {
+    pub fn new(base: R) -> Self {
+        Self { model: base }
+    }
+}
+
+/// `x` with a constant treatment column appended.
+fn with_treatment(x: ArrayView2<f64>, t: f64) -> Array2<f64> {
+    concatenate![Axis(1), x, Array2::from_elem((x.nrows(), 1), t)]
+}
+
+impl<R: OutcomeRegressor> CausalModel for SLearner<R> {
+    fn fit(&mut self, x: ArrayView2<f64>, t: ArrayView1<f64>, y: ArrayView1<f64>) -> Result<()> {
+        validate_training_data(x, t, y)?;
+        validate_binary_treatment(t)?;
+
+        let augmented = concatenate![Axis(1), x, t.insert_axis(Axis(1))];
+        self.model = fit_cached(&self.model, augmented.view(), y)?;
+        Ok(())
+    }
+
+    fn predict(&self, x: ArrayView2<f64>) -> Result<InferenceResult> {
+        let predictions = self.model.predict(with_treatment(x, 1.0).view())?
+            - self.model.predict(with_treatment(x, 0.0).view())?;
+        // Drop the treatment column and renormalize over the features
+        let mut feature_importance = self.model.feature_importance();
+        feature_importance.truncate(x.ncols());
+        let total: f64 = feature_importance.iter().sum();
+        if total > 0.0 {
+            feature_importance.iter_mut().for_each(|v| *v /= total);
+        }
+        Ok(effects_result(predictions, feature_importance))
+    }
+}
+
+/// Augmented inverse-propensity weighting (AIPW): per-arm outcome models plus a
+/// logistic propensity model. The ATE stays consistent if either the outcome models
+/// or the propensity model is correctly specified.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct DoublyRobustModel<R> {
+    pub treated: R,
+    pub control: R,
+    /// Mean of the influence-function values of the training rows.
+    pub ate: f64,
+    /// Standard error of `ate`: the standard deviation of the influence-function
+    /// values over `sqrt(n)`.
+    pub std_error: f64,
+    /// Influence-function value of each training row, in row order.
+    pub influence: Vec<f64>,
+}
+
+impl<R: OutcomeRegressor> DoublyRobustModel<R> {
+    pub fn new(base: R) -> Self {
+        Self {
+            treated: base.clone(),
+            control: base,
+            ate: 0.0,
+            std_error: f64::NAN,
+            influence: Vec::new(),
+        }
+    }
+
+    /// Normal interval of the doubly-robust training-set ATE at `confidence_level`.
+    pub fn ate_interval(&self, confidence_level: f64) -> Result<(f64, f64)> {
+        validate_confidence_level(confidence_level)?;
+        let half_width = normal_quantile((1.0 + confidence_level) / 2.0) * self.std_error;
+        Ok((self.ate - half_width, self.ate + half_width))
+    }
+}
+
+impl<R: OutcomeRegressor> CausalModel for DoublyRobustModel<R> {
+    /// `psi_i = mu1(x_i) - mu0(x_i) + t_i (y_i - mu1(x_i)) / e(x_i)
+    ///   - (1 - t_i) (y_i - mu0(x_i)) / (1 - e(x_i))`, with clipped propensities `e`.
+    fn fit(&mut self, x: ArrayView2<f64>, t: ArrayView1<f64>, y: ArrayView1<f64>) -> Result<()> {
+        validate_training_data(x, t, y)?;
+        validate_binary_treatment(t)?;
+
+        let (treated, control): (Vec<usize>, Vec<usize>) = (0..t.len()).partition(|&i| t[i] > 0.5);
+        if treated.is_empty() || control.is_empty() {
+            return Err(CausalFlowError::InvalidParameter(
+                "the doubly-robust estimator needs both treated and control rows".to_string(),
+            ));
+        }
+        self.treated = fit_cached(
+            &self.treated,
+            x.select(Axis(0), &treated).view(),
+            y.select(Axis(0), &treated).view(),
+        )?;
+        self.control = fit_cached(
+            &self.control,
+            x.select(Axis(0), &control).view(),
+            y.select(Axis(0), &control).view(),
+        )?;
+
+        let mu1 = self.treated.predict(x)?;
+        let mu0 = self.control.predict(x)?;
+        let propensity = estimate_propensity(x, t)?;
+        let influence: Array1<f64> = (0..t.len())
+            .map(|i| {
+                let (ti, e) = (t[i], propensity[i]);
+                mu1[i] - mu0[i] + ti * (y[i] - mu1[i]) / e
+                    - (1.0 - ti) * (y[i] - mu0[i]) / (1.0 - e)
+            })
+            .collect();
+
+        let n = influence.len() as f64;
+        self.ate = influence.mean().unwrap_or(0.0);
+        self.std_error = if influence.len() > 1 {
+            influence.std(1.0) / n.sqrt()
+        } else {
+            f64::NAN
+        };
+        self.influence = influence.to_vec();
+        Ok(())
+    }
+
+    fn predict(&self, x: ArrayView2<f64>) -> Result<InferenceResult> {
+        self.predict_with_level(x, 0.95)
+    }
+
+    /// Rows get the outcome models' CATE `mu1(x) - mu0(x)` and `mean_effect` is their
+    /// mean over `x`, so held-out folds are scored on their own rows. The doubly-robust
+    /// ATE needs outcomes and treatments, so it only exists for the training rows (see
+    /// `ate`): every row's interval is that ATE's interval at `confidence_level` (see
+    /// `ate_interval`), whatever `x` is.
+    fn predict_with_level(
+        &self,
+        x: ArrayView2<f64>,
+        confidence_level: f64,
+    ) -> Result<InferenceResult> {
+        let interval = self.ate_interval(confidence_level)?;
+        let predictions = self.treated.predict(x)? - self.control.predict(x)?;
+        let feature_importance = self
+            .treated
+            .feature_importance()
+            .iter()
+            .zip(self.control.feature_importance())
+            .map(|(a, b)| (a + b) / 2.0)
+            .collect();
+        Ok(InferenceResult {
+            confidence_intervals: vec![interval; predictions.len()],
+            mean_effect: predictions.mean().unwrap_or(0.0),
+            predictions,
+            feature_importance,
+            effect_scale: EffectScale::Absolute,
+            n_clipped: 0,
+        })
+    }
+}
@@ -1,8 +1,134 @@
 use crate::forest::InferenceResult;
-use crate::errors::Result;
+use crate::errors::{CausalFlowError, Result};
+use crate::stats::validate_confidence_level;
 use ndarray::{ArrayView1, ArrayView2};
 
-pub trait CausalModel: Send + Sync {
+pub trait CausalModel: Send + Sync + CausalModelClone {
     fn fit(&mut self, x: ArrayView2<f64>, t: ArrayView1<f64>, y: ArrayView1<f64>) -> Result<()>;
     fn predict(&self, x: ArrayView2<f64>) -> Result<InferenceResult>;
+
+    /// Fits with a non-negative weight per row (e.g. survey or importance weights).
+    /// Models that cannot use weights reject them.
+    fn fit_weighted(
+        &mut self,
+        _x: ArrayView2<f64>,
+        _t: ArrayView1<f64>,
+        _y: ArrayView1<f64>,
+        _sample_weight: ArrayView1<f64>,
+    ) -> Result<()> {
+        Err(CausalFlowError::InvalidParameter(
+            "this model does not support sample weights".to_string(),
+        ))
+    }
+
+    /// Predicts with intervals at `confidence_level`. Models without a notion of
+    /// interval width fall back to `predict`.
+    fn predict_with_level(
+        &self,
+        x: ArrayView2<f64>,
+        confidence_level: f64,
+    ) -> Result<InferenceResult> {
+        validate_confidence_level(confidence_level)?;
+        self.predict(x)
+    }
+
+    /// A copy to refit on `x` with one column named `name` appended.
+    /// Models with per-feature settings extend them; the default is a plain clone.
+    fn box_clone_with_feature(&self, _name: &str) -> Box<dyn CausalModel> {
+        self.box_clone()
+    }
+}
+
+/// Cloning through `&dyn CausalModel`, so validation can refit copies of any model
+/// (placebo shuffles, cross-validation folds). Implemented for every `Clone` model.
+pub trait CausalModelClone {
+    fn box_clone(&self) -> Box<dyn CausalModel>;
+}
+
+impl<T: CausalModel + Clone + 'static> CausalModelClone for T {
+    fn box_clone(&self) -> Box<dyn CausalModel> {
+        Box::new(self.clone())
+    }
+}
+
+/// Checks shared by every model's `fit`: non-empty, matching row counts and finite
+/// values.
+pub fn validate_training_data(
+    x: ArrayView2<f64>,
+    t: ArrayView1<f64>,
+    y: ArrayView1<f64>,
+) -> Result<()> {
+    if x.is_empty() || t.is_empty() || y.is_empty() {
+        return Err(CausalFlowError::EmptyData);
+    }
+    if t.len() != x.nrows() || y.len() != x.nrows() {
+        return Err(CausalFlowError::InvalidParameter(format!(
+            "x has {} rows but treatment has {} and outcome {}",
+            x.nrows(),
+            t.len(),
+            y.len()
+        )));
+    }
+    if x.iter()
+        .chain(t.iter())
+        .chain(y.iter())
+        .any(|v| !v.is_finite())
+    {
+        return Err(CausalFlowError::InvalidData);
+    }
+    Ok(())
+}
+
+/// Requires one finite, non-negative weight per row, not all zero.
+pub fn validate_sample_weight(sample_weight: ArrayView1<f64>, n_rows: usize) -> Result<()> {
+    if sample_weight.len() != n_rows {
+        return Err(CausalFlowError::InvalidParameter(format!(
+            "sample_weight has {} entries but x has {} rows",
+            sample_weight.len(),
+            n_rows
+        )));
+    }
+    if sample_weight.iter().any(|&w| !w.is_finite() || w < 0.0) {
+        return Err(CausalFlowError::InvalidParameter(
+            "sample_weight must be finite and non-negative".to_string(),
+        ));
+    }
+    if sample_weight.sum() <= 0.0 {
+        return Err(CausalFlowError::InvalidParameter(
+            "sample_weight must have a positive total".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Requires every treatment value to be an integer arm in `[0, n_arms)`, with at
+/// least two arms.
+pub fn validate_categorical_treatment(t: ArrayView1<f64>, n_arms: usize) -> Result<()> {
+    if n_arms < 2 {
+        return Err(CausalFlowError::InvalidParameter(format!(
+            "a categorical treatment needs at least 2 arms, got {}",
+            n_arms
+        )));
+    }
+    if let Some(&v) = t
+        .iter()
+        .find(|&&v| v.fract() != 0.0 || v < 0.0 || v >= n_arms as f64)
+    {
+        return Err(CausalFlowError::InvalidParameter(format!(
+            "categorical treatment values must be integers in [0, {}), found {}",
+            n_arms, v
+        )));
+    }
+    Ok(())
+}
+
+/// Rejects treatment values other than 0 and 1.
+pub fn validate_binary_treatment(t: ArrayView1<f64>) -> Result<()> {
+    if let Some(&v) = t
+        .iter()
+        .find(|&&v| v.abs() > f64::EPSILON && (v - 1.0).abs() > f64::EPSILON)
+    {
+        return Err(CausalFlowError::InvalidTreatment(v));
+    }
+    Ok(())
 }
@@ -0,0 +1,47 @@
+use crate::errors::{CausalFlowError, Result};
+use std::sync::OnceLock;
+
+/// Environment variable capping the number of threads used anywhere in CausalFlow.
+pub const NUM_THREADS_ENV: &str = "CAUSALFLOW_NUM_THREADS";
+
+static THREAD_CEILING: OnceLock<Option<usize>> = OnceLock::new();
+
+/// Reads `CAUSALFLOW_NUM_THREADS` once and sizes the global rayon pool to it.
+///
+/// Later calls return the ceiling read the first time. Per-model `n_jobs` values are
+/// clamped to this ceiling.
+pub fn init_thread_pool_from_env() -> Result<Option<usize>> {
+    if let Some(ceiling) = THREAD_CEILING.get() {
+        return Ok(*ceiling);
+    }
+
+    let ceiling = match std::env::var(NUM_THREADS_ENV) {
+        Ok(value) => {
+            let n = value
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| {
+                    CausalFlowError::InvalidParameter(format!(
+                        "{} must be a positive integer, got '{}'",
+                        NUM_THREADS_ENV, value
+                    ))
+                })?;
+            // The global pool can only be built once per process; if something else
+            // already built it, the ceiling still applies to per-model pools.
+            let _ = rayon::ThreadPoolBuilder::new()
+                .num_threads(n)
+                .build_global();
+            Some(n)
+        }
+        Err(_) => None,
+    };
+
+    Ok(*THREAD_CEILING.get_or_init(|| ceiling))
+}
+
+/// Thread ceiling set from the environment, if any.
+pub fn thread_ceiling() -> Option<usize> {
+    THREAD_CEILING.get().copied().flatten()
+}
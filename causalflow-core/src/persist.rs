@@ -0,0 +1,268 @@
+use crate::errors::{CausalFlowError, Result};
+use crate::forest::{CausalForest, CausalTree, Node};
+use std::collections::VecDeque;
+use std::path::Path;
+
+/// Version written by `CausalForest::save`; `load` rejects any other value.
+pub const FOREST_FORMAT_VERSION: u32 = 1;
+
+/// On-disk layout of a `CausalTree`: nodes in breadth-first order, children always
+/// after their parent and referenced by index.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct SerializedTree {
+    nodes: Vec<SerializedNode>,
+    feature_importance: Vec<f64>,
+    #[serde(default)]
+    oob_indices: Vec<usize>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SerializedNode {
+    /// `treatment_effect` is `None` for undefined (NaN) leaves, which JSON cannot hold;
+    /// the same goes for each entry of `arm_effects`.
+    Leaf {
+        treatment_effect: Option<f64>,
+        size: usize,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        arm_effects: Vec<Option<f64>>,
+    },
+    Internal {
+        feature_idx: usize,
+        threshold: f64,
+        left: usize,
+        right: usize,
+        #[serde(default)]
+        default_left: bool,
+    },
+}
+
+impl From<CausalTree> for SerializedTree {
+    fn from(tree: CausalTree) -> Self {
+        let mut nodes = Vec::new();
+        let mut queue: VecDeque<&Node> = tree.root.as_deref().into_iter().collect();
+        while let Some(node) = queue.pop_front() {
+            nodes.push(match node {
+                Node::Leaf {
+                    treatment_effect,
+                    size,
+                    arm_effects,
+                } => SerializedNode::Leaf {
+                    treatment_effect: Some(*treatment_effect).filter(|v| !v.is_nan()),
+                    size: *size,
+                    arm_effects: arm_effects
+                        .iter()
+                        .map(|&v| Some(v).filter(|v| !v.is_nan()))
+                        .collect(),
+                },
+                Node::Internal {
+                    feature_idx,
+                    threshold,
+                    left,
+                    right,
+                    default_left,
+                } => {
+                    // Children are numbered in the order they will be dequeued
+                    let left_idx = nodes.len() + queue.len() + 1;
+                    queue.push_back(left);
+                    queue.push_back(right);
+                    SerializedNode::Internal {
+                        feature_idx: *feature_idx,
+                        threshold: *threshold,
+                        left: left_idx,
+                        right: left_idx + 1,
+                        default_left: *default_left,
+                    }
+                }
+            });
+        }
+        Self {
+            nodes,
+            feature_importance: tree.feature_importance,
+            oob_indices: tree.oob_indices,
+        }
+    }
+}
+
+impl TryFrom<SerializedTree> for CausalTree {
+    type Error = String;
+
+    fn try_from(tree: SerializedTree) -> std::result::Result<Self, String> {
+        // Boxed bottom-up; every child index must point past its parent
+        let n = tree.nodes.len();
+        let mut built: Vec<Option<Box<Node>>> = (0..n).map(|_| None).collect();
+        for (idx, node) in tree.nodes.into_iter().enumerate().rev() {
+            let node = match node {
+                SerializedNode::Leaf {
+                    treatment_effect,
+                    size,
+                    arm_effects,
+                } => Node::Leaf {
+                    treatment_effect: treatment_effect.unwrap_or(f64::NAN),
+                    size,
+                    arm_effects: arm_effects
+                        .into_iter()
+                        .map(|v| v.unwrap_or(f64::NAN))
+                        .collect(),
+                },
+                SerializedNode::Internal {
+                    feature_idx,
+                    threshold,
+                    left,
+                    right,
+                    default_left,
+                } => {
+                    let mut child = |child_idx: usize| {
+                        (child_idx > idx && child_idx < n)
+                            .then(|| built[child_idx].take())
+                            .flatten()
+                            .ok_or_else(|| {
+                                format!("node {} has an invalid child {}", idx, child_idx)
+                            })
+                    };
+                    Node::Internal {
+                        feature_idx,
+                        threshold,
+                        left: child(left)?,
+                        right: child(right)?,
+                        default_left,
+                    }
+                }
+            };
+            built[idx] = Some(Box::new(node));
+        }
+        Ok(CausalTree {
+            root: built.into_iter().next().flatten(),
+            feature_importance: tree.feature_importance,
+            oob_indices: tree.oob_indices,
+        })
+    }
+}
+
+#[derive(serde::Serialize)]
+struct ForestFileRef<'a> {
+    format_version: u32,
+    forest: &'a CausalForest,
+}
+
+#[derive(serde::Deserialize)]
+struct ForestFile {
+    format_version: u32,
+    forest: serde_json::Value,
+}
+
+impl CausalForest {
+    /// Writes the forest (configuration and all trees) as versioned JSON.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string(&ForestFileRef {
+            format_version: FOREST_FORMAT_VERSION,
+            forest: self,
+        })
+        .map_err(|e| CausalFlowError::Internal(e.to_string()))?;
+        std::fs::write(path, json).map_err(|e| {
+            CausalFlowError::Internal(format!("cannot write {}: {}", path.display(), e))
+        })
+    }
+
+    /// Reads a forest written by `save`. Files from another format version are rejected
+    /// before the trees are parsed.
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            CausalFlowError::Internal(format!("cannot read {}: {}", path.display(), e))
+        })?;
+        let file: ForestFile = serde_json::from_str(&json).map_err(|e| {
+            CausalFlowError::Internal(format!("{} is not a saved forest: {}", path.display(), e))
+        })?;
+        if file.format_version != FOREST_FORMAT_VERSION {
+            return Err(CausalFlowError::Internal(format!(
+                "unsupported forest format_version {} (expected {})",
+                file.format_version, FOREST_FORMAT_VERSION
+            )));
+        }
+        serde_json::from_value(file.forest).map_err(|e| {
+            CausalFlowError::Internal(format!("corrupt forest in {}: {}", path.display(), e))
+        })
+    }
+}
+
+/// Version of the layout written by `CausalForest::to_structure_json`.
+pub const STRUCTURE_FORMAT_VERSION: u32 = 1;
+
+/// `root` as a nested JSON object. Built bottom-up with an explicit stack, like
+/// `build_tree`, so deep trees do not recurse once per level.
+fn node_structure(root: &Node, labels: &[String]) -> serde_json::Value {
+    // `(node, children_done)`: internal nodes are visited again after both subtrees,
+    // whose `(value, size)` pairs are then on top of `done`
+    let mut pending = vec![(root, false)];
+    let mut done: Vec<(serde_json::Value, usize)> = Vec::new();
+    while let Some((node, children_done)) = pending.pop() {
+        match node {
+            Node::Leaf {
+                treatment_effect,
+                size,
+                arm_effects,
+            } => {
+                // NaN (undefined) leaf effects become null
+                let mut leaf =
+                    serde_json::json!({ "treatment_effect": treatment_effect, "size": size });
+                if !arm_effects.is_empty() {
+                    leaf["arm_effects"] = serde_json::json!(arm_effects);
+                }
+                done.push((leaf, *size));
+            }
+            Node::Internal { left, right, .. } if !children_done => {
+                pending.push((node, true));
+                pending.push((right, false));
+                pending.push((left, false));
+            }
+            Node::Internal {
+                feature_idx,
+                threshold,
+                default_left,
+                ..
+            } => {
+                let (right, right_size) = done.pop().expect("right subtree was built");
+                let (left, left_size) = done.pop().expect("left subtree was built");
+                let size = left_size + right_size;
+                done.push((
+                    serde_json::json!({
+                        "feature_idx": feature_idx,
+                        "feature": labels.get(*feature_idx),
+                        "threshold": threshold,
+                        "missing_goes_left": default_left,
+                        "size": size,
+                        "left": left,
+                        "right": right,
+                    }),
+                    size,
+                ));
+            }
+        }
+    }
+    done.pop().expect("the root was built").0
+}
+
+impl CausalForest {
+    /// The learned trees as nested JSON for inspection. Internal nodes hold
+    /// `feature_idx`, `feature` (its name) and `threshold`, with rows at or below the
+    /// threshold going `left` and missing values following `missing_goes_left`; leaves
+    /// hold `treatment_effect` on the fitted scale (`null` when undefined). Every node
+    /// reports its estimation-row `size`.
+    pub fn to_structure_json(&self) -> String {
+        let labels = self.feature_labels();
+        let trees: Vec<serde_json::Value> = self
+            .trees
+            .iter()
+            .map(|tree| match &tree.root {
+                Some(root) => node_structure(root, &labels),
+                None => serde_json::Value::Null,
+            })
+            .collect();
+        serde_json::json!({
+            "format_version": STRUCTURE_FORMAT_VERSION,
+            "feature_names": labels,
+            "trees": trees,
+        })
+        .to_string()
+    }
+}
@@ -0,0 +1,195 @@
+use crate::calibration::IsotonicMap;
+use crate::errors::{CausalFlowError, Result};
+use ndarray::ArrayView1;
+
+/// Objective used to pick a treatment cutoff on held-out data.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThresholdMetric {
+    /// Maximize the total estimated uplift of treating everyone above the cutoff.
+    Uplift,
+    /// Maximize total uplift while treating at most this many units.
+    Budget(usize),
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct ThresholdResult {
+    pub cutoff: f64,
+    pub uplift: f64,
+    pub n_treated: usize,
+}
+
+/// Finds the predicted-effect cutoff that maximizes realized uplift on a holdout set.
+///
+/// Units are ranked by predicted effect; for every top-k prefix the uplift is the
+/// difference of observed treated/control outcome means within the prefix, scaled by k.
+/// Units whose predicted effect is at least `cutoff` should be treated.
+///
+/// ```
+/// use causalflow_core::policy::{calibrate_threshold, ThresholdMetric};
+/// use ndarray::array;
+///
+/// // Treatment helps the four highest-ranked units by 2 and hurts the rest by 1
+/// let predicted = array![8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 2.0, 1.0];
+/// let t = array![1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0];
+/// let y = array![2.0, 0.0, 2.0, 0.0, -1.0, 0.0, -1.0, 0.0];
+///
+/// let best = calibrate_threshold(predicted.view(), t.view(), y.view(), ThresholdMetric::Uplift)
+///     .unwrap();
+/// assert_eq!((best.cutoff, best.n_treated), (5.0, 4));
+/// assert_eq!(best.uplift, 8.0);
+///
+/// // A budget of three caps the prefix: (2 - 0) * 3 beats (2 - 0) * 2
+/// let capped =
+///     calibrate_threshold(predicted.view(), t.view(), y.view(), ThresholdMetric::Budget(3))
+///         .unwrap();
+/// assert_eq!((capped.cutoff, capped.n_treated, capped.uplift), (6.0, 3, 6.0));
+/// ```
+pub fn calibrate_threshold(
+    predicted: ArrayView1<f64>,
+    t: ArrayView1<f64>,
+    y: ArrayView1<f64>,
+    metric: ThresholdMetric,
+) -> Result<ThresholdResult> {
+    let n = predicted.len();
+    if n == 0 {
+        return Err(CausalFlowError::EmptyData);
+    }
+    if t.len() != n || y.len() != n {
+        return Err(CausalFlowError::InvalidParameter(format!(
+            "holdout lengths differ: {} predictions, {} treatments, {} outcomes",
+            n,
+            t.len(),
+            y.len()
+        )));
+    }
+
+    let max_k = match metric {
+        ThresholdMetric::Uplift => n,
+        ThresholdMetric::Budget(budget) => budget.min(n),
+    };
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| predicted[b].total_cmp(&predicted[a]));
+
+    let mut best = ThresholdResult {
+        cutoff: f64::INFINITY,
+        uplift: 0.0,
+        n_treated: 0,
+    };
+    let (mut y1_sum, mut y1_count, mut y0_sum, mut y0_count) = (0.0, 0usize, 0.0, 0usize);
+    for (k, &i) in order.iter().take(max_k).enumerate() {
+        if t[i] > 0.5 {
+            y1_sum += y[i];
+            y1_count += 1;
+        } else {
+            y0_sum += y[i];
+            y0_count += 1;
+        }
+
+        // Only cut between distinct predicted values
+        if k + 1 < n && predicted[order[k + 1]] == predicted[i] {
+            continue;
+        }
+        if y1_count == 0 || y0_count == 0 {
+            continue;
+        }
+
+        let n_treated = k + 1;
+        let uplift = (y1_sum / y1_count as f64 - y0_sum / y0_count as f64) * n_treated as f64;
+        if uplift > best.uplift {
+            best = ThresholdResult {
+                cutoff: predicted[i],
+                uplift,
+                n_treated,
+            };
+        }
+    }
+
+    Ok(best)
+}
+
+/// Segment-level effects before and after forcing them to be non-decreasing in a
+/// user-specified tier order.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct MonotoneSegments {
+    /// Segment labels in tier order, lowest tier first.
+    pub segments: Vec<i64>,
+    pub sizes: Vec<usize>,
+    /// Mean predicted effect of each segment.
+    pub effects: Vec<f64>,
+    /// Size-weighted isotonic fit of `effects`, non-decreasing along `segments`.
+    pub monotone_effects: Vec<f64>,
+    /// Largest absolute change made to any segment's effect.
+    pub max_adjustment: f64,
+    /// Unit-weighted mean absolute change.
+    pub mean_adjustment: f64,
+}
+
+/// Makes segment effects non-decreasing along `order` (e.g. policy tiers) while
+/// changing them as little as possible in size-weighted squared error.
+///
+/// `segments` holds each unit's segment label; every label must appear in `order` and
+/// every ordered segment must contain at least one unit.
+pub fn monotone_segment_effects(
+    predicted: ArrayView1<f64>,
+    segments: &[i64],
+    order: &[i64],
+) -> Result<MonotoneSegments> {
+    if predicted.len() != segments.len() {
+        return Err(CausalFlowError::InvalidParameter(format!(
+            "segments has {} entries but there are {} predictions",
+            segments.len(),
+            predicted.len()
+        )));
+    }
+    if order.is_empty() {
+        return Err(CausalFlowError::EmptyData);
+    }
+
+    let mut sums = vec![0.0; order.len()];
+    let mut sizes = vec![0usize; order.len()];
+    for (&effect, label) in predicted.iter().zip(segments) {
+        let tier = order.iter().position(|s| s == label).ok_or_else(|| {
+            CausalFlowError::InvalidParameter(format!("segment {} is not in the ordering", label))
+        })?;
+        sums[tier] += effect;
+        sizes[tier] += 1;
+    }
+    if let Some(tier) = sizes.iter().position(|&n| n == 0) {
+        return Err(CausalFlowError::InvalidParameter(format!(
+            "segment {} has no units",
+            order[tier]
+        )));
+    }
+
+    let effects: Vec<f64> = sums
+        .iter()
+        .zip(&sizes)
+        .map(|(s, &n)| s / n as f64)
+        .collect();
+    let weights: Vec<f64> = sizes.iter().map(|&n| n as f64).collect();
+    let tiers: Vec<f64> = (0..order.len()).map(|i| i as f64).collect();
+    let monotone_effects = IsotonicMap::fit(&tiers, &effects, &weights).values;
+
+    let changes: Vec<f64> = effects
+        .iter()
+        .zip(&monotone_effects)
+        .map(|(a, b)| (a - b).abs())
+        .collect();
+    let max_adjustment = changes.iter().cloned().fold(0.0, f64::max);
+    let mean_adjustment = changes
+        .iter()
+        .zip(&weights)
+        .map(|(c, w)| c * w)
+        .sum::<f64>()
+        / predicted.len() as f64;
+
+    Ok(MonotoneSegments {
+        segments: order.to_vec(),
+        sizes,
+        effects,
+        monotone_effects,
+        max_adjustment,
+        mean_adjustment,
+    })
+}
@@ -0,0 +1,125 @@
+use crate::cache::{get_or_fit, nuisance_key};
+use crate::errors::{CausalFlowError, Result};
+use crate::linear::least_squares;
+use crate::model::validate_binary_treatment;
+use ndarray::{concatenate, Array1, Array2, ArrayView1, ArrayView2, Axis};
+
+/// Propensities are clipped to `[PROPENSITY_CLIP, 1 - PROPENSITY_CLIP]` so that a few
+/// nearly deterministic units cannot dominate the inverse-propensity weights.
+pub const PROPENSITY_CLIP: f64 = 0.01;
+
+const MAX_NEWTON_STEPS: usize = 50;
+const NEWTON_TOLERANCE: f64 = 1e-8;
+
+fn sigmoid(v: f64) -> f64 {
+    1.0 / (1.0 + (-v).exp())
+}
+
+/// Logistic-regression model of `P(T = 1 | x)`, the shared propensity backend for the
+/// weighting estimators.
+///
+/// ```
+/// use causalflow_core::errors::CausalFlowError;
+/// use causalflow_core::propensity::{LogisticPropensity, PROPENSITY_CLIP};
+/// use ndarray::{array, Array2};
+///
+/// let mut model = LogisticPropensity::new();
+/// assert!(matches!(
+///     model.predict_proba(Array2::zeros((1, 1)).view()),
+///     Err(CausalFlowError::ModelNotFitted)
+/// ));
+///
+/// // Treatment is perfectly separated at x = 0.5, so the raw probabilities reach 0 and 1
+/// let x = array![[0.0], [0.1], [0.2], [0.3], [0.4], [0.6], [0.7], [0.8], [0.9], [1.0]];
+/// let t = array![0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0];
+/// model.fit(x.view(), t.view()).unwrap();
+/// let p = model.predict_proba(array![[-10.0], [0.5], [10.0]].view()).unwrap();
+/// assert_eq!(p[0], PROPENSITY_CLIP);
+/// assert!((p[1] - 0.5).abs() < 1e-6);
+/// assert_eq!(p[2], 1.0 - PROPENSITY_CLIP);
+///
+/// assert!(matches!(
+///     model.predict_proba(Array2::zeros((1, 2)).view()),
+///     Err(CausalFlowError::InvalidParameter(_))
+/// ));
+/// assert!(matches!(
+///     model.fit(x.view(), (&t * 2.0).view()),
+///     Err(CausalFlowError::InvalidTreatment(v)) if v == 2.0
+/// ));
+/// ```
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+pub struct LogisticPropensity {
+    /// Intercept followed by one coefficient per feature; empty until fitted.
+    pub coefficients: Vec<f64>,
+}
+
+impl LogisticPropensity {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fits by Newton's method (iteratively reweighted least squares). Near-separable
+    /// data stops at the last finite step rather than diverging. Treatment must be
+    /// binary.
+    pub fn fit(&mut self, x: ArrayView2<f64>, t: ArrayView1<f64>) -> Result<()> {
+        if x.is_empty() || t.is_empty() {
+            return Err(CausalFlowError::EmptyData);
+        }
+        if t.len() != x.nrows() {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "x has {} rows but treatment has {}",
+                x.nrows(),
+                t.len()
+            )));
+        }
+        if x.iter().chain(t.iter()).any(|v| !v.is_finite()) {
+            return Err(CausalFlowError::InvalidData);
+        }
+        validate_binary_treatment(t)?;
+
+        let design = concatenate![Axis(1), Array2::ones((x.nrows(), 1)), x];
+        let mut beta = Array1::<f64>::zeros(design.ncols());
+        for _ in 0..MAX_NEWTON_STEPS {
+            let p = design.dot(&beta).mapv(sigmoid);
+            // Newton step: (X'WX)^-1 X'(t - p) as least squares on sqrt(W)-scaled rows
+            let w = p.mapv(|v| (v * (1.0 - v)).max(1e-10).sqrt());
+            let scaled = &design * &w.clone().insert_axis(Axis(1));
+            let target = (&t - &p) / &w;
+            let Ok((step, _)) = least_squares(scaled.view(), target.view()) else {
+                break;
+            };
+            beta += &step;
+            if step.iter().all(|s| s.abs() < NEWTON_TOLERANCE) {
+                break;
+            }
+        }
+        self.coefficients = beta.to_vec();
+        Ok(())
+    }
+
+    /// `P(T = 1 | x)` for every row of `x`, clipped with `PROPENSITY_CLIP`.
+    pub fn predict_proba(&self, x: ArrayView2<f64>) -> Result<Array1<f64>> {
+        let Some((&intercept, slopes)) = self.coefficients.split_first() else {
+            return Err(CausalFlowError::ModelNotFitted);
+        };
+        if slopes.len() != x.ncols() {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "expected {} features, got {}",
+                slopes.len(),
+                x.ncols()
+            )));
+        }
+        Ok((x.dot(&ArrayView1::from(slopes)) + intercept)
+            .mapv(|v| sigmoid(v).clamp(PROPENSITY_CLIP, 1.0 - PROPENSITY_CLIP)))
+    }
+}
+
+/// Propensities of the training rows themselves: a `LogisticPropensity` fit on
+/// `(x, t)` and evaluated on `x`. Results are cached on `(x, t)` (see `cache`).
+pub fn estimate_propensity(x: ArrayView2<f64>, t: ArrayView1<f64>) -> Result<Array1<f64>> {
+    get_or_fit(nuisance_key("logistic-propensity", x, t), || {
+        let mut model = LogisticPropensity::new();
+        model.fit(x, t)?;
+        model.predict_proba(x)
+    })
+}
@@ -0,0 +1,129 @@
+use crate::forest::{CausalTree, Node};
+use std::collections::HashMap;
+
+/// One feature's range in a rule: `lower < x <= upper`, either side optional.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct RuleCondition {
+    pub feature_idx: usize,
+    pub lower: Option<f64>,
+    pub upper: Option<f64>,
+}
+
+/// A root-to-leaf path simplified to one range per feature, with its leaf effect.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct EffectRule {
+    pub conditions: Vec<RuleCondition>,
+    /// Support-weighted mean leaf effect over every tree containing the rule.
+    pub mean_effect: f64,
+    /// Mean number of estimation units in the rule's leaves.
+    pub support: f64,
+    /// Number of trees whose leaves carry exactly this rule.
+    pub frequency: usize,
+}
+
+impl EffectRule {
+    /// Human-readable condition such as `age > 50.000 AND income <= 3.200`.
+    pub fn describe(&self, feature_names: &[String]) -> String {
+        if self.conditions.is_empty() {
+            return "(all units)".to_string();
+        }
+        let name = |i: usize| {
+            feature_names
+                .get(i)
+                .cloned()
+                .unwrap_or_else(|| format!("Feature {}", i))
+        };
+        self.conditions
+            .iter()
+            .map(|c| match (c.lower, c.upper) {
+                (Some(lo), Some(hi)) => format!("{:.3} < {} <= {:.3}", lo, name(c.feature_idx), hi),
+                (Some(lo), None) => format!("{} > {:.3}", name(c.feature_idx), lo),
+                (None, Some(hi)) => format!("{} <= {:.3}", name(c.feature_idx), hi),
+                (None, None) => name(c.feature_idx),
+            })
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    }
+}
+
+/// Collects every leaf of every tree as a rule, merges identical rules across trees
+/// and returns the `top_k` with the largest mean effect.
+pub fn mine_rules(trees: &[CausalTree], n_features: usize, top_k: usize) -> Vec<EffectRule> {
+    type Bounds = Vec<(Option<f64>, Option<f64>)>;
+    // Keyed by the bounds' bit patterns. While merging, `mean_effect` holds the
+    // size-weighted effect sum and `support` the total size.
+    let mut merged: HashMap<Vec<(usize, u64, u64)>, EffectRule> = HashMap::new();
+
+    for tree in trees {
+        let Some(root) = &tree.root else { continue };
+        let mut stack: Vec<(&Node, Bounds)> = vec![(root, vec![(None, None); n_features])];
+        while let Some((node, bounds)) = stack.pop() {
+            match node {
+                Node::Leaf {
+                    treatment_effect,
+                    size,
+                    ..
+                } => {
+                    let conditions: Vec<RuleCondition> = bounds
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, (lo, hi))| lo.is_some() || hi.is_some())
+                        .map(|(feature_idx, &(lower, upper))| RuleCondition {
+                            feature_idx,
+                            lower,
+                            upper,
+                        })
+                        .collect();
+                    let key = conditions
+                        .iter()
+                        .map(|c| {
+                            (
+                                c.feature_idx,
+                                c.lower.map_or(u64::MAX, f64::to_bits),
+                                c.upper.map_or(u64::MAX, f64::to_bits),
+                            )
+                        })
+                        .collect();
+                    let weight = (*size).max(1) as f64;
+                    let rule = merged.entry(key).or_insert(EffectRule {
+                        conditions,
+                        mean_effect: 0.0,
+                        support: 0.0,
+                        frequency: 0,
+                    });
+                    rule.mean_effect += treatment_effect * weight;
+                    rule.support += weight;
+                    rule.frequency += 1;
+                }
+                Node::Internal {
+                    feature_idx,
+                    threshold,
+                    left,
+                    right,
+                    ..
+                } => {
+                    let mut left_bounds = bounds.clone();
+                    let upper = &mut left_bounds[*feature_idx].1;
+                    *upper = Some(upper.map_or(*threshold, |u| u.min(*threshold)));
+                    let mut right_bounds = bounds;
+                    let lower = &mut right_bounds[*feature_idx].0;
+                    *lower = Some(lower.map_or(*threshold, |l| l.max(*threshold)));
+                    stack.push((right, right_bounds));
+                    stack.push((left, left_bounds));
+                }
+            }
+        }
+    }
+
+    let mut rules: Vec<EffectRule> = merged
+        .into_values()
+        .map(|mut rule| {
+            rule.mean_effect /= rule.support;
+            rule.support /= rule.frequency as f64;
+            rule
+        })
+        .collect();
+    rules.sort_by(|a, b| b.mean_effect.total_cmp(&a.mean_effect));
+    rules.truncate(top_k);
+    rules
+}
@@ -0,0 +1,167 @@
+use crate::errors::{CausalFlowError, Result};
+use ndarray::ArrayView1;
+use rand::thread_rng;
+use rand::Rng;
+use std::collections::HashMap;
+
+/// Fewest distinct clusters a cluster bootstrap will accept.
+pub const MIN_BOOTSTRAP_CLUSTERS: usize = 5;
+
+/// Inverse CDF of the standard normal distribution (Acklam's rational approximation).
+pub fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239e0,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838e0,
+        -2.549732539343734e0,
+        4.374664141464968e0,
+        2.938163982698783e0,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996e0,
+        3.754408661907416e0,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    if p <= 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if p >= 1.0 {
+        return f64::INFINITY;
+    }
+
+    if p < P_LOW {
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    } else if p <= 1.0 - P_LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        -normal_quantile(1.0 - p)
+    }
+}
+
+/// Inverse CDF of Student's t distribution with `df` degrees of freedom.
+///
+/// Exact for 1 and 2 degrees of freedom, Cornish-Fisher expansion otherwise.
+pub fn student_t_quantile(p: f64, df: f64) -> f64 {
+    if df <= 0.0 {
+        return f64::NAN;
+    }
+    if (df - 1.0).abs() < f64::EPSILON {
+        return (std::f64::consts::PI * (p - 0.5)).tan();
+    }
+    if (df - 2.0).abs() < f64::EPSILON {
+        return (2.0 * p - 1.0) / (2.0 * p * (1.0 - p)).sqrt();
+    }
+
+    let z = normal_quantile(p);
+    let z3 = z.powi(3);
+    let z5 = z.powi(5);
+    let z7 = z.powi(7);
+    let z9 = z.powi(9);
+    z + (z3 + z) / (4.0 * df)
+        + (5.0 * z5 + 16.0 * z3 + 3.0 * z) / (96.0 * df.powi(2))
+        + (3.0 * z7 + 19.0 * z5 + 17.0 * z3 - 15.0 * z) / (384.0 * df.powi(3))
+        + (79.0 * z9 + 776.0 * z7 + 1482.0 * z5 - 1920.0 * z3 - 945.0 * z) / (92160.0 * df.powi(4))
+}
+
+pub fn validate_confidence_level(level: f64) -> Result<()> {
+    if level > 0.0 && level < 1.0 {
+        Ok(())
+    } else {
+        Err(CausalFlowError::InvalidParameter(format!(
+            "confidence level must be in (0, 1), got {}",
+            level
+        )))
+    }
+}
+
+/// Linearly interpolated `q`-quantile of an ascending-sorted slice.
+pub fn sorted_quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let pos = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let lo = pos.floor() as usize;
+    let hi = pos.ceil() as usize;
+    sorted[lo] + (pos - lo as f64) * (sorted[hi] - sorted[lo])
+}
+
+/// Percentile confidence interval for the mean of `values`, resampling whole clusters
+/// with replacement so within-cluster correlation is respected.
+pub fn cluster_bootstrap_mean_ci(
+    values: ArrayView1<f64>,
+    clusters: &[i64],
+    n_bootstrap: usize,
+    confidence_level: f64,
+) -> Result<(f64, f64)> {
+    validate_confidence_level(confidence_level)?;
+    if clusters.len() != values.len() {
+        return Err(CausalFlowError::InvalidParameter(format!(
+            "cluster ids have {} entries but data has {} rows",
+            clusters.len(),
+            values.len()
+        )));
+    }
+    if n_bootstrap == 0 {
+        return Err(CausalFlowError::InvalidParameter(
+            "n_bootstrap must be at least 1".to_string(),
+        ));
+    }
+
+    let mut totals: HashMap<i64, (f64, usize)> = HashMap::new();
+    for (&c, &v) in clusters.iter().zip(values.iter()) {
+        let entry = totals.entry(c).or_insert((0.0, 0));
+        entry.0 += v;
+        entry.1 += 1;
+    }
+    if totals.len() < MIN_BOOTSTRAP_CLUSTERS {
+        return Err(CausalFlowError::InvalidParameter(format!(
+            "cluster bootstrap needs at least {} clusters, got {}",
+            MIN_BOOTSTRAP_CLUSTERS,
+            totals.len()
+        )));
+    }
+
+    let totals: Vec<(f64, usize)> = totals.into_values().collect();
+    let mut rng = thread_rng();
+    let mut means: Vec<f64> = (0..n_bootstrap)
+        .map(|_| {
+            let (mut sum, mut count) = (0.0, 0);
+            for _ in 0..totals.len() {
+                let (s, c) = totals[rng.gen_range(0..totals.len())];
+                sum += s;
+                count += c;
+            }
+            sum / count as f64
+        })
+        .collect();
+    means.sort_by(|a, b| a.total_cmp(b));
+
+    let alpha = 1.0 - confidence_level;
+    Ok((
+        sorted_quantile(&means, alpha / 2.0),
+        sorted_quantile(&means, 1.0 - alpha / 2.0),
+    ))
+}
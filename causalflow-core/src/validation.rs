@@ -1,50 +1,560 @@
+use crate::errors::{CausalFlowError, Result};
 use crate::forest::CausalForest;
-use ndarray::{ArrayView1, ArrayView2};
+use crate::model::CausalModel;
+use ndarray::{concatenate, Array1, ArrayView1, ArrayView2, Axis};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng, SeedableRng};
 
 pub struct ValidationResult {
     pub is_robust: bool,
     pub message: String,
+    /// Permutation p-value of the placebo test, when permutations were run.
+    pub p_value: Option<f64>,
+    /// Mean held-out effect of each cross-validation fold, when folds were run.
+    pub fold_effects: Vec<f64>,
+    /// Absolute placebo ATE (the mean over permutations when several were run); NaN
+    /// when the placebo test did not run.
+    pub placebo_effect: f64,
+    /// Absolute ATE of the model under validation; NaN when it could not predict.
+    pub estimated_effect: f64,
+    /// `placebo_effect / estimated_effect`: near 0 for a real effect, near 1 or above
+    /// when shuffled treatment explains as much. NaN when either is unavailable or the
+    /// estimated effect is zero.
+    pub robustness_score: f64,
 }
 
+impl ValidationResult {
+    /// A check without placebo numbers or folds.
+    fn new(is_robust: bool, message: String) -> Self {
+        Self {
+            is_robust,
+            message,
+            p_value: None,
+            fold_effects: Vec::new(),
+            placebo_effect: f64::NAN,
+            estimated_effect: f64::NAN,
+            robustness_score: f64::NAN,
+        }
+    }
+
+    /// Placebo-test result carrying the two effects and their ratio.
+    fn placebo(
+        is_robust: bool,
+        message: String,
+        placebo_effect: f64,
+        estimated_effect: f64,
+    ) -> Self {
+        Self {
+            placebo_effect,
+            estimated_effect,
+            robustness_score: robustness_score(placebo_effect, estimated_effect),
+            ..Self::new(is_robust, message)
+        }
+    }
+}
+
+fn robustness_score(placebo_effect: f64, estimated_effect: f64) -> f64 {
+    if estimated_effect > 0.0 {
+        placebo_effect / estimated_effect
+    } else {
+        f64::NAN
+    }
+}
+
+/// Significance level for the placebo permutation test.
+const PLACEBO_ALPHA: f64 = 0.05;
+
+/// Default largest placebo effect, as a share of the estimated effect, of the
+/// single-shuffle placebo check.
+pub const DEFAULT_PLACEBO_RATIO_THRESHOLD: f64 = 0.2;
+
+/// Default largest absolute placebo effect of the single-shuffle check when the
+/// estimated effect is zero.
+pub const DEFAULT_PLACEBO_ABSOLUTE_THRESHOLD: f64 = 0.05;
+
+/// Largest coefficient of variation of the per-fold effects still called stable.
+const MAX_FOLD_CV: f64 = 0.5;
+
+/// Largest relative change of the ATE under an added random common cause still
+/// called robust.
+const MAX_COMMON_CAUSE_CHANGE: f64 = 0.1;
+
+/// Placebo validation plus k-fold stability, for any model.
+///
+/// With `n_permutations > 0` the placebo check is a permutation test: treatment is
+/// shuffled `n_permutations` times, each shuffle refits a copy of the model, and the p-value is
+/// the share of placebo ATEs at least as extreme as the observed one (with the usual
+/// +1 correction). With `n_permutations == 0` a single shuffle is compared to the
+/// observed effect instead: it is robust when the placebo effect is below
+/// `placebo_ratio_threshold` times the observed effect, or below `absolute_threshold`
+/// when the observed effect is zero (see `DEFAULT_PLACEBO_RATIO_THRESHOLD` and
+/// `DEFAULT_PLACEBO_ABSOLUTE_THRESHOLD`).
+///
+/// With `n_folds >= 2` the rows are also split into `n_folds` contiguous blocks; a
+/// fresh copy of the model is trained without each block and predicts it. The structure is only
+/// robust if the held-out mean effects agree (coefficient of variation at most 0.5).
+///
+/// With `is_time_series` the rows are taken to be in time order and the folds are
+/// rolling-origin windows instead: the rows are cut into `n_folds + 1` contiguous blocks
+/// and each window trains on every block before one and predicts that block, so a
+/// model never sees rows later than the ones it is scored on.
+///
+/// Every refit uses `sample_weight` (the rows' weights travel with them) when the
+/// model was fit with weights.
+#[allow(clippy::too_many_arguments)]
 pub fn validate_causal_structure(
-    forest: &CausalForest,
+    model: &dyn CausalModel,
     x: ArrayView2<f64>,
     t: ArrayView1<f64>,
     y: ArrayView1<f64>,
+    sample_weight: Option<ArrayView1<f64>>,
     n_folds: usize,
+    n_permutations: usize,
+    is_time_series: bool,
+    placebo_ratio_threshold: f64,
+    absolute_threshold: f64,
+) -> ValidationResult {
+    let placebo = placebo_check(
+        model,
+        x,
+        t,
+        y,
+        sample_weight,
+        n_permutations,
+        placebo_ratio_threshold,
+        absolute_threshold,
+    );
+    if n_folds < 2 {
+        return placebo;
+    }
+    let folds = if is_time_series {
+        rolling_origin_validate(model, x, t, y, sample_weight, n_folds)
+    } else {
+        cross_validate(model, x, t, y, sample_weight, n_folds)
+    };
+    ValidationResult {
+        is_robust: placebo.is_robust && folds.is_robust,
+        message: format!("{} {}", placebo.message, folds.message),
+        fold_effects: folds.fold_effects,
+        ..placebo
+    }
+}
+
+/// Fits `model`, with `sample_weight` when given.
+fn refit(
+    model: &mut dyn CausalModel,
+    x: ArrayView2<f64>,
+    t: ArrayView1<f64>,
+    y: ArrayView1<f64>,
+    sample_weight: Option<ArrayView1<f64>>,
+) -> Result<()> {
+    match sample_weight {
+        Some(w) => model.fit_weighted(x, t, y, w),
+        None => model.fit(x, t, y),
+    }
+}
+
+/// Mean effect of a copy of `model` refit on `(x, shuffled t, y)`.
+fn placebo_effect(
+    model: &dyn CausalModel,
+    x: ArrayView2<f64>,
+    t: ArrayView1<f64>,
+    y: ArrayView1<f64>,
+    sample_weight: Option<ArrayView1<f64>>,
+) -> Result<f64> {
+    let mut t_shuffled = t.to_vec();
+    t_shuffled.shuffle(&mut thread_rng());
+    let mut placebo_model = model.box_clone();
+    refit(
+        placebo_model.as_mut(),
+        x,
+        Array1::from(t_shuffled).view(),
+        y,
+        sample_weight,
+    )?;
+    Ok(placebo_model.predict(x)?.mean_effect)
+}
+
+fn failed_check(message: String) -> ValidationResult {
+    ValidationResult::new(false, message)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn placebo_check(
+    model: &dyn CausalModel,
+    x: ArrayView2<f64>,
+    t: ArrayView1<f64>,
+    y: ArrayView1<f64>,
+    sample_weight: Option<ArrayView1<f64>>,
+    n_permutations: usize,
+    placebo_ratio_threshold: f64,
+    absolute_threshold: f64,
 ) -> ValidationResult {
+    let original_effect = match model.predict(x) {
+        Ok(res) => res.mean_effect.abs(),
+        Err(e) => return failed_check(format!("Warning: the model could not predict: {}.", e)),
+    };
+    if n_permutations > 0 {
+        return permutation_placebo(
+            model,
+            x,
+            t,
+            y,
+            sample_weight,
+            original_effect,
+            n_permutations,
+        );
+    }
+
     // 1. Placebo Test: Shuffling treatment should result in near-zero effect
-    let mut placebo_forest = forest.clone();
-    placebo_forest.fit_placebo(x, t, y);
-    let placebo_res = placebo_forest.predict(x);
-    let placebo_effect = placebo_res.mean_effect.abs();
+    let placebo_effect = match placebo_effect(model, x, t, y, sample_weight) {
+        Ok(effect) => effect.abs(),
+        Err(e) => return failed_check(format!("Warning: the placebo fit failed: {}.", e)),
+    };
 
     // Threshold for placebo effect (should be close to 0)
-    // In a real scenario, this might be relative to the original effect
-    let original_res = forest.predict(x);
-    let original_effect = original_res.mean_effect.abs();
-    
     let is_robust = if original_effect > 0.0 {
-        placebo_effect < original_effect * 0.2 // Placebo should be < 20% of real effect
+        placebo_effect < original_effect * placebo_ratio_threshold
     } else {
-        placebo_effect < 0.05 // Absolute threshold if original is 0
+        placebo_effect < absolute_threshold // Absolute threshold if original is 0
     };
 
-    if is_robust {
-        ValidationResult {
-            is_robust: true,
-            message: format!(
-                "Causal structure looks robust. Placebo effect ({:.4}) is significantly lower than estimated effect ({:.4}). Verified using {} folds.",
-                placebo_effect, original_effect, n_folds
-            ),
+    let mut result =
+        ValidationResult::placebo(is_robust, String::new(), placebo_effect, original_effect);
+    result.message = if is_robust {
+        format!(
+            "Causal structure looks robust. Placebo effect ({:.4}) is significantly lower than estimated effect ({:.4}); robustness score {:.3}.",
+            result.placebo_effect, result.estimated_effect, result.robustness_score
+        )
+    } else {
+        format!(
+            "Warning: Causal structure may NOT be robust. Placebo effect ({:.4}) is too high compared to estimated effect ({:.4}); robustness score {:.3}.",
+            result.placebo_effect, result.estimated_effect, result.robustness_score
+        )
+    };
+    result
+}
+
+fn permutation_placebo(
+    model: &dyn CausalModel,
+    x: ArrayView2<f64>,
+    t: ArrayView1<f64>,
+    y: ArrayView1<f64>,
+    sample_weight: Option<ArrayView1<f64>>,
+    original_effect: f64,
+    n_permutations: usize,
+) -> ValidationResult {
+    let mut as_extreme = 0;
+    let mut placebo_sum = 0.0;
+    for _ in 0..n_permutations {
+        match placebo_effect(model, x, t, y, sample_weight) {
+            Ok(effect) => {
+                placebo_sum += effect.abs();
+                if effect.abs() >= original_effect {
+                    as_extreme += 1;
+                }
+            }
+            Err(e) => return failed_check(format!("Warning: a placebo fit failed: {}.", e)),
         }
+    }
+    let p_value = (as_extreme + 1) as f64 / (n_permutations + 1) as f64;
+    let is_robust = p_value <= PLACEBO_ALPHA;
+
+    let mut result = ValidationResult {
+        p_value: Some(p_value),
+        ..ValidationResult::placebo(
+            is_robust,
+            String::new(),
+            placebo_sum / n_permutations as f64,
+            original_effect,
+        )
+    };
+    result.message = if is_robust {
+        format!(
+            "Causal structure looks robust. Estimated effect ({:.4}) exceeds {} of {} placebo effects (mean placebo {:.4}, robustness score {:.3}, permutation p = {:.3}).",
+            result.estimated_effect,
+            n_permutations - as_extreme,
+            n_permutations,
+            result.placebo_effect,
+            result.robustness_score,
+            p_value
+        )
     } else {
-        ValidationResult {
-            is_robust: false,
-            message: format!(
-                "Warning: Causal structure may NOT be robust. Placebo effect ({:.4}) is too high compared to estimated effect ({:.4}).",
-                placebo_effect, original_effect
-            ),
+        format!(
+            "Warning: Causal structure may NOT be robust. {} of {} placebo effects are at least as large as the estimated effect ({:.4}) (mean placebo {:.4}, robustness score {:.3}, permutation p = {:.3}).",
+            as_extreme,
+            n_permutations,
+            result.estimated_effect,
+            result.placebo_effect,
+            result.robustness_score,
+            p_value
+        )
+    };
+    result
+}
+
+fn cross_validate(
+    model: &dyn CausalModel,
+    x: ArrayView2<f64>,
+    t: ArrayView1<f64>,
+    y: ArrayView1<f64>,
+    sample_weight: Option<ArrayView1<f64>>,
+    n_folds: usize,
+) -> ValidationResult {
+    let n = x.nrows();
+    let mut fold_effects = Vec::with_capacity(n_folds);
+    for fold in 0..n_folds {
+        let (start, end) = (fold * n / n_folds, (fold + 1) * n / n_folds);
+        let train: Vec<usize> = (0..start).chain(end..n).collect();
+        let held_out: Vec<usize> = (start..end).collect();
+        if held_out.is_empty() {
+            continue;
+        }
+        match held_out_effect(model, x, t, y, sample_weight, &train, &held_out) {
+            Ok(effect) => fold_effects.push(effect),
+            Err(e) => {
+                return ValidationResult {
+                    fold_effects,
+                    ..failed_check(format!(
+                        "Warning: fold {} of {} could not be fit: {}.",
+                        fold + 1,
+                        n_folds,
+                        e
+                    ))
+                }
+            }
+        }
+    }
+
+    fold_stability(fold_effects, "folds", "")
+}
+
+/// Rolling-origin validation for time-ordered rows: `n_folds` windows, each training
+/// on an expanding prefix of the rows and predicting the block right after it.
+fn rolling_origin_validate(
+    model: &dyn CausalModel,
+    x: ArrayView2<f64>,
+    t: ArrayView1<f64>,
+    y: ArrayView1<f64>,
+    sample_weight: Option<ArrayView1<f64>>,
+    n_folds: usize,
+) -> ValidationResult {
+    let n = x.nrows();
+    let n_blocks = n_folds + 1;
+    let mut fold_effects = Vec::with_capacity(n_folds);
+    for window in 1..n_blocks {
+        let (start, end) = (window * n / n_blocks, (window + 1) * n / n_blocks);
+        let train: Vec<usize> = (0..start).collect();
+        let held_out: Vec<usize> = (start..end).collect();
+        if train.is_empty() || held_out.is_empty() {
+            continue;
+        }
+        match held_out_effect(model, x, t, y, sample_weight, &train, &held_out) {
+            Ok(effect) => fold_effects.push(effect),
+            Err(e) => {
+                return ValidationResult {
+                    fold_effects,
+                    ..failed_check(format!(
+                        "Warning: time-ordered window {} of {} could not be fit: {}.",
+                        window, n_folds, e
+                    ))
+                }
+            }
+        }
+    }
+    fold_stability(
+        fold_effects,
+        "time-ordered windows",
+        "Time-ordered (rolling-origin) validation was used. ",
+    )
+}
+
+/// Mean effect on the `held_out` rows of a copy of `model` fit on the `train` rows.
+fn held_out_effect(
+    model: &dyn CausalModel,
+    x: ArrayView2<f64>,
+    t: ArrayView1<f64>,
+    y: ArrayView1<f64>,
+    sample_weight: Option<ArrayView1<f64>>,
+    train: &[usize],
+    held_out: &[usize],
+) -> Result<f64> {
+    let mut fold_model = model.box_clone();
+    let train_weight = sample_weight.map(|w| w.select(Axis(0), train));
+    refit(
+        fold_model.as_mut(),
+        x.select(Axis(0), train).view(),
+        t.select(Axis(0), train).view(),
+        y.select(Axis(0), train).view(),
+        train_weight.as_ref().map(|w| w.view()),
+    )?;
+    fold_model
+        .predict(x.select(Axis(0), held_out).view())
+        .map(|res| res.mean_effect)
+}
+
+/// Robust when the per-fold effects agree (coefficient of variation at most
+/// `MAX_FOLD_CV`); `unit` names the folds and `prefix` opens the message.
+fn fold_stability(fold_effects: Vec<f64>, unit: &str, prefix: &str) -> ValidationResult {
+    let effects = Array1::from(fold_effects.clone());
+    let mean = effects.mean().unwrap_or(0.0);
+    let std = if effects.len() > 1 {
+        effects.std(1.0)
+    } else {
+        0.0
+    };
+    let cv = if std == 0.0 { 0.0 } else { std / mean.abs() };
+    let listed = fold_effects
+        .iter()
+        .map(|e| format!("{:.4}", e))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let message = if cv <= MAX_FOLD_CV {
+        format!(
+            "{}Held-out effects are stable across {} {} (mean {:.4}, CV {:.2}): [{}].",
+            prefix,
+            fold_effects.len(),
+            unit,
+            mean,
+            cv,
+            listed
+        )
+    } else {
+        format!(
+            "Warning: {}Held-out effects vary across {} {} (mean {:.4}, CV {:.2}): [{}].",
+            prefix,
+            fold_effects.len(),
+            unit,
+            mean,
+            cv,
+            listed
+        )
+    };
+    ValidationResult {
+        fold_effects,
+        ..ValidationResult::new(cv <= MAX_FOLD_CV, message)
+    }
+}
+
+/// Random-common-cause refutation, as in DoWhy.
+///
+/// Appends an independent standard-normal column to `x`, refits a copy of `model` on
+/// the extended features and compares its ATE with the original one. A covariate that
+/// causes nothing should barely move a valid estimate: the check is robust when the ATE
+/// changes by at most 10%, or by less than `DEFAULT_PLACEBO_ABSOLUTE_THRESHOLD` when
+/// the original ATE is zero. `seed` makes the random column reproducible, and the
+/// refit uses `sample_weight` when given.
+pub fn refute_random_common_cause(
+    model: &dyn CausalModel,
+    x: ArrayView2<f64>,
+    t: ArrayView1<f64>,
+    y: ArrayView1<f64>,
+    sample_weight: Option<ArrayView1<f64>>,
+    seed: Option<u64>,
+) -> Result<ValidationResult> {
+    let original_effect = model.predict(x)?.mean_effect;
+
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let common_cause: Array1<f64> = (0..x.nrows()).map(|_| standard_normal(&mut rng)).collect();
+    let extended = concatenate![Axis(1), x, common_cause.insert_axis(Axis(1))];
+    let mut refit_model = model.box_clone_with_feature("random_common_cause");
+    refit(refit_model.as_mut(), extended.view(), t, y, sample_weight)?;
+    let refuted_effect = refit_model.predict(extended.view())?.mean_effect;
+
+    let change = refuted_effect - original_effect;
+    let (is_robust, change_text) = if original_effect != 0.0 {
+        let relative = change / original_effect.abs();
+        (
+            relative.abs() <= MAX_COMMON_CAUSE_CHANGE,
+            format!("{:+.1}%", 100.0 * relative),
+        )
+    } else {
+        (
+            change.abs() < DEFAULT_PLACEBO_ABSOLUTE_THRESHOLD,
+            format!("absolute change {:.4}", change),
+        )
+    };
+    let verdict = if is_robust {
+        "Estimate is robust to a random common cause."
+    } else {
+        "Warning: Estimate changed under a random common cause."
+    };
+    let message = format!(
+        "{} ATE moved from {:.4} to {:.4} ({}).",
+        verdict, original_effect, refuted_effect, change_text
+    );
+    Ok(ValidationResult {
+        estimated_effect: original_effect.abs(),
+        ..ValidationResult::new(is_robust, message)
+    })
+}
+
+/// Standard-normal draw by the Box-Muller transform.
+fn standard_normal(rng: &mut impl Rng) -> f64 {
+    let u1: f64 = 1.0 - rng.gen::<f64>(); // in (0, 1], so the log is finite
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+/// Falsification check with a negative-control outcome, one the treatment cannot affect.
+///
+/// Refits `forest`'s configuration on `(x, t, y_negative_control)`; the estimated ATE
+/// should be near zero. The check fails when the ATE's confidence interval excludes
+/// zero and the effect exceeds 0.1 standard deviations of the negative-control outcome,
+/// which points to confounding or leakage that also biases the real estimate. With
+/// `sample_weight` the refit and the ATE are both weighted.
+pub fn validate_negative_control(
+    forest: &CausalForest,
+    x: ArrayView2<f64>,
+    t: ArrayView1<f64>,
+    y_negative_control: ArrayView1<f64>,
+    sample_weight: Option<ArrayView1<f64>>,
+) -> Result<ValidationResult> {
+    if y_negative_control.len() != x.nrows() {
+        return Err(CausalFlowError::InvalidParameter(format!(
+            "negative_control has {} values but x has {} rows",
+            y_negative_control.len(),
+            x.nrows()
+        )));
+    }
+
+    let mut control_forest = forest.clone();
+    let (ate, (lower, upper)) = match sample_weight {
+        Some(w) => {
+            control_forest.fit_weighted(x, t, y_negative_control, w)?;
+            control_forest.weighted_ate(x, &w.to_owned())?
         }
+        None => {
+            control_forest.fit_result(x, t, y_negative_control)?;
+            let all_rows = Array1::from_elem(x.nrows(), true);
+            control_forest.conditional_ate(x, &all_rows)?
+        }
+    };
+
+    let std = y_negative_control.std(1.0);
+    let standardized = if std > 0.0 { ate.abs() / std } else { 0.0 };
+    let excludes_zero = lower > 0.0 || upper < 0.0;
+
+    if excludes_zero && standardized > 0.1 {
+        Ok(ValidationResult::new(
+            false,
+            format!(
+                "Warning: Negative control outcome shows an effect of {:.4} (CI {:.4} to {:.4}, {:.2} SD). The treatment should not affect it; check for confounding or leakage.",
+                ate, lower, upper, standardized
+            ),
+        ))
+    } else {
+        Ok(ValidationResult::new(
+            true,
+            format!(
+                "Negative control passed. Effect on the negative control outcome ({:.4}, CI {:.4} to {:.4}) is consistent with zero.",
+                ate, lower, upper
+            ),
+        ))
     }
 }
@@ -1,3 +1,4 @@
+use crate::errors::{CausalFlowError, Result};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -13,6 +14,13 @@ pub struct FeatureImportanceData {
     pub values: Vec<f64>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ImportanceStabilityData {
+    pub labels: Vec<String>,
+    pub values: Vec<f64>,
+    pub errors: Vec<f64>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CausalGraphData {
     pub nodes: Vec<NodeInfo>,
@@ -40,6 +48,15 @@ pub struct EffectDistData {
     pub y_label: String,
     pub bins: Vec<f64>,
     pub counts: Vec<u64>,
+    #[serde(default)]
+    pub n_dropped: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PartialDependenceData {
+    pub feature_name: String,
+    pub grid: Vec<f64>,
+    pub effects: Vec<f64>,
 }
 
 impl VisualOutput {
@@ -51,6 +68,85 @@ impl VisualOutput {
         }
     }
 
+    /// Importance rescaled to percentages of the total, with every feature below
+    /// `min_percent` merged into a single trailing "Other" bar.
+    pub fn feature_importance_percent(
+        labels: Vec<String>,
+        values: Vec<f64>,
+        min_percent: f64,
+    ) -> Self {
+        let total: f64 = values.iter().sum();
+        let mut kept_labels = Vec::new();
+        let mut kept_values = Vec::new();
+        let mut other = 0.0;
+        let mut n_other = 0;
+        for (label, value) in labels.into_iter().zip(values) {
+            let percent = if total > 0.0 {
+                100.0 * value / total
+            } else {
+                0.0
+            };
+            if percent < min_percent {
+                other += percent;
+                n_other += 1;
+            } else {
+                kept_labels.push(label);
+                kept_values.push(percent);
+            }
+        }
+        if n_other > 0 {
+            kept_labels.push("Other".to_string());
+            kept_values.push(other);
+        }
+
+        Self {
+            visual_type: "feature_importance".to_string(),
+            title: "Feature Importance Analysis (%)".to_string(),
+            data: serde_json::to_value(FeatureImportanceData {
+                labels: kept_labels,
+                values: kept_values,
+            })
+            .unwrap(),
+        }
+    }
+
+    /// Mean bootstrap importance per feature with one-standard-deviation error bars.
+    pub fn importance_stability(labels: Vec<String>, values: Vec<f64>, errors: Vec<f64>) -> Self {
+        Self {
+            visual_type: "importance_stability".to_string(),
+            title: "Feature Importance Stability".to_string(),
+            data: serde_json::to_value(ImportanceStabilityData {
+                labels,
+                values,
+                errors,
+            })
+            .unwrap(),
+        }
+    }
+
+    /// Pie chart of each feature's share of the variance in predicted effects.
+    pub fn variance_shares(labels: Vec<String>, values: Vec<f64>) -> Self {
+        Self {
+            visual_type: "variance_shares".to_string(),
+            title: "Drivers of Effect Heterogeneity".to_string(),
+            data: serde_json::to_value(FeatureImportanceData { labels, values }).unwrap(),
+        }
+    }
+
+    /// Line chart of the predicted effect as one feature sweeps `grid`.
+    pub fn partial_dependence(feature_name: String, grid: Vec<f64>, effects: Vec<f64>) -> Self {
+        Self {
+            visual_type: "partial_dependence".to_string(),
+            title: format!("Partial Dependence of the Effect on {}", feature_name),
+            data: serde_json::to_value(PartialDependenceData {
+                feature_name,
+                grid,
+                effects,
+            })
+            .unwrap(),
+        }
+    }
+
     pub fn causal_graph(nodes: Vec<NodeInfo>, links: Vec<LinkInfo>) -> Self {
         Self {
             visual_type: "causal_graph".to_string(),
@@ -68,6 +164,72 @@ impl VisualOutput {
                 y_label,
                 bins,
                 counts,
+                n_dropped: 0,
+            })
+            .unwrap(),
+        }
+    }
+
+    /// Histogram of individual effects in 10 bins spanning the observed range.
+    pub fn effect_histogram(predictions: &[f64]) -> Self {
+        Self::effect_dist_with_bins(predictions, 10, None)
+    }
+
+    /// Histogram of individual effects in `n_bins` equal-width bins (at least one).
+    /// `range` fixes the histogram bounds; effects outside it are clamped into the
+    /// edge bins. Without it the bins span the observed effects, and a constant
+    /// effect gets a single bar labelled with its value. Non-finite predictions are
+    /// left out of the bins and reported through `n_dropped` and the title.
+    pub fn effect_dist_with_bins(
+        predictions: &[f64],
+        n_bins: usize,
+        range: Option<(f64, f64)>,
+    ) -> Self {
+        let finite: Vec<f64> = predictions
+            .iter()
+            .cloned()
+            .filter(|p| p.is_finite())
+            .collect();
+        let n_dropped = (predictions.len() - finite.len()) as u64;
+
+        let (min, max) = match range {
+            Some(bounds) => bounds,
+            None if finite.is_empty() => (0.0, 0.0),
+            None => {
+                let min = finite.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+                let max = finite.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+                (min, max)
+            }
+        };
+
+        let (bins, counts) = if (max - min).abs() < f64::EPSILON {
+            (vec![min], vec![finite.len() as u64])
+        } else {
+            let n_bins = n_bins.max(1);
+            let bin_width = (max - min) / n_bins as f64;
+            let bins: Vec<f64> = (0..n_bins).map(|i| min + i as f64 * bin_width).collect();
+            let mut counts = vec![0u64; n_bins];
+            for &p in &finite {
+                let b = (((p.clamp(min, max) - min) / bin_width) as usize).min(n_bins - 1);
+                counts[b] += 1;
+            }
+            (bins, counts)
+        };
+
+        let mut title = "Treatment Effect Distribution".to_string();
+        if n_dropped > 0 {
+            title.push_str(&format!(" ({} non-finite effects dropped)", n_dropped));
+        }
+
+        Self {
+            visual_type: "effect_dist".to_string(),
+            title,
+            data: serde_json::to_value(EffectDistData {
+                x_label: "Individual Treatment Effect Distribution".to_string(),
+                y_label: "Frequency".to_string(),
+                bins,
+                counts,
+                n_dropped,
             })
             .unwrap(),
         }
@@ -76,4 +238,472 @@ impl VisualOutput {
     pub fn to_json(&self) -> String {
         serde_json::to_string_pretty(self).unwrap()
     }
+
+    fn graph_data(&self) -> Option<CausalGraphData> {
+        if self.visual_type != "causal_graph" {
+            return None;
+        }
+        serde_json::from_value(self.data.clone()).ok()
+    }
+
+    /// Graphviz DOT rendering of a causal graph, with nodes colored by role and edge
+    /// widths scaled by link weight. Returns `None` for other visual types.
+    pub fn to_graph_dot(&self) -> Option<String> {
+        let graph = self.graph_data()?;
+        let mut dot = String::from("digraph CausalGraph {\n");
+        dot.push_str("    rankdir=LR;\n");
+        dot.push_str("    node [style=filled, fontname=\"Helvetica\"];\n");
+        for node in &graph.nodes {
+            dot.push_str(&format!(
+                "    \"{}\" [label=\"{}\", shape={}, fillcolor=\"{}\"];\n",
+                escape_label(&node.id),
+                escape_label(&node.label),
+                role_shape(&node.role),
+                role_color(&node.role)
+            ));
+        }
+        for (link, penwidth) in graph.links.iter().zip(link_widths(&graph.links)) {
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"{:.3}\", penwidth={:.2}];\n",
+                escape_label(&link.source),
+                escape_label(&link.target),
+                link.weight,
+                penwidth
+            ));
+        }
+        dot.push_str("}\n");
+        Some(dot)
+    }
+
+    /// Mermaid flowchart rendering of a causal graph. Returns `None` for other visual types.
+    pub fn to_graph_mermaid(&self) -> Option<String> {
+        let graph = self.graph_data()?;
+        // Mermaid ids must be plain identifiers, so nodes are keyed by position.
+        let key = |id: &str| {
+            graph
+                .nodes
+                .iter()
+                .position(|n| n.id == id)
+                .map(|i| format!("n{}", i))
+        };
+
+        let mut mermaid = String::from("graph LR\n");
+        for (i, node) in graph.nodes.iter().enumerate() {
+            mermaid.push_str(&format!(
+                "    n{}[\"{}\"]\n",
+                i,
+                node.label.replace('"', "#quot;")
+            ));
+        }
+        for link in &graph.links {
+            if let (Some(source), Some(target)) = (key(&link.source), key(&link.target)) {
+                mermaid.push_str(&format!(
+                    "    {} -->|{:.3}| {}\n",
+                    source, target, link.weight
+                ));
+            }
+        }
+        for role in ["treatment", "outcome", "confounder", "feature"] {
+            let members: Vec<String> = graph
+                .nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, n)| n.role == role)
+                .map(|(i, _)| format!("n{}", i))
+                .collect();
+            if members.is_empty() {
+                continue;
+            }
+            mermaid.push_str(&format!(
+                "    classDef {} fill:{},color:#1a1a2e;\n",
+                role,
+                role_color(role)
+            ));
+            mermaid.push_str(&format!("    class {} {};\n", members.join(","), role));
+        }
+        Some(mermaid)
+    }
+
+    /// Standalone SVG document of `width` x `height` pixels, with no scripts or
+    /// external resources: bars for importance-style charts, a histogram for the
+    /// effect distribution, a line for partial dependence and a role-column layout
+    /// (confounders and features, then treatment, then outcome) for causal graphs.
+    /// Other visual types render only their title. Sizes below `MIN_SVG_WIDTH` x
+    /// `MIN_SVG_HEIGHT` leave no room for the plot area and are rejected.
+    pub fn to_svg(&self, width: u32, height: u32) -> Result<String> {
+        if width < MIN_SVG_WIDTH || height < MIN_SVG_HEIGHT {
+            return Err(CausalFlowError::InvalidParameter(format!(
+                "SVG size must be at least {} x {} pixels, got {} x {}",
+                MIN_SVG_WIDTH, MIN_SVG_HEIGHT, width, height
+            )));
+        }
+        let canvas = SvgCanvas {
+            width: width as f64,
+            height: height as f64,
+        };
+        let mut body = String::new();
+        match self.visual_type.as_str() {
+            "feature_importance" | "variance_shares" => {
+                if let Ok(data) = serde_json::from_value::<FeatureImportanceData>(self.data.clone())
+                {
+                    canvas.bars(&mut body, &data.labels, &data.values, None);
+                }
+            }
+            "importance_stability" => {
+                if let Ok(data) =
+                    serde_json::from_value::<ImportanceStabilityData>(self.data.clone())
+                {
+                    canvas.bars(&mut body, &data.labels, &data.values, Some(&data.errors));
+                }
+            }
+            "effect_dist" => {
+                if let Ok(data) = serde_json::from_value::<EffectDistData>(self.data.clone()) {
+                    canvas.histogram(&mut body, &data);
+                }
+            }
+            "partial_dependence" => {
+                if let Ok(data) = serde_json::from_value::<PartialDependenceData>(self.data.clone())
+                {
+                    canvas.line(&mut body, &data.grid, &data.effects);
+                }
+            }
+            "causal_graph" => {
+                if let Some(graph) = self.graph_data() {
+                    canvas.graph(&mut body, &graph);
+                }
+            }
+            _ => {}
+        }
+        Ok(format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+             viewBox=\"0 0 {w} {h}\" font-family=\"Helvetica, Arial, sans-serif\">\n\
+             <rect width=\"{w}\" height=\"{h}\" fill=\"#ffffff\"/>\n\
+             <text x=\"{cx}\" y=\"24\" text-anchor=\"middle\" font-size=\"16\">{title}</text>\n\
+             {body}</svg>\n",
+            w = canvas.width,
+            h = canvas.height,
+            cx = canvas.width / 2.0,
+            title = escape_xml(&self.title),
+            body = body
+        ))
+    }
+}
+
+/// Edge widths from 1 (no weight) to 5 (the heaviest link), in link order.
+fn link_widths(links: &[LinkInfo]) -> Vec<f64> {
+    let max_weight = links
+        .iter()
+        .map(|link| link.weight.abs())
+        .filter(|w| w.is_finite())
+        .fold(0.0, f64::max);
+    links
+        .iter()
+        .map(|link| {
+            if max_weight > 0.0 && link.weight.is_finite() {
+                1.0 + 4.0 * link.weight.abs() / max_weight
+            } else {
+                1.0
+            }
+        })
+        .collect()
+}
+
+/// Plot area below the title, shared by the SVG chart renderers.
+struct SvgCanvas {
+    width: f64,
+    height: f64,
+}
+
+/// Smallest SVG size `to_svg` accepts.
+pub const MIN_SVG_WIDTH: u32 = 200;
+pub const MIN_SVG_HEIGHT: u32 = 150;
+
+const SVG_MARGIN: f64 = 40.0;
+const SVG_TOP: f64 = 48.0;
+const SVG_BAR_COLOR: &str = "#4fc3f7";
+const SVG_WHISKER_COLOR: &str = "#37474f";
+
+impl SvgCanvas {
+    fn plot_bottom(&self) -> f64 {
+        self.height - SVG_MARGIN
+    }
+
+    /// Horizontal bars, labels on the left and values at the bar ends. With `errors`
+    /// (one standard deviation per bar) each bar gets a +-1 SD whisker.
+    fn bars(&self, out: &mut String, labels: &[String], values: &[f64], errors: Option<&[f64]>) {
+        if values.is_empty() {
+            return;
+        }
+        let error = |i: usize| {
+            errors
+                .and_then(|e| e.get(i))
+                .copied()
+                .filter(|e| e.is_finite())
+                .map_or(0.0, f64::abs)
+        };
+        let label_width = self.width * 0.3;
+        let plot_width = self.width - label_width - 2.0 * SVG_MARGIN;
+        // Whiskers must fit in the plot too
+        let reach: Vec<f64> = (0..values.len())
+            .map(|i| values[i].abs() + error(i))
+            .collect();
+        let max = finite_abs_max(&reach);
+        let scale = |v: f64| {
+            if max > 0.0 && v.is_finite() {
+                plot_width * v / max
+            } else {
+                0.0
+            }
+        };
+        let slot = (self.plot_bottom() - SVG_TOP) / values.len() as f64;
+        for (i, (label, &value)) in labels.iter().zip(values).enumerate() {
+            let y = SVG_TOP + i as f64 * slot;
+            let length = scale(value.abs());
+            let x = SVG_MARGIN + label_width;
+            out.push_str(&format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\" font-size=\"12\">{}</text>\n",
+                x - 6.0,
+                y + slot / 2.0 + 4.0,
+                escape_xml(label)
+            ));
+            out.push_str(&format!(
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"/>\n",
+                x,
+                y + slot * 0.15,
+                length,
+                slot * 0.7,
+                SVG_BAR_COLOR
+            ));
+            let mut label_x = x + length + 4.0;
+            if errors.is_some() && value.is_finite() {
+                let (low, high) = (
+                    x + scale((value.abs() - error(i)).max(0.0)),
+                    x + scale(value.abs() + error(i)),
+                );
+                let (mid, cap) = (y + slot / 2.0, slot * 0.2);
+                out.push_str(&format!(
+                    "<path d=\"M{low:.1} {mid:.1} H{high:.1} M{low:.1} {top:.1} V{bottom:.1} \
+                     M{high:.1} {top:.1} V{bottom:.1}\" stroke=\"{color}\" stroke-width=\"1.5\" \
+                     fill=\"none\"/>\n",
+                    low = low,
+                    high = high,
+                    mid = mid,
+                    top = mid - cap,
+                    bottom = mid + cap,
+                    color = SVG_WHISKER_COLOR
+                ));
+                label_x = label_x.max(high + 4.0);
+            }
+            out.push_str(&format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"11\">{:.3}</text>\n",
+                label_x,
+                y + slot / 2.0 + 4.0,
+                value
+            ));
+        }
+    }
+
+    /// Vertical histogram bars, each labelled with its bin's lower edge.
+    fn histogram(&self, out: &mut String, data: &EffectDistData) {
+        if data.counts.is_empty() {
+            return;
+        }
+        let plot_width = self.width - 2.0 * SVG_MARGIN;
+        let plot_height = self.plot_bottom() - SVG_TOP;
+        let max = data.counts.iter().copied().max().unwrap_or(0) as f64;
+        let slot = plot_width / data.counts.len() as f64;
+        for (i, (&count, &edge)) in data.counts.iter().zip(&data.bins).enumerate() {
+            let bar_height = if max > 0.0 {
+                plot_height * count as f64 / max
+            } else {
+                0.0
+            };
+            let x = SVG_MARGIN + i as f64 * slot;
+            out.push_str(&format!(
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"/>\n",
+                x + 1.0,
+                self.plot_bottom() - bar_height,
+                (slot - 2.0).max(1.0),
+                bar_height,
+                SVG_BAR_COLOR
+            ));
+            out.push_str(&format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" font-size=\"10\">{:.2}</text>\n",
+                x + slot / 2.0,
+                self.plot_bottom() + 14.0,
+                edge
+            ));
+        }
+        out.push_str(&format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" font-size=\"12\">{}</text>\n",
+            self.width / 2.0,
+            self.height - 8.0,
+            escape_xml(&data.x_label)
+        ));
+    }
+
+    /// Polyline of `ys` against `xs`, scaled to the plot area.
+    fn line(&self, out: &mut String, xs: &[f64], ys: &[f64]) {
+        let points: Vec<(f64, f64)> = xs
+            .iter()
+            .zip(ys)
+            .map(|(&x, &y)| (x, y))
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .collect();
+        if points.is_empty() {
+            return;
+        }
+        let bounds = |values: Vec<f64>| {
+            let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+            let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            (min, if max > min { max - min } else { 1.0 })
+        };
+        let (x_min, x_span) = bounds(points.iter().map(|p| p.0).collect());
+        let (y_min, y_span) = bounds(points.iter().map(|p| p.1).collect());
+        let plot_width = self.width - 2.0 * SVG_MARGIN;
+        let plot_height = self.plot_bottom() - SVG_TOP;
+        let path: Vec<String> = points
+            .iter()
+            .map(|(x, y)| {
+                format!(
+                    "{:.1},{:.1}",
+                    SVG_MARGIN + plot_width * (x - x_min) / x_span,
+                    self.plot_bottom() - plot_height * (y - y_min) / y_span
+                )
+            })
+            .collect();
+        out.push_str(&format!(
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"/>\n",
+            path.join(" "),
+            role_color("treatment")
+        ));
+        out.push_str(&format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"10\">{:.3}</text>\n\
+             <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"10\">{:.3}</text>\n",
+            4.0,
+            self.plot_bottom(),
+            y_min,
+            4.0,
+            SVG_TOP + 4.0,
+            y_min + y_span
+        ));
+    }
+
+    /// Nodes in columns by role, edges as arrows whose width follows the link weight.
+    fn graph(&self, out: &mut String, graph: &CausalGraphData) {
+        let column_of = |role: &str| match role {
+            "treatment" => 1,
+            "outcome" => 2,
+            _ => 0,
+        };
+        let used: Vec<usize> = (0..3)
+            .filter(|&c| graph.nodes.iter().any(|n| column_of(&n.role) == c))
+            .collect();
+        let plot_width = self.width - 2.0 * SVG_MARGIN;
+        let mut positions = Vec::with_capacity(graph.nodes.len());
+        for node in &graph.nodes {
+            let column = column_of(&node.role);
+            let slot = used.iter().position(|&c| c == column).unwrap_or(0);
+            let members: Vec<&NodeInfo> = graph
+                .nodes
+                .iter()
+                .filter(|n| column_of(&n.role) == column)
+                .collect();
+            let row = members
+                .iter()
+                .position(|n| std::ptr::eq(*n, node))
+                .unwrap_or(0);
+            let x = SVG_MARGIN + plot_width * (slot as f64 + 0.5) / used.len() as f64;
+            let y = SVG_TOP
+                + (self.plot_bottom() - SVG_TOP) * (row as f64 + 0.5) / members.len() as f64;
+            positions.push((node.id.as_str(), x, y));
+        }
+        let position = |id: &str| {
+            positions
+                .iter()
+                .find(|(node_id, _, _)| *node_id == id)
+                .map(|&(_, x, y)| (x, y))
+        };
+
+        out.push_str(
+            "<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" \
+             markerUnits=\"userSpaceOnUse\" markerWidth=\"10\" markerHeight=\"10\" orient=\"auto-start-reverse\">\
+             <path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"#555555\"/></marker></defs>\n",
+        );
+        for (link, width) in graph.links.iter().zip(link_widths(&graph.links)) {
+            let (Some((x1, y1)), Some((x2, y2))) = (position(&link.source), position(&link.target))
+            else {
+                continue;
+            };
+            // Stop short of the target so the arrowhead stays outside its shape
+            let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt().max(1.0);
+            let shorten = (45.0 / length).min(0.5);
+            out.push_str(&format!(
+                "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"#555555\" \
+                 stroke-width=\"{:.2}\" marker-end=\"url(#arrow)\"/>\n",
+                x1,
+                y1,
+                x2 - (x2 - x1) * shorten,
+                y2 - (y2 - y1) * shorten,
+                width
+            ));
+        }
+        for (node, &(_, x, y)) in graph.nodes.iter().zip(&positions) {
+            let shape = match role_shape(&node.role) {
+                "box" => format!(
+                    "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"80\" height=\"30\" rx=\"4\" fill=\"{}\"/>",
+                    x - 40.0,
+                    y - 15.0,
+                    role_color(&node.role)
+                ),
+                _ => format!(
+                    "<ellipse cx=\"{:.1}\" cy=\"{:.1}\" rx=\"40\" ry=\"15\" fill=\"{}\"/>",
+                    x,
+                    y,
+                    role_color(&node.role)
+                ),
+            };
+            out.push_str(&format!(
+                "{}\n<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" font-size=\"11\">{}</text>\n",
+                shape,
+                x,
+                y + 4.0,
+                escape_xml(&node.label)
+            ));
+        }
+    }
+}
+
+fn finite_abs_max(values: &[f64]) -> f64 {
+    values
+        .iter()
+        .map(|v| v.abs())
+        .filter(|v| v.is_finite())
+        .fold(0.0, f64::max)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn role_color(role: &str) -> &'static str {
+    match role {
+        "treatment" => "#ff7043",
+        "outcome" => "#66bb6a",
+        _ => "#4fc3f7",
+    }
+}
+
+fn role_shape(role: &str) -> &'static str {
+    match role {
+        "treatment" | "outcome" => "box",
+        _ => "ellipse",
+    }
+}
+
+fn escape_label(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
 }
@@ -1,21 +1,150 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, ItemFn};
+use syn::{
+    parse_macro_input, Attribute, Expr, ExprLit, FnArg, GenericArgument, ItemFn, Lit, Meta, Pat,
+    PathArguments, Type,
+};
 
+/// Generates `metadata_for_<name>()`, returning a JSON description of the annotated
+/// function for tool-calling clients: its name, the `///` doc comment as the
+/// description, and a JSON schema of its arguments. `Option<T>` arguments are left
+/// out of `required`.
+///
+/// ```
+/// use causalflow_macros::causal_tool;
+///
+/// /// Estimates the lift of a campaign.
+/// #[causal_tool]
+/// fn estimate_lift(spend: f64, region: String, segments: Vec<String>, cap: Option<f64>) -> f64 {
+///     spend
+/// }
+///
+/// assert_eq!(
+///     metadata_for_estimate_lift(),
+///     concat!(
+///         r#"{"description":"Estimates the lift of a campaign.","name":"estimate_lift","#,
+///         r#""parameters":{"properties":{"cap":{"type":"number"},"region":{"type":"string"},"#,
+///         r#""segments":{"type":"array"},"spend":{"type":"number"}},"#,
+///         r#""required":["spend","region","segments"],"type":"object"}}"#,
+///     )
+/// );
+/// ```
 #[proc_macro_attribute]
 pub fn causal_tool(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let input = parse_macro_input!(item as ItemFn);
     let name = &input.sig.ident;
     let metadata_name = quote::format_ident!("metadata_for_{}", name);
 
+    let mut properties = serde_json::Map::new();
+    let mut required = Vec::new();
+    for arg in &input.sig.inputs {
+        let FnArg::Typed(arg) = arg else {
+            return syn::Error::new_spanned(arg, "causal_tool functions cannot take self")
+                .to_compile_error()
+                .into();
+        };
+        let Pat::Ident(pat) = arg.pat.as_ref() else {
+            return syn::Error::new_spanned(&arg.pat, "causal_tool arguments must be plain names")
+                .to_compile_error()
+                .into();
+        };
+        let (ty, optional) = match option_inner(&arg.ty) {
+            Some(inner) => (inner, true),
+            None => (arg.ty.as_ref(), false),
+        };
+        let Some(schema_type) = json_schema_type(ty) else {
+            return syn::Error::new_spanned(ty, "unsupported argument type for causal_tool")
+                .to_compile_error()
+                .into();
+        };
+        let arg_name = pat.ident.to_string();
+        properties.insert(arg_name.clone(), serde_json::json!({ "type": schema_type }));
+        if !optional {
+            required.push(arg_name);
+        }
+    }
+
+    let metadata = serde_json::json!({
+        "name": name.to_string(),
+        "description": doc_comment(&input.attrs),
+        "parameters": {
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        },
+    })
+    .to_string();
+
     let expanded = quote! {
         #input
 
         pub fn #metadata_name() -> &'static str {
-            // Placeholder for JSON metadata generation
-            "{ \"name\": \"#name\", \"description\": \"Causal tool defined in Rust\" }"
+            #metadata
         }
     };
 
     TokenStream::from(expanded)
 }
+
+/// The `///` lines of `attrs`, trimmed and joined with spaces.
+fn doc_comment(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(nv) => match &nv.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(s), ..
+                }) => Some(s.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `T` when `ty` is `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// JSON schema type name for a Rust argument type.
+fn json_schema_type(ty: &Type) -> Option<&'static str> {
+    match ty {
+        Type::Reference(reference) => match reference.elem.as_ref() {
+            Type::Path(path) if path.path.is_ident("str") => Some("string"),
+            Type::Slice(_) => Some("array"),
+            _ => None,
+        },
+        Type::Slice(_) | Type::Array(_) => Some("array"),
+        Type::Path(path) => {
+            let ident = path.path.segments.last()?.ident.to_string();
+            match ident.as_str() {
+                "f32" | "f64" => Some("number"),
+                "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize" => {
+                    Some("integer")
+                }
+                "bool" => Some("boolean"),
+                "String" => Some("string"),
+                "Vec" => Some("array"),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
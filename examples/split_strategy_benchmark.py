@@ -0,0 +1,30 @@
+import time
+
+import numpy as np
+import causalflow as cf
+
+
+def pehe(true_effects, predicted):
+    # Precision in Estimation of Heterogeneous Effects (root mean squared CATE error)
+    return float(np.sqrt(np.mean((true_effects - predicted) ** 2)))
+
+
+def main():
+    rng = np.random.default_rng(42)
+    n, p = 5000, 10
+    X = rng.normal(size=(n, p))
+    T = rng.integers(0, 2, size=n).astype(np.float64)
+    tau = 1.0 + 2.0 * (X[:, 0] > 0)
+    Y = X[:, 1] + tau * T + rng.normal(scale=0.5, size=n)
+
+    for strategy in ["best_random", "extra_random"]:
+        start = time.perf_counter()
+        model = cf.create_model(X, T, Y, method="forest", split_strategy=strategy)
+        fit_time = time.perf_counter() - start
+
+        res = model.estimate_effects(X)
+        print(f"{strategy:>13}: fit {fit_time:.3f}s, PEHE {pehe(tau, res.predictions):.4f}")
+
+
+if __name__ == "__main__":
+    main()
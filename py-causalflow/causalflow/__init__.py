@@ -1,6 +1,8 @@
 from . import _causalflow
 from ._causalflow import *
-from .preprocessing import DataProcessor
+from .preprocessing import DataProcessor, as_float64_contiguous
+from .spec import ModelSpec
+from .settings import config, get_config, set_config
 import pandas as pd
 import numpy as np
 
@@ -10,35 +12,389 @@ class CausalModelWrapper:
         self._processor = processor
         self.feature_names_out_ = processor.feature_names_out_
 
-    def estimate_effects(self, x):
+    def estimate_effects(self, x, cluster_col=None, n_bootstrap=200, id_col=None):
+        """
+        Estimate effects on `x`. `cluster_col` (a column name of `x`, or one cluster id
+        per row) adds a cluster-robust bootstrap CI for the ATE.
+
+        Row identifiers are carried to ``result.to_dataframe()``: the `id_col` column
+        when given (it is not used as a feature), otherwise the DataFrame index.
+        """
+        index = None
+        if id_col is not None:
+            if not isinstance(x, pd.DataFrame) or id_col not in x.columns:
+                raise ValueError(f"id_col '{id_col}' not found in data")
+            index = pd.Index(x[id_col])
+            if id_col not in self._processor.feature_names_in_:
+                x = x.drop(columns=[id_col])
+        elif isinstance(x, pd.DataFrame):
+            index = x.index
+
+        result = self._estimate_effects(x, cluster_col, n_bootstrap)
+        result.index = index
+        return result
+
+    def _estimate_effects(self, x, cluster_col, n_bootstrap):
+        if cluster_col is None:
+            return self._model.estimate_effects(self._processor.transform(x))
+
+        if isinstance(cluster_col, str):
+            if not isinstance(x, pd.DataFrame):
+                raise ValueError("cluster_col by name requires `x` to be a DataFrame")
+            if cluster_col not in x.columns:
+                raise ValueError(f"cluster_col '{cluster_col}' not found in data")
+            clusters = x[cluster_col]
+            if cluster_col not in self._processor.feature_names_in_:
+                x = x.drop(columns=[cluster_col])
+        else:
+            clusters = cluster_col
+
+        clusters = np.asarray(clusters)
+        if len(clusters) != len(x):
+            raise ValueError(f"cluster ids have {len(clusters)} entries but data has {len(x)} rows")
+        codes, _ = pd.factorize(clusters)
         x_proc = self._processor.transform(x)
-        return self._model.estimate_effects(x_proc)
+        return self._model.estimate_effects(x_proc, codes.astype(np.int64), n_bootstrap)
     
-    def validate(self, n_folds=5, is_time_series=False):
-        return self._model.validate(n_folds, is_time_series)
+    def transform(self, x):
+        """
+        The preprocessed feature matrix for `x`. A model reloaded with `load_model`
+        has no preprocessing and takes this matrix instead of raw data.
+        """
+        return self._processor.transform(x)
+
+    def predict_multi_ci(self, x, levels=(0.9, 0.95)):
+        """Predictions and intervals at several levels, keyed by level (fan charts)."""
+        x_proc = self._processor.transform(x)
+        return self._model.predict_multi_ci(x_proc, [float(level) for level in levels])
+
+    def tree_predictions(self, x):
+        x_proc = self._processor.transform(x)
+        return self._model.tree_predictions(x_proc)
+
+    def predict_contrast(self, x, arm_a, arm_b):
+        """Effect of arm `arm_b` relative to arm `arm_a` per row, for categorical treatments."""
+        x_proc = self._processor.transform(x)
+        return self._model.predict_contrast(x_proc, arm_a, arm_b)
+
+    def explain_one(self, row):
+        """
+        Per-tree ``(leaf_effect, leaf_size)`` for one row (a Series, dict, or one-row
+        DataFrame/array), to see the spread and support behind its prediction.
+        """
+        if isinstance(row, (dict, pd.Series)):
+            row = pd.DataFrame([row])
+        elif not isinstance(row, pd.DataFrame):
+            row = np.asarray(row, dtype=np.float64).reshape(1, -1)
+        if len(row) != 1:
+            raise ValueError(f"explain_one expects a single row, got {len(row)}")
+        row_proc = self._processor.transform(row)[0]
+        return self._model.explain_one([float(v) for v in row_proc])
+
+    def benchmark_predict(self, x, n_iters=100):
+        """Latency (mean/median/p99 in ms) and rows per second for scoring `x`."""
+        x_proc = self._processor.transform(x)
+        return self._model.benchmark_predict(x_proc, n_iters)
+
+    def tree_correlation(self, x):
+        x_proc = self._processor.transform(x)
+        return self._model.tree_correlation(x_proc)
+
+    def conditional_ate(self, x, mask):
+        """
+        ATE with CI over a subgroup. `mask` is a boolean array or, when `x` is a
+        DataFrame, a filter expression such as ``"age > 50 and sex == 'F'"``.
+        """
+        if isinstance(mask, str):
+            if not isinstance(x, pd.DataFrame):
+                raise ValueError("Filter expressions require `x` to be a DataFrame")
+            mask = x.eval(mask)
+        mask = np.asarray(mask, dtype=bool)
+        x_proc = self._processor.transform(x)
+        return self._model.conditional_ate(x_proc, mask)
+
+    def weighted_ate(self, x, weights):
+        """
+        ATE with CI for a reweighted population, e.g. a target demographic mix.
+        `weights` is one non-negative weight per row of `x`, or a callable that takes
+        `x` and returns them.
+        """
+        if callable(weights):
+            weights = weights(x)
+        weights = np.asarray(weights, dtype=np.float64).ravel()
+        x_proc = self._processor.transform(x)
+        return self._model.weighted_ate(x_proc, weights)
+
+    def effect_relative_to(self, x, reference_row="mean"):
+        """
+        Each row's effect minus the effect of a baseline profile. `reference_row` is
+        ``"mean"`` (the column means of `x`), a dict or Series of feature values, or a
+        row already in the model's feature space.
+        """
+        x_proc = self._processor.transform(x)
+        if isinstance(reference_row, str):
+            if reference_row != "mean":
+                raise ValueError(f"Unknown reference_row: {reference_row}. Use 'mean', a dict or a Series")
+            ref_proc = x_proc.mean(axis=0)
+        elif isinstance(reference_row, (dict, pd.Series)):
+            ref_proc = self._processor.transform(pd.DataFrame([reference_row]))[0]
+        else:
+            ref_proc = np.asarray(reference_row, dtype=np.float64).ravel()
+        return self._model.effect_relative_to(x_proc, [float(v) for v in ref_proc])
+
+    def calibrate_threshold(self, x, t, y, budget=None):
+        x_proc = self._processor.transform(x)
+        t = np.asarray(t, dtype=np.float64)
+        y = np.asarray(y, dtype=np.float64)
+        return self._model.calibrate_threshold(x_proc, t, y, budget)
+
+    def monotone_segment_effects(self, x, segments, order):
+        """
+        Segment effects forced to be non-decreasing along `order` (lowest tier first)
+        for tiered policies. `segments` is a column name of `x` or one label per row.
+        Returns a DataFrame per segment plus the max and mean absolute adjustment.
+        """
+        if isinstance(segments, str):
+            if not isinstance(x, pd.DataFrame) or segments not in x.columns:
+                raise ValueError(f"segment column '{segments}' not found in data")
+            labels = x[segments]
+            if segments not in self._processor.feature_names_in_:
+                x = x.drop(columns=[segments])
+        else:
+            labels = segments
+        order = list(order)
+        tiers = {label: i for i, label in enumerate(order)}
+        unknown = sorted({str(label) for label in labels if label not in tiers})
+        if unknown:
+            raise ValueError(f"segments not in the ordering: {unknown}")
+        codes = [tiers[label] for label in labels]
+
+        res = self._model.monotone_segment_effects(
+            self._processor.transform(x), codes, list(range(len(order)))
+        )
+        table = pd.DataFrame({
+            "segment": order,
+            "n_units": res["sizes"],
+            "effect": res["effects"],
+            "monotone_effect": res["monotone_effects"],
+        })
+        table["adjustment"] = table["monotone_effect"] - table["effect"]
+        return {
+            "segments": table,
+            "max_adjustment": res["max_adjustment"],
+            "mean_adjustment": res["mean_adjustment"],
+        }
+
+    def calibrate(self, x, t, y):
+        x_proc = self._processor.transform(x)
+        t = np.asarray(t, dtype=np.float64)
+        y = np.asarray(y, dtype=np.float64)
+        return self._model.calibrate(x_proc, t, y)
+
+    def effect_at_percentiles(self, x, feature, percentiles=(10, 25, 50, 75, 90, 100)):
+        """
+        Mean effect per percentile band of `feature` (a name or column index). Each
+        percentile closes a band opened by the previous one, so ``(90, 100)`` ends with
+        the top 10%.
+        """
+        if isinstance(feature, str):
+            if feature not in self.feature_names_out_:
+                raise ValueError(f"feature '{feature}' not found in model features")
+            feature = list(self.feature_names_out_).index(feature)
+        x_proc = self._processor.transform(x)
+        rows = self._model.effect_at_percentiles(x_proc, feature, [float(p) for p in percentiles])
+        return pd.DataFrame(
+            [(p, value, effect) for p, (value, effect) in zip(percentiles, rows)],
+            columns=["percentile", "feature_value", "mean_effect"],
+        )
+
+    def effect_profiles(self, x, n_bins=4):
+        """
+        Mean effect per quantile bin of every feature in long format (feature_name,
+        bin, mean_effect, count), ready for faceted plotting.
+        """
+        rows = self._model.effect_profiles(self._processor.transform(x), n_bins)
+        return pd.DataFrame(
+            [
+                (r["feature"], f"[{r['lower']:.4g}, {r['upper']:.4g}]", r["mean_effect"], r["count"])
+                for r in rows
+            ],
+            columns=["feature_name", "bin", "mean_effect", "count"],
+        )
+
+    def feature_importance(self, method="gain", n_repeats=10, seed=None):
+        """
+        Per-feature importance as a DataFrame (feature_name, importance, std).
+        `method="gain"` is the forest's split gain; `method="permutation"` is the
+        increase in effect error when the feature is shuffled, averaged over
+        `n_repeats` shuffles of the training data.
+        """
+        res = self._model.feature_importance(method, n_repeats, seed)
+        return pd.DataFrame({
+            "feature_name": res["feature"],
+            "importance": res["importance"],
+            "std": res["std"] if res["std"] is not None else np.nan,
+        })
+
+    def subgroup_effects(self, feature, thresholds):
+        """
+        Mean predicted effect and count of the training rows within subgroups of
+        `feature` (a column name or index) cut at `thresholds`, as a DataFrame with
+        columns lower, upper, mean_effect and count. Subgroups are closed on the right:
+        `thresholds=[50]` gives `feature <= 50` and `feature > 50`. The table also
+        appears in the `summary()` of results estimated afterwards.
+        """
+        return pd.DataFrame(self._model.subgroup_effects(feature, list(thresholds)),
+                            columns=["lower", "upper", "mean_effect", "count"])
+
+    def heterogeneity_gain(self, x, t, y):
+        """
+        How much the forest's per-unit effects beat a constant effect on `(x, t, y)`.
+        Use held-out data; in-sample gains are optimistic.
+        """
+        x_proc = self._processor.transform(x)
+        t = np.asarray(t, dtype=np.float64)
+        y = np.asarray(y, dtype=np.float64)
+        return self._model.heterogeneity_gain(x_proc, t, y)
+
+    def effect_variance_shares(self, x, n_grid=20):
+        x_proc = self._processor.transform(x)
+        return self._model.effect_variance_shares(x_proc, n_grid)
+
+    def rules_to_frame(self, top_k=20):
+        """Mined high-effect rules as a DataFrame sorted by mean effect."""
+        rows = self._model.mine_rules(top_k)
+        return pd.DataFrame(rows, columns=["condition", "mean_effect", "support", "frequency"])
+
+    def validate(self, n_folds=5, is_time_series=False, negative_control=None, n_permutations=19,
+                 placebo_ratio_threshold=DEFAULT_PLACEBO_RATIO_THRESHOLD,
+                 absolute_threshold=DEFAULT_PLACEBO_ABSOLUTE_THRESHOLD):
+        """
+        Placebo permutation test: treatment is shuffled `n_permutations` times and the
+        result's `p_value` is the share of placebo effects at least as extreme as the
+        observed one. The rows are also split into `n_folds` contiguous blocks and each
+        block is predicted by a copy of the model trained on the others; `fold_effects`
+        holds the held-out mean effects, which must agree for the result to be robust.
+        With `is_time_series=True` the rows are treated as time-ordered: the folds become
+        rolling-origin windows, each trained on all earlier rows and predicting the
+        next block, and the rows are never shuffled across time.
+        Both checks work for every method. `negative_control` (one value per training
+        row of an outcome the treatment cannot affect, forests only) adds a
+        negative-control falsification check.
+        With `n_permutations=0` the placebo is a single shuffle instead, passing when its
+        effect is below `placebo_ratio_threshold` times the estimated effect, or below
+        `absolute_threshold` when the estimated effect is zero. The permutation test
+        ignores both thresholds, so changing them without `n_permutations=0` raises.
+        """
+        if negative_control is not None:
+            negative_control = np.asarray(negative_control, dtype=np.float64)
+        return self._model.validate(n_folds, is_time_series, negative_control, n_permutations,
+                                    placebo_ratio_threshold, absolute_threshold)
     
-    def show(self, plot_type="graph"):
-        return self._model.show(plot_type)
+    def show(self, plot_type="graph", feature=None, n_bins=10, range=None):
+        """`plot_type="pdp"` plots the effect against `feature` (other features at their means).
+
+        For `plot_type="effect_dist"`, `n_bins` sets the histogram bin count and
+        `range=(min, max)` fixes its bounds, clamping effects outside them.
+        """
+        return self._model.show(plot_type, feature, n_bins, range)
     
-    def preview(self, plot_type="graph"):
-        return self._model.preview(plot_type)
+    def preview(self, plot_type="graph", theme="dark", feature=None, n_bins=10, range=None):
+        return self._model.preview(plot_type, theme, feature, n_bins, range)
     
-    def to_visual_tag(self, plot_type="graph"):
-        return self._model.to_visual_tag(plot_type)
+    def to_visual_tag(self, plot_type="graph", feature=None, n_bins=10, range=None):
+        return self._model.to_visual_tag(plot_type, feature, n_bins, range)
 
     def __getattr__(self, name):
         # Fallback to the internal Rust model
         return getattr(self._model, name)
 
-def create_model(features, treatment, outcome, method="forest", feature_names=None, use_mice=True):
+class FactorialModelWrapper(CausalModelWrapper):
+    """Model for two simultaneous binary treatments (factorial A/B/AB designs)."""
+
+    def __init__(self, model, processor, treatment_names):
+        super().__init__(model, processor)
+        self.treatment_names_ = list(treatment_names)
+
+    def estimate_effects(self, x):
+        """Main effect of each treatment and their interaction, one row per unit."""
+        effects = self._model.estimate_effects(self._processor.transform(x))
+        a, b = self.treatment_names_
+        index = x.index if isinstance(x, pd.DataFrame) else None
+        return pd.DataFrame(
+            {
+                f"effect_{a}": effects["effect_a"],
+                f"effect_{b}": effects["effect_b"],
+                "interaction": effects["interaction"],
+            },
+            index=index,
+        )
+
+def create_model(features, treatment=None, outcome=None, method=None, feature_names=None, use_mice=None, spec=None, **kwargs):
     """
     High-level factory function with automated preprocessing and unified API.
+
+    Extra keyword arguments (e.g. ``ci_spread``, ``ci_distribution``) are forwarded
+    to the Rust model factory. A two-column ``treatment`` fits a factorial model with
+    main effects for each column and their interaction. When a ``ModelSpec`` is given, its method, params,
+    column roles and preprocessing are used; explicit keyword arguments still win. Without a spec,
+    ``method`` defaults to ``"forest"`` and ``use_mice`` to ``True``.
     """
+    if spec is not None:
+        features, treatment, outcome = spec.resolve(features, treatment, outcome)
+        if method is None:
+            method = spec.method
+        if use_mice is None:
+            use_mice = spec.use_mice
+        kwargs = {**spec.params, **kwargs}
+    if method is None:
+        method = "forest"
+    if use_mice is None:
+        use_mice = True
+
+    if treatment is None or outcome is None:
+        raise ValueError("create_model requires treatment and outcome (or a ModelSpec naming them)")
+
+    settings = get_config()
+    if settings["n_jobs"] is not None and method == "forest":
+        kwargs.setdefault("n_jobs", settings["n_jobs"])
+    if settings["seed"] is not None and method == "forest":
+        kwargs.setdefault("seed", settings["seed"])
+
     if not isinstance(features, pd.DataFrame):
         features = pd.DataFrame(features)
+
+    # CUPED covariate: a column of `features` (removed from the model inputs) or an array
+    pre_covariate = kwargs.pop("pre_covariate", None)
+    if isinstance(pre_covariate, str):
+        if pre_covariate not in features.columns:
+            raise ValueError(f"pre_covariate '{pre_covariate}' not found in features")
+        pre_name = pre_covariate
+        pre_covariate = features[pre_name].to_numpy(dtype=np.float64)
+        features = features.drop(columns=[pre_name])
+    elif pre_covariate is not None:
+        pre_covariate = np.asarray(pre_covariate, dtype=np.float64).ravel()
+        if len(pre_covariate) != len(features):
+            raise ValueError(f"pre_covariate has {len(pre_covariate)} entries but features have {len(features)} rows")
+
+    # Predictions of an external outcome model; the forest is fit on y - baseline
+    baseline = kwargs.pop("baseline", None)
+    if baseline is not None:
+        baseline = np.asarray(baseline, dtype=np.float64).ravel()
+        if len(baseline) != len(features):
+            raise ValueError(f"baseline has {len(baseline)} entries but features have {len(features)} rows")
+
+    # Per-row survey or importance weights
+    sample_weight = kwargs.pop("sample_weight", None)
+    if sample_weight is not None:
+        sample_weight = np.asarray(sample_weight, dtype=np.float64).ravel()
+        if len(sample_weight) != len(features):
+            raise ValueError(f"sample_weight has {len(sample_weight)} entries but features have {len(features)} rows")
     
     if isinstance(treatment, (pd.Series, pd.DataFrame)):
         treatment_df = pd.DataFrame(treatment)
+    elif np.ndim(treatment) == 2 and np.shape(treatment)[1] == 2:
+        treatment_df = pd.DataFrame(treatment, columns=["a", "b"])
     else:
         treatment_df = pd.DataFrame(treatment, columns=["treatment"])
         
@@ -64,27 +420,70 @@ def create_model(features, treatment, outcome, method="forest", feature_names=No
         features = features[valid_mask]
         treatment_df = treatment_df[valid_mask]
         outcome_df = outcome_df[valid_mask]
+        if pre_covariate is not None:
+            pre_covariate = pre_covariate[valid_mask.to_numpy()]
+        if baseline is not None:
+            baseline = baseline[valid_mask.to_numpy()]
+        if sample_weight is not None:
+            sample_weight = sample_weight[valid_mask.to_numpy()]
 
-    processor = DataProcessor(use_mice=use_mice)
+    seed = settings["seed"]
+    processor = DataProcessor(use_mice=use_mice, random_state=42 if seed is None else seed)
     
     # 1. Preprocess features
     x_processed = processor.fit_transform(features)
     
+    if treatment_df.shape[1] == 2:
+        if method != "forest":
+            raise ValueError("Factorial designs are only available for method='forest'")
+        unsupported = [
+            name for name, value in
+            [("pre_covariate", pre_covariate), ("baseline", baseline), ("sample_weight", sample_weight)]
+            if value is not None
+        ]
+        if unsupported:
+            raise ValueError(f"{', '.join(unsupported)} cannot be combined with a two-column (factorial) treatment")
+        rust_model = _causalflow.create_factorial_model(
+            x_processed,
+            as_float64_contiguous(treatment_df.values, "treatment"),
+            as_float64_contiguous(outcome_df.values.ravel(), "outcome"),
+            feature_names=processor.feature_names_out_,
+            **kwargs
+        )
+        return FactorialModelWrapper(rust_model, processor, treatment_df.columns.astype(str))
+
     # 2. Preprocess treatment
     t_numeric = treatment_df.values.flatten()
         
     # 3. Preprocess outcome
     y_numeric = outcome_df.values.flatten()
 
+    if pre_covariate is not None:
+        kwargs["pre_covariate"] = pre_covariate
+    if baseline is not None:
+        kwargs["baseline"] = baseline
+    if sample_weight is not None:
+        kwargs["sample_weight"] = sample_weight
+
     # Create the internal Rust model
     rust_model = _causalflow.create_model(
         x_processed, 
-        t_numeric.astype(np.float64), 
-        y_numeric.astype(np.float64), 
+        as_float64_contiguous(t_numeric, "treatment"),
+        as_float64_contiguous(y_numeric, "outcome"),
         method, 
-        processor.feature_names_out_
+        processor.feature_names_out_,
+        **kwargs
     )
     
     return CausalModelWrapper(rust_model, processor)
 
-__all__ = ["create_model", "DataProcessor", "CausalModelWrapper"]
+__all__ = [
+    "create_model",
+    "DataProcessor",
+    "CausalModelWrapper",
+    "FactorialModelWrapper",
+    "ModelSpec",
+    "config",
+    "get_config",
+    "set_config",
+]
@@ -1,40 +1,283 @@
 import numpy as np
 import numpy.typing as npt
-from typing import List, Tuple, Optional, Any, Dict
+from typing import List, Tuple, Optional, Any, Dict, Union
+
+DEFAULT_PLACEBO_RATIO_THRESHOLD: float
+DEFAULT_PLACEBO_ABSOLUTE_THRESHOLD: float
 
 class InferenceResult:
+    # Categorical treatments: predictions, confidence_intervals and mean_effect cover arm 1
+    # versus arm 0 only (reading mean_effect warns with more than two arms); every arm's
+    # effects are the columns of arm_effects and their means are arm_mean_effects.
     mean_effect: float
     predictions: npt.NDArray[np.float64]
     confidence_intervals: List[Tuple[float, float]]
     feature_importance: List[float]
+    confidence_level: float
+    ate_confidence_interval: Optional[Tuple[float, float]]
+    effect_scale: str
+    n_clipped: int
+    arm_effects: Optional[npt.NDArray[np.float64]]
+    arm_mean_effects: Optional[List[float]]
+    index: Optional[Any]
+    def to_dataframe(self) -> Any: ...
     def summary(self) -> str: ...
-    def to_visual_tag(self) -> str: ...
-    def to_dict(self) -> Dict[str, Any]: ...
-    def show(self) -> None: ...
-    def preview(self) -> None: ...
-    def to_html(self) -> str: ...
+    def subgroup_effects(
+        self, values: npt.NDArray[np.float64], thresholds: List[float], feature: str = "feature"
+    ) -> List[Dict[str, Any]]: ...
+    def cate_quantiles(self, qs: List[float]) -> List[float]: ...
+    def to_visual_tag(
+        self, plot_type: str = "importance", min_percent: Optional[float] = None,
+        n_bins: int = 10, range: Optional[Tuple[float, float]] = None,
+    ) -> str: ...
+    def to_dict(
+        self, plot_type: str = "importance", min_percent: Optional[float] = None,
+        n_bins: int = 10, range: Optional[Tuple[float, float]] = None,
+    ) -> Dict[str, Any]: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_json(json: str) -> "InferenceResult": ...
+    def show(
+        self, plot_type: str = "importance", min_percent: Optional[float] = None,
+        n_bins: int = 10, range: Optional[Tuple[float, float]] = None,
+    ) -> None: ...
+    def preview(
+        self, plot_type: str = "importance", min_percent: Optional[float] = None, theme: str = "dark",
+        n_bins: int = 10, range: Optional[Tuple[float, float]] = None,
+    ) -> None: ...
+    def to_html(
+        self, plot_type: str = "importance", min_percent: Optional[float] = None, theme: str = "dark",
+        n_bins: int = 10, range: Optional[Tuple[float, float]] = None,
+    ) -> str: ...
 
 class ValidationResult:
     is_robust: bool
     message: str
+    p_value: Optional[float]
+    fold_effects: List[float]
+    per_fold_effects: List[float]  # same as fold_effects
+    placebo_effect: float
+    estimated_effect: float
+    robustness_score: float
 
 class Model:
-    def estimate_effects(self, x: npt.NDArray[np.float64]) -> InferenceResult: ...
-    def validate(self, n_folds: int = 5, is_time_series: bool = False) -> ValidationResult: ...
+    confidence_level: float
+    def estimate_effects(
+        self,
+        x: npt.NDArray[np.float64],
+        clusters: Optional[npt.NDArray[np.int64]] = None,
+        n_bootstrap: int = 200,
+    ) -> InferenceResult: ...
+    def estimate_oob_effects(self) -> InferenceResult: ...
+    def predict_multi_ci(self, x: npt.NDArray[np.float64], levels: List[float]) -> Dict[Any, Any]: ...
+    def set_confidence_level(self, level: float) -> None: ...
+    def validate(
+        self,
+        n_folds: int = 5,
+        is_time_series: bool = False,
+        negative_control: Optional[npt.NDArray[np.float64]] = None,
+        n_permutations: int = 19,
+        placebo_ratio_threshold: float = 0.2,
+        absolute_threshold: float = 0.05,
+    ) -> ValidationResult: ...
+    def refute(self, method: str = "random_common_cause", seed: Optional[int] = None) -> ValidationResult: ...
+    def tree_predictions(self, x: npt.NDArray[np.float64]) -> npt.NDArray[np.float64]: ...
+    def predict_contrast(self, x: npt.NDArray[np.float64], arm_a: int, arm_b: int) -> npt.NDArray[np.float64]: ...
+    def explain_one(self, row: List[float]) -> List[Tuple[float, int]]: ...
+    def tree_correlation(self, x: npt.NDArray[np.float64]) -> float: ...
+    def benchmark_predict(self, x: npt.NDArray[np.float64], n_iters: int = 100) -> Dict[str, Any]: ...
+    def top_features(self, k: int = 10) -> List[Tuple[str, float]]: ...
+    def feature_importance(
+        self, method: str = "gain", n_repeats: int = 10, seed: Optional[int] = None
+    ) -> Dict[str, Any]: ...
+    def effect_relative_to(
+        self,
+        x: npt.NDArray[np.float64],
+        reference_row: List[float],
+    ) -> npt.NDArray[np.float64]: ...
+    def conditional_ate(
+        self, x: npt.NDArray[np.float64], mask: npt.NDArray[np.bool_]
+    ) -> Dict[str, Any]: ...
+    def doubly_robust_ate(self) -> Dict[str, float]: ...
+    def weighted_ate(
+        self, x: npt.NDArray[np.float64], weights: npt.NDArray[np.float64]
+    ) -> Dict[str, Any]: ...
+    def calibrate_threshold(
+        self,
+        x: npt.NDArray[np.float64],
+        t: npt.NDArray[np.float64],
+        y: npt.NDArray[np.float64],
+        budget: Optional[int] = None,
+    ) -> Dict[str, Any]: ...
+    def monotone_segment_effects(
+        self,
+        x: npt.NDArray[np.float64],
+        segments: List[int],
+        order: List[int],
+    ) -> Dict[str, Any]: ...
+    def to_svg(
+        self, plot_type: str = "graph", feature: Optional[str] = None, n_bins: int = 10,
+        range: Optional[Tuple[float, float]] = None, width: int = 800, height: int = 500,
+    ) -> str: ...
+    def to_dot(self, plot_type: str = "graph") -> Optional[str]: ...
+    def to_graph_dot(self) -> str: ...  # deprecated: use to_dot()
+    def to_graph_mermaid(self) -> str: ...
+    def compare_naive_ate(self) -> Dict[str, float]: ...
+    def calibrate(
+        self,
+        x: npt.NDArray[np.float64],
+        t: npt.NDArray[np.float64],
+        y: npt.NDArray[np.float64],
+    ) -> None: ...
+    def add_trees(self, n_trees: int) -> None: ...
+    def effect_variance_shares(
+        self, x: npt.NDArray[np.float64], n_grid: int = 20
+    ) -> Dict[str, float]: ...
+    def effect_at_percentiles(
+        self,
+        x: npt.NDArray[np.float64],
+        feature_idx: int,
+        percentiles: List[float],
+    ) -> List[Tuple[float, float]]: ...
+    def effect_profiles(self, x: npt.NDArray[np.float64], n_bins: int = 4) -> List[Dict[str, Any]]: ...
+    def heterogeneity_gain(
+        self,
+        x: npt.NDArray[np.float64],
+        t: npt.NDArray[np.float64],
+        y: npt.NDArray[np.float64],
+    ) -> Dict[str, float]: ...
+    def importance_bootstrap(
+        self, n_boot: int = 20, seed: Optional[int] = None
+    ) -> Dict[str, Dict[str, float]]: ...
+    def to_report(
+        self,
+        plots: List[str] = ["graph", "importance", "effect_dist", "diagnostics"],
+        theme: str = "dark",
+    ) -> str: ...
+    def save(self, path: str) -> None: ...
+    def save_report(
+        self,
+        path: str,
+        plots: List[str] = ["graph", "importance", "effect_dist", "diagnostics"],
+        theme: str = "dark",
+    ) -> None: ...
+    def mine_rules(self, top_k: int = 20) -> List[Dict[str, Any]]: ...
+    def fingerprint(self) -> int: ...
+    def fit_report(self) -> Dict[str, Any]: ...
+    def cuped_report(self) -> Optional[Dict[str, float]]: ...
+    def data_summary(self) -> Dict[str, Any]: ...
+    def export_structure(self) -> Dict[str, Any]: ...
+    def subgroup_effects(
+        self, feature: Union[str, int], thresholds: List[float]
+    ) -> List[Dict[str, Any]]: ...
     def plot_importance(self) -> None: ...
     def plot_effects(self) -> None: ...
-    def to_visual_tag(self, plot_type: str = "graph") -> str: ...
-    def show(self, plot_type: str = "graph") -> None: ...
-    def preview(self, plot_type: str = "graph") -> None: ...
-    def to_html(self, plot_type: str = "graph") -> str: ...
+    def to_visual_tag(
+        self, plot_type: str = "graph", feature: Optional[str] = None,
+        n_bins: int = 10, range: Optional[Tuple[float, float]] = None,
+    ) -> str: ...
+    def show(
+        self, plot_type: str = "graph", feature: Optional[str] = None,
+        n_bins: int = 10, range: Optional[Tuple[float, float]] = None,
+    ) -> None: ...
+    def preview(
+        self, plot_type: str = "graph", theme: str = "dark", feature: Optional[str] = None,
+        n_bins: int = 10, range: Optional[Tuple[float, float]] = None,
+    ) -> None: ...
+    def to_html(
+        self, plot_type: str = "graph", theme: str = "dark", feature: Optional[str] = None,
+        n_bins: int = 10, range: Optional[Tuple[float, float]] = None,
+    ) -> str: ...
 
 def create_model(
     features: npt.NDArray[np.float64],
     treatment: npt.NDArray[np.float64],
     outcome: npt.NDArray[np.float64],
-    feature_names: Optional[List[str]] = None
+    method: str = "forest",
+    feature_names: Optional[List[str]] = None,
+    ci_spread: str = "tree_std",
+    ci_distribution: str = "normal",
+    split_strategy: str = "best_random",
+    feature_sample_weights: Optional[List[float]] = None,
+    constant_outcome: str = "zero",
+    max_depth: int = 5,
+    n_jobs: Optional[int] = None,
+    gain_balance_penalty: float = 1.0,
+    outcome_transform: str = "none",
+    pre_covariate: Optional[npt.NDArray[np.float64]] = None,
+    degenerate_leaf: str = "zero",
+    post_treatment_threshold: Optional[float] = 0.5,
+    min_leaf_treated: int = 0,
+    min_leaf_control: int = 0,
+    seed: Optional[int] = None,
+    subsample_ratio: float = 1.0,
+    baseline: Optional[npt.NDArray[np.float64]] = None,
+    effect_floor: Optional[float] = None,
+    effect_ceil: Optional[float] = None,
+    treatment_kind: str = "auto",
+    use_propensity: bool = False,
+    n_split_candidates: int = 10,
+    max_features: Union[str, int, float] = "sqrt",
+    min_gain: float = 0.0,
+    sample_weight: Optional[npt.NDArray[np.float64]] = None,
+    ridge_lambda: Optional[float] = None,
+    forest_config: Optional[Dict[str, Any]] = None,
+    relative_gain_tol: float = 0.0,
+    n_arms: Optional[int] = None,
 ) -> Model: ...
 
+class FactorialModel:
+    def estimate_effects(self, x: npt.NDArray[np.float64]) -> Dict[str, npt.NDArray[np.float64]]: ...
+
+def create_factorial_model(
+    features: npt.NDArray[np.float64],
+    treatment: npt.NDArray[np.float64],
+    outcome: npt.NDArray[np.float64],
+    feature_names: Optional[List[str]] = None,
+    ci_spread: str = "tree_std",
+    ci_distribution: str = "normal",
+    split_strategy: str = "best_random",
+    feature_sample_weights: Optional[List[float]] = None,
+    constant_outcome: str = "zero",
+    max_depth: int = 5,
+    n_jobs: Optional[int] = None,
+    gain_balance_penalty: float = 1.0,
+    outcome_transform: str = "none",
+    degenerate_leaf: str = "zero",
+    post_treatment_threshold: Optional[float] = 0.5,
+    min_leaf_treated: int = 0,
+    min_leaf_control: int = 0,
+    seed: Optional[int] = None,
+    subsample_ratio: float = 1.0,
+    effect_floor: Optional[float] = None,
+    effect_ceil: Optional[float] = None,
+    use_propensity: bool = False,
+    n_split_candidates: int = 10,
+    max_features: Union[str, int, float] = "sqrt",
+    min_gain: float = 0.0,
+    forest_config: Optional[Dict[str, Any]] = None,
+    relative_gain_tol: float = 0.0,
+) -> FactorialModel: ...
+
+# The returned model expects features already processed by the original
+# wrapper's `transform` (preprocessing is not saved).
+def load_model(path: str) -> Model: ...
+
 def plot_model(model: Model, plot: str = "graph") -> Dict[str, Any]: ...
 
+def data_summary(
+    treatment: npt.NDArray[np.float64],
+    outcome: npt.NDArray[np.float64],
+) -> Dict[str, Any]: ...
+
+def min_detectable_effect(
+    n_treated: int,
+    n_control: int,
+    outcome_std: float,
+    power: float = 0.8,
+    alpha: float = 0.05,
+) -> float: ...
+
+def clear_cache() -> int: ...
+
 def analyze_flow() -> str: ...
@@ -1,19 +1,45 @@
+import logging
+import warnings
+
 import pandas as pd
 import numpy as np
 from sklearn.experimental import enable_iterative_imputer
 from sklearn.impute import IterativeImputer, SimpleImputer
 
+logger = logging.getLogger(__name__)
+
+
+def as_float64_contiguous(values, name="input"):
+    """
+    Return `values` as a C-contiguous float64 array, which the Rust extension reads
+    row by row without a per-row copy. Anything else is converted once, with a debug log.
+    """
+    array = np.asarray(values)
+    if array.dtype == np.float64 and array.flags.c_contiguous:
+        return array
+    logger.debug(
+        "Copying %s (dtype=%s, c_contiguous=%s) to a C-contiguous float64 array",
+        name, array.dtype, array.flags.c_contiguous,
+    )
+    try:
+        return np.ascontiguousarray(array, dtype=np.float64)
+    except (TypeError, ValueError) as exc:
+        raise ValueError(f"{name} must be numeric, got dtype {array.dtype}") from exc
+
 class DataProcessor:
-    def __init__(self, use_mice=True):
+    def __init__(self, use_mice=True, random_state=42):
         self.use_mice = use_mice
+        self.random_state = random_state
         self.feature_names_in_ = None
         self.feature_names_out_ = None
+        self.named_features_ = False
         self.categorical_columns_ = []
         self.imputers_ = {}
         self.mice_imputer_ = None
         self.one_hot_encodings_ = {}
 
     def fit_transform(self, df):
+        self.named_features_ = isinstance(df, pd.DataFrame)
         if not isinstance(df, pd.DataFrame):
             df = pd.DataFrame(df)
         
@@ -36,7 +62,7 @@ class DataProcessor:
                 processed_df = pd.get_dummies(processed_df, columns=self.categorical_columns_)
             
             # Now apply MICE to all columns (which are now numeric)
-            self.mice_imputer_ = IterativeImputer(random_state=42)
+            self.mice_imputer_ = IterativeImputer(random_state=self.random_state)
             processed_df_values = self.mice_imputer_.fit_transform(processed_df)
             processed_df = pd.DataFrame(processed_df_values, columns=processed_df.columns)
         else:
@@ -56,11 +82,10 @@ class DataProcessor:
                 processed_df = pd.get_dummies(processed_df, columns=self.categorical_columns_)
         
         self.feature_names_out_ = [str(c) for c in processed_df.columns.tolist()]
-        return processed_df.values.astype(np.float64)
+        return as_float64_contiguous(processed_df.values, "features")
 
     def transform(self, df):
-        if not isinstance(df, pd.DataFrame):
-            df = pd.DataFrame(df, columns=self.feature_names_in_)
+        df = self._align_columns(df)
         
         processed_df = df.copy()
         
@@ -98,4 +123,32 @@ class DataProcessor:
                         processed_df[col] = 0
                 processed_df = processed_df[self.feature_names_out_]
 
-        return processed_df.values.astype(np.float64)
+        return as_float64_contiguous(processed_df.values, "features")
+
+    def _align_columns(self, df):
+        """
+        Put prediction columns in training order. DataFrames are aligned by name; raw
+        arrays are checked for column count, with a warning that order is assumed when
+        the model was trained on named columns.
+        """
+        if isinstance(df, pd.DataFrame):
+            missing = [c for c in self.feature_names_in_ if c not in df.columns]
+            if missing:
+                raise ValueError(f"Prediction data is missing training columns: {missing}")
+            return df[self.feature_names_in_]
+
+        values = np.asarray(df)
+        n_cols = values.shape[1] if values.ndim == 2 else 1
+        if n_cols != len(self.feature_names_in_):
+            raise ValueError(
+                f"Prediction data has {n_cols} columns but the model was trained on "
+                f"{len(self.feature_names_in_)}"
+            )
+        if self.named_features_:
+            warnings.warn(
+                "Predicting from an unnamed array; columns are assumed to be in training "
+                f"order {self.feature_names_in_}. Pass a DataFrame to align by name.",
+                UserWarning,
+                stacklevel=3,
+            )
+        return pd.DataFrame(values, columns=self.feature_names_in_)
@@ -0,0 +1,44 @@
+from contextlib import contextmanager
+
+_DEFAULTS = {
+    "n_jobs": None,
+    "seed": None,
+}
+
+_settings = dict(_DEFAULTS)
+
+
+def get_config():
+    """Current module-level defaults as a dict."""
+    return dict(_settings)
+
+
+def set_config(**kwargs):
+    """
+    Permanently override module-level defaults.
+
+    ``n_jobs`` limits the threads used to grow trees; ``seed`` seeds preprocessing
+    randomness (MICE imputation) and forest training. Arguments passed to
+    ``create_model`` take precedence.
+    """
+    unknown = set(kwargs) - set(_DEFAULTS)
+    if unknown:
+        raise ValueError(f"Unknown config options: {sorted(unknown)}. Supported: {sorted(_DEFAULTS)}")
+    _settings.update(kwargs)
+
+
+@contextmanager
+def config(**kwargs):
+    """
+    Temporarily override module-level defaults for everything created inside the block::
+
+        with causalflow.config(n_jobs=1, seed=42):
+            model = causalflow.create_model(X, T, Y)
+    """
+    previous = dict(_settings)
+    set_config(**kwargs)
+    try:
+        yield get_config()
+    finally:
+        _settings.clear()
+        _settings.update(previous)
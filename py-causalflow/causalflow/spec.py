@@ -0,0 +1,53 @@
+from dataclasses import dataclass, field
+from typing import Any, Dict, List, Optional
+
+import pandas as pd
+
+
+@dataclass
+class ModelSpec:
+    """
+    Reusable model configuration: method, hyperparameters, column roles,
+    treatment encoding and preprocessing. Define it once and pass it to
+    ``create_model(data, spec=spec)`` for every dataset.
+    """
+    method: str = "forest"
+    params: Dict[str, Any] = field(default_factory=dict)
+    treatment: Optional[str] = None
+    outcome: Optional[str] = None
+    features: Optional[List[str]] = None
+    treatment_encoding: Optional[Dict[Any, float]] = None
+    use_mice: bool = True
+
+    def resolve(self, data, treatment=None, outcome=None):
+        """
+        Split ``data`` into (features, treatment, outcome) using the spec's column
+        roles. Explicitly passed treatment/outcome arrays take precedence.
+        """
+        role_columns = []
+        if isinstance(data, pd.DataFrame):
+            if treatment is None and self.treatment is not None:
+                treatment = data[self.treatment]
+                role_columns.append(self.treatment)
+            if outcome is None and self.outcome is not None:
+                outcome = data[self.outcome]
+                role_columns.append(self.outcome)
+
+            if self.features is not None:
+                features = data[self.features]
+            else:
+                features = data.drop(columns=role_columns)
+        else:
+            features = data
+
+        if treatment is None or outcome is None:
+            raise ValueError("Treatment and outcome must be given explicitly or named in the ModelSpec")
+
+        if self.treatment_encoding is not None:
+            treatment = pd.Series(treatment)
+            unknown = set(treatment.dropna().unique()) - set(self.treatment_encoding)
+            if unknown:
+                raise ValueError(f"Treatment values missing from treatment_encoding: {sorted(map(str, unknown))}")
+            treatment = treatment.map(self.treatment_encoding)
+
+        return features, treatment, outcome
@@ -1,17 +1,46 @@
-use causalflow_core::forest::CausalForest;
-use causalflow_core::validation::validate_causal_structure;
-use numpy::{PyArray1, PyArray2, PyReadonlyArray2, ToPyArray};
+use causalflow_core::cache::clear_cache as core_clear_cache;
+use causalflow_core::diagnostics::{
+    cuped_adjust, data_summary as core_data_summary, min_detectable_effect as core_min_detectable_effect,
+    partial_dependence as core_partial_dependence, subgroup_effects as core_subgroup_effects,
+    SubgroupEffect,
+};
+use causalflow_core::errors::CausalFlowError;
+use causalflow_core::factorial::FactorialForest;
+use causalflow_core::forest::{
+    difference_in_means, AteComparison, CausalForest, CausalForestConfig, ConstantOutcome,
+    DegenerateLeafPolicy, EffectScale, IntervalDistribution, IntervalSpread, MaxFeatures,
+    OutcomeTransform, SplitStrategy, TreatmentKind,
+};
+use causalflow_core::parallel::init_thread_pool_from_env;
+use causalflow_core::policy::{calibrate_threshold, monotone_segment_effects, ThresholdMetric};
+use causalflow_core::stats::{
+    cluster_bootstrap_mean_ci, sorted_quantile, validate_confidence_level,
+};
+use causalflow_core::validation::{
+    refute_random_common_cause, validate_causal_structure, validate_negative_control,
+    DEFAULT_PLACEBO_ABSOLUTE_THRESHOLD, DEFAULT_PLACEBO_RATIO_THRESHOLD,
+};
+use ndarray::{ArrayView1, ArrayView2};
+use numpy::{PyArray1, PyArray2, PyReadonlyArray1, PyReadonlyArray2, ToPyArray};
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
 
 #[pyfunction]
 fn analyze_flow() -> PyResult<String> {
     Ok("Rust core analysis results".to_string())
 }
 
+/// Finite entries of `values`, ascending.
+fn sorted_finite(values: &[f64]) -> Vec<f64> {
+    let mut sorted: Vec<f64> = values.iter().cloned().filter(|v| v.is_finite()).collect();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    sorted
+}
+
 #[pyclass]
 pub struct InferenceResult {
-    #[pyo3(get)]
+    /// Read through the `mean_effect` getter, which warns on multi-arm results.
     pub mean_effect: f64,
     #[pyo3(get)]
     pub predictions: Py<PyArray1<f64>>,
@@ -19,43 +48,281 @@ pub struct InferenceResult {
     pub confidence_intervals: Vec<(f64, f64)>,
     #[pyo3(get)]
     pub feature_importance: Vec<f64>,
+    #[pyo3(get)]
+    pub confidence_level: f64,
+    /// Cluster-bootstrap interval for the ATE, when clusters were given.
+    #[pyo3(get)]
+    pub ate_confidence_interval: Option<(f64, f64)>,
+    /// "absolute" outcome differences, or "relative_change" for log-outcome models.
+    #[pyo3(get)]
+    pub effect_scale: String,
+    /// Predictions moved by the model's `effect_floor`/`effect_ceil`.
+    #[pyo3(get)]
+    pub n_clipped: usize,
     pub feature_names: Option<Vec<String>>,
+    /// Row identifiers of the predicted data (e.g. a DataFrame index), used by
+    /// `to_dataframe`. Not included in `to_json`.
+    #[pyo3(get, set)]
+    pub index: Option<PyObject>,
+    /// Feature name and table from the last `subgroup_effects` call, shown by `summary`.
+    pub subgroups: Option<(String, Vec<SubgroupEffect>)>,
+    /// One column per non-baseline arm (arm `k + 1` minus arm 0) for a categorical
+    /// treatment. `predictions`, `confidence_intervals` and `mean_effect` then describe
+    /// the arm 1 column only; `arm_mean_effects` has every arm's mean.
+    #[pyo3(get)]
+    pub arm_effects: Option<Py<PyArray2<f64>>>,
 }
 
 use causalflow_core::visualization::{LinkInfo, NodeInfo, VisualOutput};
 
+/// JSON document produced by `InferenceResult.to_json` and read by `from_json`.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct InferenceResultDocument {
+    schema_version: u32,
+    mean_effect: f64,
+    predictions: Vec<f64>,
+    confidence_intervals: Vec<(f64, f64)>,
+    feature_importance: Vec<f64>,
+    #[serde(default)]
+    feature_names: Option<Vec<String>>,
+    confidence_level: f64,
+    #[serde(default)]
+    ate_confidence_interval: Option<(f64, f64)>,
+    #[serde(default)]
+    effect_scale: EffectScale,
+    #[serde(default)]
+    n_clipped: usize,
+}
+
+fn effect_scale_name(scale: EffectScale) -> String {
+    match scale {
+        EffectScale::Absolute => "absolute",
+        EffectScale::RelativeChange => "relative_change",
+    }
+    .to_string()
+}
+
+const INFERENCE_RESULT_SCHEMA_VERSION: u32 = 1;
+
 #[pymethods]
 impl InferenceResult {
-    #[pyo3(signature = (plot_type = "importance"))]
-    fn to_visual_tag(&self, py: Python, plot_type: &str) -> String {
-        let visual = self.get_visual(py, plot_type);
-        format!("```json:causal-plot\n{}\n```", visual.to_json())
+    /// Mean of `predictions`. For a categorical treatment with more than two arms this is
+    /// arm 1 versus arm 0 only, so reading it warns; see `arm_mean_effects`.
+    #[getter]
+    fn mean_effect(&self, py: Python) -> PyResult<f64> {
+        let n_contrasts = self
+            .arm_effects
+            .as_ref()
+            .map_or(0, |arms| arms.as_ref(py).shape()[1]);
+        if n_contrasts > 1 {
+            PyErr::warn(
+                py,
+                py.get_type::<pyo3::exceptions::PyUserWarning>(),
+                "mean_effect of a categorical result is arm 1 versus arm 0 only; \
+                 use arm_mean_effects for every arm",
+                1,
+            )?;
+        }
+        Ok(self.mean_effect)
     }
 
-    #[pyo3(signature = (plot_type = "importance"))]
-    fn to_dict(&self, py: Python, plot_type: &str) -> PyResult<PyObject> {
-        let visual = self.get_visual(py, plot_type);
+    /// Mean effect of each non-baseline arm against arm 0 (one per `arm_effects`
+    /// column), or `None` unless the treatment is categorical.
+    #[getter]
+    fn arm_mean_effects(&self, py: Python) -> Option<Vec<f64>> {
+        self.arm_effects.as_ref().map(|arms| {
+            let arms = unsafe { arms.as_ref(py).as_array() };
+            arms.columns()
+                .into_iter()
+                .map(|column| column.mean().unwrap_or(0.0))
+                .collect()
+        })
+    }
+
+    /// `n_bins` and `range` shape the `effect_dist` histogram.
+    #[pyo3(signature = (plot_type = "importance", min_percent = None, n_bins = 10, range = None))]
+    fn to_visual_tag(
+        &self,
+        py: Python,
+        plot_type: &str,
+        min_percent: Option<f64>,
+        n_bins: usize,
+        range: Option<(f64, f64)>,
+    ) -> PyResult<String> {
+        let visual = self.get_visual(py, plot_type, min_percent, n_bins, range)?;
+        Ok(format!("```json:causal-plot\n{}\n```", visual.to_json()))
+    }
+
+    #[pyo3(signature = (plot_type = "importance", min_percent = None, n_bins = 10, range = None))]
+    fn to_dict(
+        &self,
+        py: Python,
+        plot_type: &str,
+        min_percent: Option<f64>,
+        n_bins: usize,
+        range: Option<(f64, f64)>,
+    ) -> PyResult<PyObject> {
+        let visual = self.get_visual(py, plot_type, min_percent, n_bins, range)?;
         let json_str = visual.to_json();
         let json_module = py.import("json")?;
         let dict = json_module.call_method1("loads", (json_str,))?;
         Ok(dict.to_object(py))
     }
 
-    #[pyo3(signature = (plot_type = "importance"))]
-    fn show(&self, py: Python, plot_type: &str) {
-        println!("{}", self.to_visual_tag(py, plot_type));
+    #[pyo3(signature = (plot_type = "importance", min_percent = None, n_bins = 10, range = None))]
+    fn show(
+        &self,
+        py: Python,
+        plot_type: &str,
+        min_percent: Option<f64>,
+        n_bins: usize,
+        range: Option<(f64, f64)>,
+    ) -> PyResult<()> {
+        println!(
+            "{}",
+            self.to_visual_tag(py, plot_type, min_percent, n_bins, range)?
+        );
+        Ok(())
+    }
+
+    /// `theme` is an ECharts theme: "dark" (default), "light" or a registered custom name.
+    #[pyo3(signature = (
+        plot_type = "importance",
+        min_percent = None,
+        theme = "dark",
+        n_bins = 10,
+        range = None
+    ))]
+    fn preview(
+        &self,
+        py: Python,
+        plot_type: &str,
+        min_percent: Option<f64>,
+        theme: &str,
+        n_bins: usize,
+        range: Option<(f64, f64)>,
+    ) -> PyResult<()> {
+        let visual = self.get_visual(py, plot_type, min_percent, n_bins, range)?;
+        render_preview(py, &visual, theme)
+    }
+
+    #[pyo3(signature = (
+        plot_type = "importance",
+        min_percent = None,
+        theme = "dark",
+        n_bins = 10,
+        range = None
+    ))]
+    fn to_html(
+        &self,
+        py: Python,
+        plot_type: &str,
+        min_percent: Option<f64>,
+        theme: &str,
+        n_bins: usize,
+        range: Option<(f64, f64)>,
+    ) -> PyResult<String> {
+        let visual = self.get_visual(py, plot_type, min_percent, n_bins, range)?;
+        Ok(render_html_fragment(&visual, theme))
+    }
+
+    /// Serializes predictions, ATE, intervals, importances and feature names as one
+    /// JSON document that `InferenceResult.from_json` can read back.
+    fn to_json(&self, py: Python) -> PyResult<String> {
+        let doc = InferenceResultDocument {
+            schema_version: INFERENCE_RESULT_SCHEMA_VERSION,
+            mean_effect: self.mean_effect,
+            predictions: self.predictions.as_ref(py).to_owned_array().to_vec(),
+            confidence_intervals: self.confidence_intervals.clone(),
+            feature_importance: self.feature_importance.clone(),
+            feature_names: self.feature_names.clone(),
+            confidence_level: self.confidence_level,
+            ate_confidence_interval: self.ate_confidence_interval,
+            effect_scale: if self.effect_scale == "relative_change" {
+                EffectScale::RelativeChange
+            } else {
+                EffectScale::Absolute
+            },
+            n_clipped: self.n_clipped,
+        };
+        serde_json::to_string(&doc).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    #[staticmethod]
+    fn from_json(py: Python, json: &str) -> PyResult<InferenceResult> {
+        let doc: InferenceResultDocument =
+            serde_json::from_str(json).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        if doc.schema_version != INFERENCE_RESULT_SCHEMA_VERSION {
+            return Err(PyValueError::new_err(format!(
+                "Unsupported InferenceResult schema_version {} (expected {})",
+                doc.schema_version, INFERENCE_RESULT_SCHEMA_VERSION
+            )));
+        }
+        Ok(InferenceResult {
+            mean_effect: doc.mean_effect,
+            predictions: doc.predictions.to_pyarray(py).to_owned(),
+            confidence_intervals: doc.confidence_intervals,
+            feature_importance: doc.feature_importance,
+            confidence_level: doc.confidence_level,
+            ate_confidence_interval: doc.ate_confidence_interval,
+            effect_scale: effect_scale_name(doc.effect_scale),
+            n_clipped: doc.n_clipped,
+            feature_names: doc.feature_names,
+            index: None,
+            subgroups: None,
+            arm_effects: None,
+        })
+    }
+
+    /// Mean effect and count per subgroup of `values` (the raw feature value of each
+    /// predicted row) cut at `thresholds`. The table is kept and shown by `summary()`.
+    #[pyo3(signature = (values, thresholds, feature = "feature"))]
+    fn subgroup_effects(
+        &mut self,
+        py: Python,
+        values: PyReadonlyArray1<f64>,
+        thresholds: Vec<f64>,
+        feature: &str,
+    ) -> PyResult<PyObject> {
+        let predictions = self.predictions.as_ref(py).to_owned_array();
+        let rows = core_subgroup_effects(values.as_array(), predictions.view(), &thresholds)?;
+        let table = to_py_object(py, &rows)?;
+        self.subgroups = Some((feature.to_string(), rows));
+        Ok(table)
     }
 
-    #[pyo3(signature = (plot_type = "importance"))]
-    fn preview(&self, py: Python, plot_type: &str) -> PyResult<()> {
-        let visual = self.get_visual(py, plot_type);
-        render_preview(py, &visual)
+    /// Linearly interpolated quantiles of the finite individual effects, one per
+    /// entry of `qs` (each in `[0, 1]`); NaN when no effect is finite.
+    fn cate_quantiles(&self, py: Python, qs: Vec<f64>) -> PyResult<Vec<f64>> {
+        if let Some(q) = qs.iter().find(|q| !(0.0..=1.0).contains(*q)) {
+            return Err(PyValueError::new_err(format!(
+                "quantiles must be between 0 and 1, got {}",
+                q
+            )));
+        }
+        let sorted = sorted_finite(&self.predictions.as_ref(py).to_owned_array().to_vec());
+        Ok(qs.iter().map(|&q| sorted_quantile(&sorted, q)).collect())
     }
 
-    #[pyo3(signature = (plot_type = "importance"))]
-    fn to_html(&self, py: Python, plot_type: &str) -> String {
-        let visual = self.get_visual(py, plot_type);
-        render_html_fragment(&visual)
+    /// Per-row effects and interval bounds as a pandas DataFrame, indexed by `index`
+    /// when set so results join back to the original rows.
+    fn to_dataframe(&self, py: Python) -> PyResult<PyObject> {
+        let (lower, upper): (Vec<f64>, Vec<f64>) =
+            self.confidence_intervals.iter().cloned().unzip();
+        let columns = PyDict::new(py);
+        columns.set_item("effect", self.predictions.as_ref(py))?;
+        columns.set_item("ci_lower", lower)?;
+        columns.set_item("ci_upper", upper)?;
+
+        let kwargs = PyDict::new(py);
+        if let Some(index) = &self.index {
+            kwargs.set_item("index", index)?;
+        }
+        let frame = py
+            .import("pandas")?
+            .getattr("DataFrame")?
+            .call((columns,), Some(kwargs))?;
+        Ok(frame.to_object(py))
     }
 
     fn __repr__(&self, py: Python) -> String {
@@ -76,6 +343,22 @@ impl InferenceResult {
             "| Number of Observations     | {:14} |\n",
             num_obs
         ));
+        table.push_str(&format!(
+            "| Confidence Level           | {:13.1}% |\n",
+            self.confidence_level * 100.0
+        ));
+        if self.effect_scale == "relative_change" {
+            table.push_str(&format!(
+                "| Effect Scale               | {:>14} |\n",
+                "rel. change"
+            ));
+        }
+        if let Some((lo, hi)) = self.ate_confidence_interval {
+            table.push_str(&format!(
+                "| ATE Cluster Bootstrap CI   | {:>14} |\n",
+                format!("[{:.3}, {:.3}]", lo, hi)
+            ));
+        }
         table.push_str("+----------------------------+----------------+\n");
 
         table.push_str("\n[Feature Importance]\n");
@@ -93,7 +376,34 @@ impl InferenceResult {
             }
         }
 
+        if let Some((feature, rows)) = &self.subgroups {
+            table.push_str(&format!("\n[Subgroup Effects by {}]\n", feature));
+            for row in rows {
+                let range = match (row.lower, row.upper) {
+                    (None, Some(hi)) => format!("<= {}", hi),
+                    (Some(lo), Some(hi)) => format!("({}, {}]", lo, hi),
+                    (Some(lo), None) => format!("> {}", lo),
+                    (None, None) => "all".to_string(),
+                };
+                table.push_str(&format!(
+                    "{:<20}: {:.4} (n={})\n",
+                    range, row.mean_effect, row.count
+                ));
+            }
+        }
+
         table.push_str("\n[Interpretation]\n");
+        let sorted = sorted_finite(&self.predictions.as_ref(py).to_owned_array().to_vec());
+        let heterogeneity = (!sorted.is_empty()).then(|| {
+            let positive = sorted.iter().filter(|&&p| p > 0.0).count();
+            format!(
+                "Individual effects: 10th percentile {:.4}, median {:.4}, 90th percentile {:.4}; {:.1}% of units have a positive effect.\n",
+                sorted_quantile(&sorted, 0.1),
+                sorted_quantile(&sorted, 0.5),
+                sorted_quantile(&sorted, 0.9),
+                100.0 * positive as f64 / sorted.len() as f64
+            )
+        });
         if self.mean_effect > 0.0 {
             table.push_str(&format!(
                 "The treatment has a POSITIVE average effect of {:.4}.\n",
@@ -107,52 +417,75 @@ impl InferenceResult {
         } else {
             table.push_str("The treatment has NO average effect on the outcome.\n");
         }
+        if let Some(line) = heterogeneity {
+            table.push_str(&line);
+        }
+        if self.effect_scale == "relative_change" {
+            table.push_str(&format!(
+                "Effects are relative changes in the outcome (log-outcome model): {:+.1}% on average.\n",
+                self.mean_effect * 100.0
+            ));
+        }
+        if self.n_clipped > 0 {
+            table.push_str(&format!(
+                "{} of {} predictions ({:.1}%) were clipped to the effect bounds; this prior is binding, not just a safeguard.\n",
+                self.n_clipped,
+                num_obs,
+                100.0 * self.n_clipped as f64 / num_obs.max(1) as f64
+            ));
+        }
+        if let Some((lo, hi)) = self.ate_confidence_interval {
+            let level = self.confidence_level * 100.0;
+            if lo <= 0.0 && 0.0 <= hi {
+                table.push_str(&format!(
+                    "Zero lies inside the {:.1}% ATE interval [{:.4}, {:.4}]: the effect is not statistically distinguishable from no effect.\n",
+                    level, lo, hi
+                ));
+            } else {
+                table.push_str(&format!(
+                    "Zero lies outside the {:.1}% ATE interval [{:.4}, {:.4}]: the effect is statistically significant at the {:.1}% level.\n",
+                    level,
+                    lo,
+                    hi,
+                    100.0 - level
+                ));
+            }
+        }
 
         table
     }
 }
 
+/// Checks the `effect_dist` histogram options passed from Python.
+fn validate_histogram_bins(n_bins: usize, range: Option<(f64, f64)>) -> PyResult<()> {
+    if n_bins == 0 {
+        return Err(PyValueError::new_err("n_bins must be at least 1"));
+    }
+    if let Some((lo, hi)) = range {
+        if !(lo.is_finite() && hi.is_finite() && lo < hi) {
+            return Err(PyValueError::new_err(format!(
+                "range must be a finite (min, max) with min < max, got ({}, {})",
+                lo, hi
+            )));
+        }
+    }
+    Ok(())
+}
+
 impl InferenceResult {
-    fn get_visual(&self, py: Python, plot_type: &str) -> VisualOutput {
-        match plot_type {
+    fn get_visual(
+        &self,
+        py: Python,
+        plot_type: &str,
+        min_percent: Option<f64>,
+        n_bins: usize,
+        range: Option<(f64, f64)>,
+    ) -> PyResult<VisualOutput> {
+        validate_histogram_bins(n_bins, range)?;
+        Ok(match plot_type {
             "effect_dist" => {
-                let preds_array = self.predictions.as_ref(py);
-                let preds = preds_array.to_owned_array().to_vec();
-
-                // Calculate real histogram
-                let min = preds.iter().fold(f64::INFINITY, |a, &b| a.min(b));
-                let max = preds.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-                let n_bins = 10;
-                let bin_width = if (max - min).abs() < f64::EPSILON {
-                    1.0
-                } else {
-                    (max - min) / n_bins as f64
-                };
-
-                let mut bins = Vec::new();
-                for i in 0..n_bins {
-                    bins.push(min + i as f64 * bin_width);
-                }
-
-                let mut counts = vec![0u64; n_bins];
-                for &p in &preds {
-                    let mut b = if bin_width == 0.0 {
-                        0
-                    } else {
-                        ((p - min) / bin_width) as usize
-                    };
-                    if b >= n_bins {
-                        b = n_bins - 1;
-                    }
-                    counts[b] += 1;
-                }
-
-                VisualOutput::effect_dist(
-                    "Individual Treatment Effect Distribution".to_string(),
-                    "Frequency".to_string(),
-                    bins,
-                    counts,
-                )
+                let preds = self.predictions.as_ref(py).to_owned_array().to_vec();
+                VisualOutput::effect_dist_with_bins(&preds, n_bins, range)
             }
             _ => {
                 let labels = self.feature_names.clone().unwrap_or_else(|| {
@@ -160,9 +493,18 @@ impl InferenceResult {
                         .map(|i| format!("Feature {}", i))
                         .collect()
                 });
-                VisualOutput::feature_importance(labels, self.feature_importance.clone())
+                match min_percent {
+                    Some(min) => VisualOutput::feature_importance_percent(
+                        labels,
+                        self.feature_importance.clone(),
+                        min,
+                    ),
+                    None => {
+                        VisualOutput::feature_importance(labels, self.feature_importance.clone())
+                    }
+                }
             }
-        }
+        })
     }
 }
 
@@ -172,22 +514,81 @@ struct ValidationResult {
     pub is_robust: bool,
     #[pyo3(get)]
     pub message: String,
+    #[pyo3(get)]
+    pub p_value: Option<f64>,
+    #[pyo3(get)]
+    pub fold_effects: Vec<f64>,
+    /// Absolute placebo ATE (mean over permutations); NaN when not computed.
+    #[pyo3(get)]
+    pub placebo_effect: f64,
+    /// Absolute ATE of the validated model; NaN when it could not predict.
+    #[pyo3(get)]
+    pub estimated_effect: f64,
+    /// `placebo_effect / estimated_effect`, for user-chosen robustness thresholds.
+    #[pyo3(get)]
+    pub robustness_score: f64,
+}
+
+impl From<causalflow_core::validation::ValidationResult> for ValidationResult {
+    fn from(res: causalflow_core::validation::ValidationResult) -> Self {
+        Self {
+            is_robust: res.is_robust,
+            message: res.message,
+            p_value: res.p_value,
+            fold_effects: res.fold_effects,
+            placebo_effect: res.placebo_effect,
+            estimated_effect: res.estimated_effect,
+            robustness_score: res.robustness_score,
+        }
+    }
+}
+
+#[pymethods]
+impl ValidationResult {
+    /// Mean held-out effect of each cross-validation fold; the same list as `fold_effects`.
+    #[getter]
+    fn per_fold_effects(&self) -> Vec<f64> {
+        self.fold_effects.clone()
+    }
 }
 
 use causalflow_core::linear::LinearCausalModel;
+use causalflow_core::meta::{DoublyRobustModel, LinearRegressor, SLearner, TLearner};
 use causalflow_core::model::CausalModel;
 
-#[derive(Clone)]
+/// Serialized with a `"method"` tag so a saved model reloads as the right variant.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 enum CausalMethod {
     Forest(CausalForest),
     Linear(LinearCausalModel),
+    #[serde(rename = "t-learner")]
+    TLearner(TLearner<LinearRegressor>),
+    #[serde(rename = "s-learner")]
+    SLearner(SLearner<LinearRegressor>),
+    Aipw(DoublyRobustModel<LinearRegressor>),
+}
+
+/// On-disk JSON document written by `Model.save` and read by `load_model`.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ModelDocument {
+    format_version: u32,
+    model: CausalMethod,
+    feature_names: Option<Vec<String>>,
+    confidence_level: f64,
 }
 
+const MODEL_FORMAT_VERSION: u32 = 1;
+
 impl CausalMethod {
     fn as_trait(&self) -> &dyn CausalModel {
         match self {
             CausalMethod::Forest(f) => f,
             CausalMethod::Linear(l) => l,
+            CausalMethod::TLearner(m) => m,
+            CausalMethod::SLearner(m) => m,
+            CausalMethod::Aipw(m) => m,
         }
     }
 
@@ -195,6 +596,9 @@ impl CausalMethod {
         match self {
             CausalMethod::Forest(f) => f,
             CausalMethod::Linear(l) => l,
+            CausalMethod::TLearner(m) => m,
+            CausalMethod::SLearner(m) => m,
+            CausalMethod::Aipw(m) => m,
         }
     }
 }
@@ -205,12 +609,36 @@ struct Model {
     method: CausalMethod,
     x: Py<PyArray2<f64>>,
     t: Py<PyArray1<f64>>,
+    /// The outcome the model was fit on: CUPED- or baseline-adjusted when
+    /// `pre_covariate` or `baseline` was given, so refits in validation, refutation and
+    /// `add_trees` use the same target.
     y: Py<PyArray1<f64>>,
+    /// Row weights the model was fit with; refits in validation, refutation and
+    /// `add_trees` reuse them.
+    sample_weight: Option<Py<PyArray1<f64>>>,
     feature_names: Option<Vec<String>>,
+    confidence_level: f64,
+    /// The last `subgroup_effects` table, shown by the `summary()` of later results.
+    subgroups: Option<(String, Vec<SubgroupEffect>)>,
 }
 
 impl Model {
-    fn get_visual(&self, py: Python, plot_type: &str) -> VisualOutput {
+    fn sample_weight_view<'py>(&self, py: Python<'py>) -> Option<ArrayView1<'py, f64>> {
+        self.sample_weight
+            .as_ref()
+            .map(|w| unsafe { w.clone_ref(py).into_ref(py).as_array() })
+    }
+
+    /// Feature importances for the graph and importance plots. Forests report their
+    /// split gains directly, so a model from `load_model` (no training rows) still has them.
+    fn model_importance(&self, x_view: ArrayView2<f64>) -> PyResult<Vec<f64>> {
+        match &self.method {
+            CausalMethod::Forest(forest) => Ok(forest.feature_importance()),
+            method => Ok(method.as_trait().predict(x_view)?.feature_importance),
+        }
+    }
+
+    fn get_visual(&self, py: Python, plot_type: &str) -> PyResult<VisualOutput> {
         let x_view = unsafe { self.x.as_ref(py).as_array() };
         match plot_type {
             "graph" => {
@@ -235,8 +663,7 @@ impl Model {
                     weight: 1.0,
                 });
 
-                let res = self.method.as_trait().predict(x_view).unwrap_or_else(|_| self.method.as_trait().predict(x_view).unwrap()); // Simplified for visual
-                let importance = res.feature_importance;
+                let importance = self.model_importance(x_view)?;
 
                 if let Some(names) = &self.feature_names {
                     for (i, name) in names.iter().enumerate() {
@@ -259,92 +686,420 @@ impl Model {
                         });
                     }
                 }
-                VisualOutput::causal_graph(nodes, links)
+                Ok(VisualOutput::causal_graph(nodes, links))
             }
             "effect_dist" => {
-                let res = self.method.as_trait().predict(x_view).unwrap_or_else(|_| self.method.as_trait().predict(x_view).unwrap());
-                let preds = res.predictions.to_vec();
-
-                // Calculate real histogram
-                let min = preds.iter().fold(f64::INFINITY, |a, &b| a.min(b));
-                let max = preds.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-                let n_bins = 10;
-                let bin_width = if (max - min).abs() < f64::EPSILON {
-                    1.0
-                } else {
-                    (max - min) / n_bins as f64
-                };
-
-                let mut bins = Vec::new();
-                for i in 0..n_bins {
-                    bins.push(min + i as f64 * bin_width);
+                let res = self.method.as_trait().predict(x_view)?;
+                Ok(VisualOutput::effect_histogram(&res.predictions.to_vec()))
+            }
+            "importance" => {
+                let importance = self.model_importance(x_view)?;
+                Ok(VisualOutput::feature_importance(
+                    self.feature_labels(importance.len()),
+                    importance,
+                ))
+            }
+            "importance_stability" => match &self.method {
+                CausalMethod::Forest(forest) => {
+                    let (t_view, y_view) =
+                        unsafe { (self.t.as_ref(py).as_array(), self.y.as_ref(py).as_array()) };
+                    let (mean, std) = forest
+                        .importance_bootstrap(
+                            x_view,
+                            t_view,
+                            y_view,
+                            self.sample_weight_view(py),
+                            20,
+                            None,
+                        )
+                        .unwrap_or_default();
+                    Ok(VisualOutput::importance_stability(
+                        self.feature_labels(mean.len()),
+                        mean,
+                        std,
+                    ))
                 }
-
-                let mut counts = vec![0u64; n_bins];
-                for &p in &preds {
-                    let mut b = if bin_width == 0.0 {
-                        0
-                    } else {
-                        ((p - min) / bin_width) as usize
-                    };
-                    if b >= n_bins {
-                        b = n_bins - 1;
-                    }
-                    counts[b] += 1;
+                _ => Ok(VisualOutput::importance_stability(vec![], vec![], vec![])),
+            },
+            "variance_shares" => match &self.method {
+                CausalMethod::Forest(forest) => {
+                    let shares = forest
+                        .effect_variance_shares(x_view, 20)
+                        .unwrap_or_default();
+                    Ok(VisualOutput::variance_shares(
+                        self.feature_labels(shares.len()),
+                        shares,
+                    ))
                 }
+                _ => Ok(VisualOutput::variance_shares(vec![], vec![])),
+            },
+            _ => Ok(VisualOutput::feature_importance(vec![], vec![])),
+        }
+    }
 
-                VisualOutput::effect_dist(
-                    "Individual Treatment Effect Distribution".to_string(),
-                    "Frequency".to_string(),
-                    bins,
-                    counts,
-                )
+    fn diagnostics_html(&self, py: Python) -> PyResult<String> {
+        let (t_view, y_view) =
+            unsafe { (self.t.as_ref(py).as_array(), self.y.as_ref(py).as_array()) };
+        let mut html = String::from("<h3>Data Summary</h3>\n");
+        html.push_str(&html_table(
+            &serde_json::to_value(core_data_summary(t_view, y_view)?).unwrap_or_default(),
+        ));
+        if let CausalMethod::Forest(forest) = &self.method {
+            if let Some(report) = &forest.fit_report {
+                html.push_str("<h3>Fit Report</h3>\n");
+                html.push_str(&html_table(
+                    &serde_json::to_value(report).unwrap_or_default(),
+                ));
             }
-            _ => VisualOutput::feature_importance(vec![], vec![]),
         }
+        Ok(html)
+    }
+
+    fn feature_labels(&self, n_features: usize) -> Vec<String> {
+        self.feature_names
+            .clone()
+            .unwrap_or_else(|| (0..n_features).map(|i| format!("Feature {}", i)).collect())
+    }
+
+    /// Column index of `feature` among the `n_features` training columns.
+    fn resolve_feature(&self, feature: &FeatureArg, n_features: usize) -> PyResult<usize> {
+        let idx = match *feature {
+            FeatureArg::Name(name) => self
+                .feature_labels(n_features)
+                .iter()
+                .position(|label| label == name),
+            FeatureArg::Index(idx) => (idx < n_features).then_some(idx),
+        };
+        idx.ok_or_else(|| match *feature {
+            FeatureArg::Name(name) => PyValueError::new_err(format!("Unknown feature: {}", name)),
+            FeatureArg::Index(idx) => CausalFlowError::FeatureOutOfBounds(idx).into(),
+        })
+    }
+
+    /// `get_visual` plus the plots that take a feature (`"pdp"`), which can fail.
+    fn visual(
+        &self,
+        py: Python,
+        plot_type: &str,
+        feature: Option<&str>,
+        n_bins: usize,
+        range: Option<(f64, f64)>,
+    ) -> PyResult<VisualOutput> {
+        validate_histogram_bins(n_bins, range)?;
+        if plot_type == "effect_dist" {
+            let x_view = unsafe { self.x.as_ref(py).as_array() };
+            let res = self.method.as_trait().predict(x_view)?;
+            return Ok(VisualOutput::effect_dist_with_bins(
+                &res.predictions.to_vec(),
+                n_bins,
+                range,
+            ));
+        }
+        if plot_type != "pdp" {
+            return self.get_visual(py, plot_type);
+        }
+        let feature =
+            feature.ok_or_else(|| PyValueError::new_err("plot_type='pdp' requires a feature"))?;
+        let x_view = unsafe { self.x.as_ref(py).as_array() };
+        let feature_idx = self.resolve_feature(&FeatureArg::Name(feature), x_view.ncols())?;
+        let (grid, effects) =
+            core_partial_dependence(self.method.as_trait(), x_view, feature_idx, 20)?;
+        Ok(VisualOutput::partial_dependence(
+            feature.to_string(),
+            grid,
+            effects,
+        ))
     }
 }
 
 #[pymethods]
 impl Model {
-    #[pyo3(signature = (plot_type = "graph"))]
-    fn to_visual_tag(&self, py: Python, plot_type: &str) -> String {
-        let visual = self.get_visual(py, plot_type);
-        format!("```json:causal-plot\n{}\n```", visual.to_json())
+    /// `plot_type="pdp"` plots the partial dependence of the effect on `feature`;
+    /// `n_bins` and `range` shape the `effect_dist` histogram.
+    #[pyo3(signature = (plot_type = "graph", feature = None, n_bins = 10, range = None))]
+    fn to_visual_tag(
+        &self,
+        py: Python,
+        plot_type: &str,
+        feature: Option<&str>,
+        n_bins: usize,
+        range: Option<(f64, f64)>,
+    ) -> PyResult<String> {
+        let visual = self.visual(py, plot_type, feature, n_bins, range)?;
+        Ok(format!("```json:causal-plot\n{}\n```", visual.to_json()))
     }
 
-    #[pyo3(signature = (plot_type = "graph"))]
-    fn show(&self, py: Python, plot_type: &str) {
-        println!("{}", self.to_visual_tag(py, plot_type));
+    #[pyo3(signature = (plot_type = "graph", feature = None, n_bins = 10, range = None))]
+    fn show(
+        &self,
+        py: Python,
+        plot_type: &str,
+        feature: Option<&str>,
+        n_bins: usize,
+        range: Option<(f64, f64)>,
+    ) -> PyResult<()> {
+        println!(
+            "{}",
+            self.to_visual_tag(py, plot_type, feature, n_bins, range)?
+        );
+        Ok(())
     }
 
-    #[pyo3(signature = (plot_type = "graph"))]
-    fn preview(&self, py: Python, plot_type: &str) -> PyResult<()> {
-        let visual = self.get_visual(py, plot_type);
-        render_preview(py, &visual)
+    /// `theme` is an ECharts theme: "dark" (default), "light" or a registered custom name.
+    #[pyo3(signature = (plot_type = "graph", theme = "dark", feature = None, n_bins = 10, range = None))]
+    fn preview(
+        &self,
+        py: Python,
+        plot_type: &str,
+        theme: &str,
+        feature: Option<&str>,
+        n_bins: usize,
+        range: Option<(f64, f64)>,
+    ) -> PyResult<()> {
+        let visual = self.visual(py, plot_type, feature, n_bins, range)?;
+        render_preview(py, &visual, theme)
     }
 
-    #[pyo3(signature = (plot_type = "graph"))]
-    fn to_html(&self, py: Python, plot_type: &str) -> String {
-        let visual = self.get_visual(py, plot_type);
-        render_html_fragment(&visual)
+    #[pyo3(signature = (plot_type = "graph", theme = "dark", feature = None, n_bins = 10, range = None))]
+    fn to_html(
+        &self,
+        py: Python,
+        plot_type: &str,
+        theme: &str,
+        feature: Option<&str>,
+        n_bins: usize,
+        range: Option<(f64, f64)>,
+    ) -> PyResult<String> {
+        let visual = self.visual(py, plot_type, feature, n_bins, range)?;
+        Ok(render_html_fragment(&visual, theme))
+    }
+
+    /// Renders several plots into one self-contained HTML page that loads ECharts once.
+    /// `"diagnostics"` adds tables of the data summary and fit report.
+    #[pyo3(signature = (
+        plots = vec!["graph", "importance", "effect_dist", "diagnostics"],
+        theme = "dark"
+    ))]
+    fn to_report(&self, py: Python, plots: Vec<&str>, theme: &str) -> PyResult<String> {
+        let mut sections = String::new();
+        for plot in plots {
+            let (title, body) = if plot == "diagnostics" {
+                ("Diagnostics".to_string(), self.diagnostics_html(py)?)
+            } else {
+                let visual = self.get_visual(py, plot)?;
+                (
+                    visual.title.clone(),
+                    render_chart_fragment(&visual, false, theme),
+                )
+            };
+            sections.push_str(&format!(
+                "<section>\n<h2>{}</h2>\n{}\n</section>\n",
+                title, body
+            ));
+        }
+        Ok(render_report_page(&sections, theme))
+    }
+
+    /// Saves the fitted model (method, parameters and trees, feature names, confidence
+    /// level) as JSON. Training data is not stored, so `validate` is unavailable after
+    /// loading.
+    fn save(&self, path: &str) -> PyResult<()> {
+        let doc = ModelDocument {
+            format_version: MODEL_FORMAT_VERSION,
+            model: self.method.clone(),
+            feature_names: self.feature_names.clone(),
+            confidence_level: self.confidence_level,
+        };
+        let json = serde_json::to_string(&doc).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        std::fs::write(path, json).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))
+    }
+
+    /// Writes `to_report(plots, theme)` to `path`.
+    #[pyo3(signature = (
+        path,
+        plots = vec!["graph", "importance", "effect_dist", "diagnostics"],
+        theme = "dark"
+    ))]
+    fn save_report(&self, py: Python, path: &str, plots: Vec<&str>, theme: &str) -> PyResult<()> {
+        let html = self.to_report(py, plots, theme)?;
+        std::fs::write(path, html).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))
+    }
+
+    /// Estimates effects on `x`. With `clusters` (one integer id per row), also reports
+    /// a cluster-robust bootstrap interval for the ATE. The last `subgroup_effects`
+    /// table, if any, is carried along for `summary()`. For a categorical treatment,
+    /// `predictions` stays one-dimensional (arm 1 versus arm 0) and the effects of every
+    /// arm are in the 2-D `arm_effects`.
+    #[pyo3(signature = (x, clusters = None, n_bootstrap = 200))]
+    fn estimate_effects(
+        &self,
+        py: Python,
+        x: PyReadonlyArray2<f64>,
+        clusters: Option<PyReadonlyArray1<i64>>,
+        n_bootstrap: usize,
+    ) -> PyResult<InferenceResult> {
+        let core_res = self
+            .method
+            .as_trait()
+            .predict_with_level(x.as_array(), self.confidence_level)?;
+        let arm_effects = match &self.method {
+            CausalMethod::Forest(forest)
+                if matches!(forest.treatment_kind, TreatmentKind::Categorical(_)) =>
+            {
+                Some(
+                    forest
+                        .predict_arm_effects(x.as_array())?
+                        .to_pyarray(py)
+                        .to_owned(),
+                )
+            }
+            _ => None,
+        };
+        let ate_confidence_interval = match clusters {
+            Some(ids) => Some(cluster_bootstrap_mean_ci(
+                core_res.predictions.view(),
+                &ids.as_array().to_vec(),
+                n_bootstrap,
+                self.confidence_level,
+            )?),
+            None => None,
+        };
+
+        Ok(InferenceResult {
+            mean_effect: core_res.mean_effect,
+            predictions: core_res.predictions.to_pyarray(py).to_owned(),
+            confidence_intervals: core_res.confidence_intervals,
+            feature_importance: core_res.feature_importance,
+            confidence_level: self.confidence_level,
+            ate_confidence_interval,
+            effect_scale: effect_scale_name(core_res.effect_scale),
+            n_clipped: core_res.n_clipped,
+            feature_names: self.feature_names.clone(),
+            index: None,
+            subgroups: self.subgroups.clone(),
+            arm_effects,
+        })
     }
 
-    fn estimate_effects(&self, py: Python, x: PyReadonlyArray2<f64>) -> PyResult<InferenceResult> {
-        let core_res = self.method.as_trait().predict(x.as_array())?;
+    /// Out-of-bag effects for the training rows: each row averages only the trees that
+    /// did not sample it. Needs a forest fitted with `subsample_ratio` below 1.0.
+    fn estimate_oob_effects(&self, py: Python) -> PyResult<InferenceResult> {
+        let forest = match self.method {
+            CausalMethod::Forest(ref forest) => forest,
+            _ => {
+                return Err(PyValueError::new_err(
+                    "estimate_oob_effects is only available for method='forest'",
+                ))
+            }
+        };
+        let x = unsafe { self.x.as_ref(py).as_array() };
+        let core_res = forest.predict_oob_with_level(x, self.confidence_level)?;
 
         Ok(InferenceResult {
             mean_effect: core_res.mean_effect,
             predictions: core_res.predictions.to_pyarray(py).to_owned(),
             confidence_intervals: core_res.confidence_intervals,
             feature_importance: core_res.feature_importance,
+            confidence_level: self.confidence_level,
+            ate_confidence_interval: None,
+            effect_scale: effect_scale_name(core_res.effect_scale),
+            n_clipped: core_res.n_clipped,
             feature_names: self.feature_names.clone(),
+            index: None,
+            subgroups: self.subgroups.clone(),
+            arm_effects: None,
         })
     }
 
-    #[pyo3(signature = (n_folds = 5, is_time_series = false))]
-    fn validate(&self, py: Python, n_folds: usize, is_time_series: bool) -> PyResult<ValidationResult> {
-        let _ = is_time_series; // Suppress unused warning while keeping the name
+    /// Intervals at several confidence levels from one pass over the tree predictions,
+    /// as a dict keyed by level with `lower`/`upper` arrays, plus `predictions`.
+    fn predict_multi_ci(
+        &self,
+        py: Python,
+        x: PyReadonlyArray2<f64>,
+        levels: Vec<f64>,
+    ) -> PyResult<PyObject> {
+        let forest = match self.method {
+            CausalMethod::Forest(ref forest) => forest,
+            _ => {
+                return Err(PyValueError::new_err(
+                    "predict_multi_ci is only available for method='forest'",
+                ))
+            }
+        };
+        let (predictions, intervals) = forest.predict_multi_ci(x.as_array(), &levels)?;
+
+        let dict = PyDict::new(py);
+        dict.set_item("predictions", predictions.to_pyarray(py))?;
+        for (level, bounds) in levels.iter().zip(intervals) {
+            let (lower, upper): (Vec<f64>, Vec<f64>) = bounds.into_iter().unzip();
+            let entry = PyDict::new(py);
+            entry.set_item("lower", lower.to_pyarray(py))?;
+            entry.set_item("upper", upper.to_pyarray(py))?;
+            dict.set_item(level, entry)?;
+        }
+        Ok(dict.to_object(py))
+    }
+
+    /// Sets the confidence level used by all subsequent `estimate_effects` calls.
+    fn set_confidence_level(&mut self, level: f64) -> PyResult<()> {
+        validate_confidence_level(level)?;
+        self.confidence_level = level;
+        Ok(())
+    }
+
+    #[getter]
+    fn confidence_level(&self) -> f64 {
+        self.confidence_level
+    }
+
+    /// Placebo permutation test over `n_permutations` treatment shuffles (0 falls back to
+    /// a single shuffle, robust when its effect is below `placebo_ratio_threshold` times
+    /// the estimated effect, or below `absolute_threshold` when that is zero; the
+    /// permutation test ignores both, so changing them requires `n_permutations=0`), `n_folds`-fold stability of held-out
+    /// effects (fewer than 2 skips it; `is_time_series` uses expanding-window,
+    /// rolling-origin folds over the rows in their given order instead), plus a negative-control check when
+    /// `negative_control` (one outcome value per training row that the treatment cannot
+    /// affect) is given.
+    #[pyo3(signature = (
+        n_folds = 5,
+        is_time_series = false,
+        negative_control = None,
+        n_permutations = 19,
+        placebo_ratio_threshold = DEFAULT_PLACEBO_RATIO_THRESHOLD,
+        absolute_threshold = DEFAULT_PLACEBO_ABSOLUTE_THRESHOLD
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn validate(
+        &self,
+        py: Python,
+        n_folds: usize,
+        is_time_series: bool,
+        negative_control: Option<PyReadonlyArray1<f64>>,
+        n_permutations: usize,
+        placebo_ratio_threshold: f64,
+        absolute_threshold: f64,
+    ) -> PyResult<ValidationResult> {
+        for (name, value) in [
+            ("placebo_ratio_threshold", placebo_ratio_threshold),
+            ("absolute_threshold", absolute_threshold),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                return Err(PyValueError::new_err(format!(
+                    "{} must be finite and non-negative, got {}",
+                    name, value
+                )));
+            }
+        }
+        if n_permutations > 0
+            && (placebo_ratio_threshold != DEFAULT_PLACEBO_RATIO_THRESHOLD
+                || absolute_threshold != DEFAULT_PLACEBO_ABSOLUTE_THRESHOLD)
+        {
+            return Err(PyValueError::new_err(
+                "placebo_ratio_threshold and absolute_threshold only apply to the single-shuffle \
+                 placebo check; pass n_permutations=0 to use them",
+            ));
+        }
         let (x_view, t_view, y_view) = unsafe {
             (
                 self.x.as_ref(py).as_array(),
@@ -352,100 +1107,1405 @@ impl Model {
                 self.y.as_ref(py).as_array(),
             )
         };
-        
-        if let CausalMethod::Forest(ref forest) = self.method {
-            let res = validate_causal_structure(forest, x_view, t_view, y_view, n_folds);
-            Ok(ValidationResult {
-                is_robust: res.is_robust,
-                message: res.message,
-            })
-        } else {
-            Ok(ValidationResult {
-                is_robust: true,
-                message: "Validation not implemented for this model type yet.".to_string(),
-            })
+
+        let forest = match (&self.method, &negative_control) {
+            (CausalMethod::Forest(forest), _) => Some(forest),
+            (_, Some(_)) => {
+                return Err(PyValueError::new_err(
+                    "negative_control is only available for method='forest'",
+                ))
+            }
+            (_, None) => None,
+        };
+        let res = validate_causal_structure(
+            self.method.as_trait(),
+            x_view,
+            t_view,
+            y_view,
+            self.sample_weight_view(py),
+            n_folds,
+            n_permutations,
+            is_time_series,
+            placebo_ratio_threshold,
+            absolute_threshold,
+        );
+        let (Some(forest), Some(negative_control)) = (forest, negative_control) else {
+            return Ok(res.into());
+        };
+        let control = validate_negative_control(
+            forest,
+            x_view,
+            t_view,
+            negative_control.as_array(),
+            self.sample_weight_view(py),
+        )?;
+        Ok(ValidationResult {
+            is_robust: res.is_robust && control.is_robust,
+            message: format!("{} {}", res.message, control.message),
+            ..res.into()
+        })
+    }
+
+    /// Data refutation of the fitted estimate. `"random_common_cause"` appends a random
+    /// covariate (reproducible with `seed`), refits and reports how far the ATE moves;
+    /// an irrelevant cause should leave a valid estimate nearly unchanged.
+    #[pyo3(signature = (method = "random_common_cause", seed = None))]
+    fn refute(&self, py: Python, method: &str, seed: Option<u64>) -> PyResult<ValidationResult> {
+        let (x_view, t_view, y_view) = unsafe {
+            (
+                self.x.as_ref(py).as_array(),
+                self.t.as_ref(py).as_array(),
+                self.y.as_ref(py).as_array(),
+            )
+        };
+        match method {
+            "random_common_cause" => Ok(refute_random_common_cause(
+                self.method.as_trait(),
+                x_view,
+                t_view,
+                y_view,
+                self.sample_weight_view(py),
+                seed,
+            )?
+            .into()),
+            other => Err(PyValueError::new_err(format!(
+                "Unknown refuter: {}. Supported values are 'random_common_cause'",
+                other
+            ))),
         }
     }
 
-    fn plot_importance(&self, py: Python) {
-        println!("{}", self.to_visual_tag(py, "importance"));
+    fn tree_predictions(
+        &self,
+        py: Python,
+        x: PyReadonlyArray2<f64>,
+    ) -> PyResult<Py<PyArray2<f64>>> {
+        match self.method {
+            CausalMethod::Forest(ref forest) => {
+                forest.check_feature_count(x.as_array())?;
+                Ok(forest
+                    .tree_predictions(x.as_array())
+                    .to_pyarray(py)
+                    .to_owned())
+            }
+            _ => Err(PyValueError::new_err(
+                "tree_predictions is only available for method='forest'",
+            )),
+        }
     }
 
-    fn plot_effects(&self, py: Python) {
-        println!("{}", self.to_visual_tag(py, "effect_dist"));
+    /// Arm `arm_b` minus arm `arm_a` for each row of a categorical-treatment forest.
+    fn predict_contrast(
+        &self,
+        py: Python,
+        x: PyReadonlyArray2<f64>,
+        arm_a: usize,
+        arm_b: usize,
+    ) -> PyResult<Py<PyArray1<f64>>> {
+        match self.method {
+            CausalMethod::Forest(ref forest) => Ok(forest
+                .predict_contrast(x.as_array(), arm_a, arm_b)?
+                .to_pyarray(py)
+                .to_owned()),
+            _ => Err(PyValueError::new_err(
+                "predict_contrast is only available for method='forest'",
+            )),
+        }
     }
-}
 
-#[pyfunction]
-#[pyo3(signature = (features, treatment, outcome, method = "forest", feature_names = None))]
-fn create_model(
-    py: Python,
-    features: Py<PyArray2<f64>>,
-    treatment: Py<PyArray1<f64>>,
-    outcome: Py<PyArray1<f64>>,
-    method: &str,
-    feature_names: Option<Vec<String>>,
-) -> PyResult<Model> {
-    let mut causal_method = match method {
-        "forest" => CausalMethod::Forest(CausalForest::new(10, 5, 5)),
-        "linear" => CausalMethod::Linear(LinearCausalModel::new()),
-        _ => {
-            return Err(PyValueError::new_err(format!(
-                "Unknown method: {}. Supported methods are 'forest', 'linear'",
-                method
-            )))
+    /// Predicted effects minus the predicted effect of `reference_row`.
+    fn effect_relative_to(
+        &self,
+        py: Python,
+        x: PyReadonlyArray2<f64>,
+        reference_row: Vec<f64>,
+    ) -> PyResult<Py<PyArray1<f64>>> {
+        match self.method {
+            CausalMethod::Forest(ref forest) => Ok(forest
+                .effect_relative_to(x.as_array(), &reference_row)?
+                .to_pyarray(py)
+                .to_owned()),
+            _ => Err(PyValueError::new_err(
+                "effect_relative_to is only available for method='forest'",
+            )),
         }
-    };
+    }
 
-    unsafe {
-        causal_method.as_trait_mut().fit(
-            features.as_ref(py).as_array(),
-            treatment.as_ref(py).as_array(),
-            outcome.as_ref(py).as_array(),
-        )?;
+    /// `(leaf_effect, leaf_size)` per tree for a single row.
+    fn explain_one(&self, row: Vec<f64>) -> PyResult<Vec<(f64, usize)>> {
+        match self.method {
+            CausalMethod::Forest(ref forest) => Ok(forest.explain_one(&row)?),
+            _ => Err(PyValueError::new_err(
+                "explain_one is only available for method='forest'",
+            )),
+        }
     }
 
-    Ok(Model {
-        method: causal_method,
-        x: features,
-        t: treatment,
-        y: outcome,
-        feature_names,
-    })
-}
+    /// Learned splits and leaf effects of every tree as a nested dict, tagged with
+    /// `format_version`.
+    fn export_structure(&self, py: Python) -> PyResult<PyObject> {
+        match self.method {
+            CausalMethod::Forest(ref forest) => {
+                let json_module = py.import("json")?;
+                let dict = json_module.call_method1("loads", (forest.to_structure_json(),))?;
+                Ok(dict.to_object(py))
+            }
+            _ => Err(PyValueError::new_err(
+                "export_structure is only available for method='forest'",
+            )),
+        }
+    }
 
-#[pyfunction]
-#[pyo3(signature = (model, plot = "graph"))]
-fn plot_model(py: Python, model: Model, plot: &str) -> PyResult<PyObject> {
-    let visual = model.get_visual(py, plot);
-    let json_str = visual.to_json();
-    let json_module = py.import("json")?;
-    let dict = json_module.call_method1("loads", (json_str,))?;
-    Ok(dict.to_object(py))
-}
+    /// Mean predicted effect and row count of the training data within subgroups of
+    /// `feature` (a name or index) cut at `thresholds`, e.g. `age <= 50` and `age > 50`.
+    /// The table is kept and shown by the `summary()` of results estimated afterwards.
+    fn subgroup_effects(
+        &mut self,
+        py: Python,
+        feature: FeatureArg,
+        thresholds: Vec<f64>,
+    ) -> PyResult<PyObject> {
+        let x = unsafe { self.x.as_ref(py).as_array() };
+        let feature_idx = self.resolve_feature(&feature, x.ncols())?;
+        let effects = self.method.as_trait().predict(x)?.predictions;
+        let rows = core_subgroup_effects(x.column(feature_idx), effects.view(), &thresholds)?;
+        let table = to_py_object(py, &rows)?;
+        let label = &self.feature_labels(x.ncols())[feature_idx];
+        self.subgroups = Some((format!("{} (training data)", label), rows));
+        Ok(table)
+    }
 
-fn render_preview(py: Python, visual: &VisualOutput) -> PyResult<()> {
-    let json_data = visual.to_json();
-    let html_template = format!(
-        r#"
-<!DOCTYPE html>
-<html>
-<head>
-    <meta charset="UTF-8">
-    <title>CausalFlow Preview: {}</title>
-    <script src="https://cdn.jsdelivr.net/npm/echarts@5.4.3/dist/echarts.min.js"></script>
-    <style>
-        body {{ font-family: sans-serif; background: #1a1a2e; color: #fff; margin: 0; padding: 20px; }}
-        #chart {{ width: 100%; height: 600px; }}
-        h1 {{ color: #4fc3f7; text-align: center; }}
-    </style>
-</head>
-<body>
-    <h1>CausalFlow: {}</h1>
+    /// The `k` most important features as `(name, importance)`, highest first.
+    #[pyo3(signature = (k = 10))]
+    fn top_features(&self, k: usize) -> PyResult<Vec<(String, f64)>> {
+        match self.method {
+            CausalMethod::Forest(ref forest) => {
+                let labels = self.feature_labels(forest.n_features);
+                Ok(forest
+                    .top_features(k)
+                    .into_iter()
+                    .map(|(idx, importance)| (labels[idx].clone(), importance))
+                    .collect())
+            }
+            _ => Err(PyValueError::new_err(
+                "top_features is only available for method='forest'",
+            )),
+        }
+    }
+
+    /// Per-feature importance on the training data as a dict of `feature`, `importance`
+    /// and `std` lists. `method="gain"` is the normalized split gain (no `std`);
+    /// `method="permutation"` is the error increase when a feature is shuffled, over
+    /// `n_repeats` shuffles.
+    #[pyo3(signature = (method = "gain", n_repeats = 10, seed = None))]
+    fn feature_importance(
+        &self,
+        py: Python,
+        method: &str,
+        n_repeats: usize,
+        seed: Option<u64>,
+    ) -> PyResult<PyObject> {
+        let forest = match self.method {
+            CausalMethod::Forest(ref forest) => forest,
+            _ => {
+                return Err(PyValueError::new_err(
+                    "feature_importance is only available for method='forest'",
+                ))
+            }
+        };
+        let (importance, std) = match method {
+            "gain" => (forest.feature_importance(), None),
+            "permutation" => {
+                let (x, t, y) = unsafe {
+                    (
+                        self.x.as_ref(py).as_array(),
+                        self.t.as_ref(py).as_array(),
+                        self.y.as_ref(py).as_array(),
+                    )
+                };
+                let (mean, std) = forest.permutation_importance(x, t, y, n_repeats, seed)?;
+                (mean, Some(std))
+            }
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown importance method: {}. Supported values are 'gain', 'permutation'",
+                    method
+                )))
+            }
+        };
+        let dict = PyDict::new(py);
+        dict.set_item("feature", self.feature_labels(importance.len()))?;
+        dict.set_item("importance", importance)?;
+        dict.set_item("std", std)?;
+        Ok(dict.to_object(py))
+    }
+
+    /// Latency percentiles (ms) and rows per second for scoring `x` `n_iters` times.
+    #[pyo3(signature = (x, n_iters = 100))]
+    fn benchmark_predict(
+        &self,
+        py: Python,
+        x: PyReadonlyArray2<f64>,
+        n_iters: usize,
+    ) -> PyResult<PyObject> {
+        match self.method {
+            CausalMethod::Forest(ref forest) => {
+                to_py_object(py, &forest.benchmark_predict(x.as_array(), n_iters)?)
+            }
+            _ => Err(PyValueError::new_err(
+                "benchmark_predict is only available for method='forest'",
+            )),
+        }
+    }
+
+    /// Mean pairwise correlation of per-tree predictions on `x` (ensemble diversity).
+    fn tree_correlation(&self, x: PyReadonlyArray2<f64>) -> PyResult<f64> {
+        match self.method {
+            CausalMethod::Forest(ref forest) => Ok(forest.tree_correlation(x.as_array())?),
+            _ => Err(PyValueError::new_err(
+                "tree_correlation is only available for method='forest'",
+            )),
+        }
+    }
+
+    fn conditional_ate(
+        &self,
+        py: Python,
+        x: PyReadonlyArray2<f64>,
+        mask: PyReadonlyArray1<bool>,
+    ) -> PyResult<PyObject> {
+        let forest = match self.method {
+            CausalMethod::Forest(ref forest) => forest,
+            _ => {
+                return Err(PyValueError::new_err(
+                    "conditional_ate is only available for method='forest'",
+                ))
+            }
+        };
+        let mask = mask.as_array().to_owned();
+        let (ate, (lower, upper)) = forest.conditional_ate(x.as_array(), &mask)?;
+
+        let dict = PyDict::new(py);
+        dict.set_item("ate", ate)?;
+        dict.set_item("ci_lower", lower)?;
+        dict.set_item("ci_upper", upper)?;
+        dict.set_item("n", mask.iter().filter(|&&m| m).count())?;
+        Ok(dict.to_object(py))
+    }
+
+    /// Doubly-robust (AIPW) ATE of the training rows with its standard error and its
+    /// interval at the model's confidence level.
+    fn doubly_robust_ate(&self, py: Python) -> PyResult<PyObject> {
+        let CausalMethod::Aipw(ref model) = self.method else {
+            return Err(PyValueError::new_err(
+                "doubly_robust_ate is only available for method='aipw'",
+            ));
+        };
+        let (lower, upper) = model.ate_interval(self.confidence_level)?;
+
+        let dict = PyDict::new(py);
+        dict.set_item("ate", model.ate)?;
+        dict.set_item("std_error", model.std_error)?;
+        dict.set_item("ci_lower", lower)?;
+        dict.set_item("ci_upper", upper)?;
+        Ok(dict.to_object(py))
+    }
+
+    /// ATE with CI for the population described by per-row importance `weights`.
+    fn weighted_ate(
+        &self,
+        py: Python,
+        x: PyReadonlyArray2<f64>,
+        weights: PyReadonlyArray1<f64>,
+    ) -> PyResult<PyObject> {
+        let forest = match self.method {
+            CausalMethod::Forest(ref forest) => forest,
+            _ => {
+                return Err(PyValueError::new_err(
+                    "weighted_ate is only available for method='forest'",
+                ))
+            }
+        };
+        let weights = weights.as_array().to_owned();
+        let (ate, (lower, upper)) = forest.weighted_ate(x.as_array(), &weights)?;
+
+        let dict = PyDict::new(py);
+        dict.set_item("ate", ate)?;
+        dict.set_item("ci_lower", lower)?;
+        dict.set_item("ci_upper", upper)?;
+        dict.set_item("effective_n", weights.sum().powi(2) / weights.dot(&weights))?;
+        Ok(dict.to_object(py))
+    }
+
+    #[pyo3(signature = (x, t, y, budget = None))]
+    fn calibrate_threshold(
+        &self,
+        py: Python,
+        x: PyReadonlyArray2<f64>,
+        t: PyReadonlyArray1<f64>,
+        y: PyReadonlyArray1<f64>,
+        budget: Option<usize>,
+    ) -> PyResult<PyObject> {
+        let metric = match budget {
+            Some(b) => ThresholdMetric::Budget(b),
+            None => ThresholdMetric::Uplift,
+        };
+        let predicted = self.method.as_trait().predict(x.as_array())?.predictions;
+        let res = calibrate_threshold(predicted.view(), t.as_array(), y.as_array(), metric)?;
+
+        let dict = PyDict::new(py);
+        dict.set_item("cutoff", res.cutoff)?;
+        dict.set_item("uplift", res.uplift)?;
+        dict.set_item("n_treated", res.n_treated)?;
+        Ok(dict.to_object(py))
+    }
+
+    /// Segment-mean effects of `x` made non-decreasing along `order`, with the size of
+    /// the adjustment. `segments` gives each row's segment label.
+    fn monotone_segment_effects(
+        &self,
+        py: Python,
+        x: PyReadonlyArray2<f64>,
+        segments: Vec<i64>,
+        order: Vec<i64>,
+    ) -> PyResult<PyObject> {
+        let predicted = self.method.as_trait().predict(x.as_array())?.predictions;
+        let res = monotone_segment_effects(predicted.view(), &segments, &order)?;
+        to_py_object(py, &res)
+    }
+
+    /// Standalone SVG of `plot_type` (`"pdp"` needs `feature`), `width` x `height`
+    /// pixels (at least 200 x 150), rendered without a browser or any scripts.
+    #[pyo3(signature = (plot_type = "graph", feature = None, n_bins = 10, range = None, width = 800, height = 500))]
+    #[allow(clippy::too_many_arguments)]
+    fn to_svg(
+        &self,
+        py: Python,
+        plot_type: &str,
+        feature: Option<&str>,
+        n_bins: usize,
+        range: Option<(f64, f64)>,
+        width: u32,
+        height: u32,
+    ) -> PyResult<String> {
+        let visual = self.visual(py, plot_type, feature, n_bins, range)?;
+        Ok(visual.to_svg(width, height)?)
+    }
+
+    /// GraphViz DOT for `plot_type`, or `None` when that visual is not a graph.
+    #[pyo3(signature = (plot_type = "graph"))]
+    fn to_dot(&self, py: Python, plot_type: &str) -> PyResult<Option<String>> {
+        Ok(self.get_visual(py, plot_type)?.to_graph_dot())
+    }
+
+    /// Deprecated alias of `to_dot("graph")`.
+    fn to_graph_dot(&self, py: Python) -> PyResult<String> {
+        PyErr::warn(
+            py,
+            py.get_type::<pyo3::exceptions::PyDeprecationWarning>(),
+            "to_graph_dot() is deprecated; use to_dot() instead",
+            1,
+        )?;
+        Ok(self.to_dot(py, "graph")?.unwrap_or_default())
+    }
+
+    fn to_graph_mermaid(&self, py: Python) -> PyResult<String> {
+        Ok(self
+            .get_visual(py, "graph")?
+            .to_graph_mermaid()
+            .unwrap_or_default())
+    }
+
+    fn compare_naive_ate(&self, py: Python) -> PyResult<PyObject> {
+        let (x_view, t_view, y_view) = unsafe {
+            (
+                self.x.as_ref(py).as_array(),
+                self.t.as_ref(py).as_array(),
+                self.y.as_ref(py).as_array(),
+            )
+        };
+        let comparison = match self.method {
+            CausalMethod::Forest(ref forest) => forest.compare_naive_ate(x_view, t_view, y_view)?,
+            CausalMethod::Linear(ref linear) if linear.treatment_kind != TreatmentKind::Binary => {
+                return Err(PyValueError::new_err(
+                    "the naive ATE requires a binary treatment",
+                ))
+            }
+            _ => {
+                let adjusted = self.method.as_trait().predict(x_view)?.mean_effect;
+                AteComparison::new(difference_in_means(t_view, y_view), adjusted)
+            }
+        };
+
+        let dict = PyDict::new(py);
+        dict.set_item("naive_ate", comparison.naive_ate)?;
+        dict.set_item("adjusted_ate", comparison.adjusted_ate)?;
+        dict.set_item("adjustment", comparison.adjustment)?;
+        Ok(dict.to_object(py))
+    }
+
+    /// Fits an isotonic calibration of predicted effects on held-out data.
+    fn calibrate(
+        &mut self,
+        x: PyReadonlyArray2<f64>,
+        t: PyReadonlyArray1<f64>,
+        y: PyReadonlyArray1<f64>,
+    ) -> PyResult<()> {
+        match self.method {
+            CausalMethod::Forest(ref mut forest) => {
+                forest.calibrate(x.as_array(), t.as_array(), y.as_array())?;
+                Ok(())
+            }
+            _ => Err(PyValueError::new_err(
+                "calibrate is only available for method='forest'",
+            )),
+        }
+    }
+
+    fn add_trees(&mut self, py: Python, n_trees: usize) -> PyResult<()> {
+        let (x_view, t_view, y_view) = unsafe {
+            (
+                self.x.as_ref(py).as_array(),
+                self.t.as_ref(py).as_array(),
+                self.y.as_ref(py).as_array(),
+            )
+        };
+        let sample_weight = self.sample_weight_view(py);
+        match self.method {
+            CausalMethod::Forest(ref mut forest) => {
+                forest.add_trees(x_view, t_view, y_view, sample_weight, n_trees)?;
+                Ok(())
+            }
+            _ => Err(PyValueError::new_err(
+                "add_trees is only available for method='forest'",
+            )),
+        }
+    }
+
+    /// Fraction of the variance in predicted effects attributable to each feature.
+    #[pyo3(signature = (x, n_grid = 20))]
+    fn effect_variance_shares(
+        &self,
+        py: Python,
+        x: PyReadonlyArray2<f64>,
+        n_grid: usize,
+    ) -> PyResult<PyObject> {
+        match &self.method {
+            CausalMethod::Forest(forest) => {
+                let shares = forest.effect_variance_shares(x.as_array(), n_grid)?;
+                let dict = PyDict::new(py);
+                for (name, share) in self.feature_labels(shares.len()).iter().zip(shares) {
+                    dict.set_item(name, share)?;
+                }
+                Ok(dict.to_object(py))
+            }
+            _ => Err(PyValueError::new_err(
+                "effect_variance_shares is only available for method='forest'",
+            )),
+        }
+    }
+
+    /// `(feature value, mean effect)` for each percentile band of feature `feature_idx`.
+    fn effect_at_percentiles(
+        &self,
+        x: PyReadonlyArray2<f64>,
+        feature_idx: usize,
+        percentiles: Vec<f64>,
+    ) -> PyResult<Vec<(f64, f64)>> {
+        match &self.method {
+            CausalMethod::Forest(forest) => {
+                Ok(forest.effect_at_percentiles(x.as_array(), feature_idx, &percentiles)?)
+            }
+            _ => Err(PyValueError::new_err(
+                "effect_at_percentiles is only available for method='forest'",
+            )),
+        }
+    }
+
+    /// Mean effect per quantile bin of every feature as long-format rows
+    /// (`feature`, `lower`, `upper`, `mean_effect`, `count`).
+    #[pyo3(signature = (x, n_bins = 4))]
+    fn effect_profiles(
+        &self,
+        py: Python,
+        x: PyReadonlyArray2<f64>,
+        n_bins: usize,
+    ) -> PyResult<PyObject> {
+        let forest = match &self.method {
+            CausalMethod::Forest(forest) => forest,
+            _ => {
+                return Err(PyValueError::new_err(
+                    "effect_profiles is only available for method='forest'",
+                ))
+            }
+        };
+        let profiles = forest.effect_profiles(x.as_array(), n_bins)?;
+        let rows = PyList::empty(py);
+        for (name, bins) in self.feature_labels(profiles.len()).iter().zip(profiles) {
+            for bin in bins {
+                let row = PyDict::new(py);
+                row.set_item("feature", name)?;
+                row.set_item("lower", bin.lower)?;
+                row.set_item("upper", bin.upper)?;
+                row.set_item("mean_effect", bin.mean_effect)?;
+                row.set_item("count", bin.count)?;
+                rows.append(row)?;
+            }
+        }
+        Ok(rows.to_object(py))
+    }
+
+    /// R²-like gain of the forest's CATEs over a constant effect on `(x, t, y)`.
+    fn heterogeneity_gain(
+        &self,
+        py: Python,
+        x: PyReadonlyArray2<f64>,
+        t: PyReadonlyArray1<f64>,
+        y: PyReadonlyArray1<f64>,
+    ) -> PyResult<PyObject> {
+        match &self.method {
+            CausalMethod::Forest(forest) => {
+                let gain = forest.heterogeneity_gain(x.as_array(), t.as_array(), y.as_array())?;
+                to_py_object(py, &gain)
+            }
+            _ => Err(PyValueError::new_err(
+                "heterogeneity_gain is only available for method='forest'",
+            )),
+        }
+    }
+
+    /// Mean and standard deviation of each feature's importance across forests refit
+    /// on bootstrap resamples of the training data.
+    #[pyo3(signature = (n_boot = 20, seed = None))]
+    fn importance_bootstrap(
+        &self,
+        py: Python,
+        n_boot: usize,
+        seed: Option<u64>,
+    ) -> PyResult<PyObject> {
+        let (x_view, t_view, y_view) = unsafe {
+            (
+                self.x.as_ref(py).as_array(),
+                self.t.as_ref(py).as_array(),
+                self.y.as_ref(py).as_array(),
+            )
+        };
+        match &self.method {
+            CausalMethod::Forest(forest) => {
+                let (mean, std) = forest.importance_bootstrap(
+                    x_view,
+                    t_view,
+                    y_view,
+                    self.sample_weight_view(py),
+                    n_boot,
+                    seed,
+                )?;
+                let dict = PyDict::new(py);
+                for (i, name) in self.feature_labels(mean.len()).iter().enumerate() {
+                    let entry = PyDict::new(py);
+                    entry.set_item("mean", mean[i])?;
+                    entry.set_item("std", std[i])?;
+                    dict.set_item(name, entry)?;
+                }
+                Ok(dict.to_object(py))
+            }
+            _ => Err(PyValueError::new_err(
+                "importance_bootstrap is only available for method='forest'",
+            )),
+        }
+    }
+
+    /// Highest-effect leaf rules as dicts with a readable condition, mean effect,
+    /// support and the number of trees containing the rule.
+    #[pyo3(signature = (top_k = 20))]
+    fn mine_rules(&self, py: Python, top_k: usize) -> PyResult<PyObject> {
+        match &self.method {
+            CausalMethod::Forest(forest) => {
+                let names = self.feature_labels(forest.n_features);
+                let rows = PyList::empty(py);
+                for rule in forest.mine_rules(top_k)? {
+                    let row = PyDict::new(py);
+                    row.set_item("condition", rule.describe(&names))?;
+                    row.set_item("mean_effect", rule.mean_effect)?;
+                    row.set_item("support", rule.support)?;
+                    row.set_item("frequency", rule.frequency)?;
+                    rows.append(row)?;
+                }
+                Ok(rows.to_object(py))
+            }
+            _ => Err(PyValueError::new_err(
+                "mine_rules is only available for method='forest'",
+            )),
+        }
+    }
+
+    /// Content hash of the fitted forest, for keying caches of downstream results.
+    fn fingerprint(&self) -> PyResult<u64> {
+        match &self.method {
+            CausalMethod::Forest(forest) => Ok(forest.fingerprint()),
+            _ => Err(PyValueError::new_err(
+                "fingerprint is only available for method='forest'",
+            )),
+        }
+    }
+
+    /// CUPED adjustment (theta, covariate mean, variance reduction) applied at fit time,
+    /// or `None` when no pre-treatment covariate was given.
+    fn cuped_report(&self, py: Python) -> PyResult<PyObject> {
+        match &self.method {
+            CausalMethod::Forest(forest) => match &forest.cuped {
+                Some(adjustment) => to_py_object(py, adjustment),
+                None => Ok(py.None()),
+            },
+            _ => Ok(py.None()),
+        }
+    }
+
+    /// Treatment-arm sample sizes and honest-leaf capacity recorded at fit time.
+    fn fit_report(&self, py: Python) -> PyResult<PyObject> {
+        match &self.method {
+            CausalMethod::Forest(forest) => match &forest.fit_report {
+                Some(report) => to_py_object(py, report),
+                None => Err(CausalFlowError::ModelNotFitted.into()),
+            },
+            _ => Err(PyValueError::new_err(
+                "fit_report is only available for method='forest'",
+            )),
+        }
+    }
+
+    fn data_summary(&self, py: Python) -> PyResult<PyObject> {
+        let (t_view, y_view) =
+            unsafe { (self.t.as_ref(py).as_array(), self.y.as_ref(py).as_array()) };
+        to_py_object(py, &core_data_summary(t_view, y_view)?)
+    }
+
+    fn plot_importance(&self, py: Python) -> PyResult<()> {
+        self.show(py, "importance", None, 10, None)
+    }
+
+    fn plot_effects(&self, py: Python) -> PyResult<()> {
+        self.show(py, "effect_dist", None, 10, None)
+    }
+}
+
+/// A feature as passed from Python: a column name or a column index.
+#[derive(FromPyObject)]
+enum FeatureArg<'a> {
+    Name(&'a str),
+    Index(usize),
+}
+
+/// `max_features` as passed from Python: a rule name, a feature count or a fraction.
+#[derive(FromPyObject)]
+enum MaxFeaturesArg<'a> {
+    Name(&'a str),
+    Count(usize),
+    Fraction(f64),
+}
+
+impl MaxFeaturesArg<'_> {
+    fn resolve(&self) -> PyResult<MaxFeatures> {
+        match *self {
+            MaxFeaturesArg::Name("sqrt") => Ok(MaxFeatures::Sqrt),
+            MaxFeaturesArg::Name("log2") => Ok(MaxFeatures::Log2),
+            MaxFeaturesArg::Name(name) => Err(PyValueError::new_err(format!(
+                "Unknown max_features: {}. Supported values are 'sqrt', 'log2', a float fraction or an int count",
+                name
+            ))),
+            MaxFeaturesArg::Count(count) => Ok(MaxFeatures::Count(count)),
+            MaxFeaturesArg::Fraction(share) => Ok(MaxFeatures::Fraction(share)),
+        }
+    }
+}
+
+/// Forest hyperparameters from the keyword arguments, with `overrides` (a dict keyed
+/// by `CausalForestConfig` field names) applied on top.
+fn resolve_forest_config(
+    base: CausalForestConfig,
+    overrides: Option<&PyDict>,
+) -> PyResult<CausalForestConfig> {
+    let mut config = base;
+    for (key, value) in overrides.into_iter().flatten() {
+        let key: &str = key.extract()?;
+        match key {
+            "n_estimators" => config.n_estimators = value.extract()?,
+            "max_depth" => config.max_depth = value.extract()?,
+            "min_leaf_size" => config.min_leaf_size = value.extract()?,
+            "max_features" => config.max_features = value.extract::<MaxFeaturesArg>()?.resolve()?,
+            "seed" => config.seed = value.extract()?,
+            "subsample_ratio" => config.subsample_ratio = value.extract()?,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown forest_config key: {}. Supported keys are 'n_estimators', \
+                     'max_depth', 'min_leaf_size', 'max_features', 'seed', 'subsample_ratio'",
+                    key
+                )))
+            }
+        }
+    }
+    Ok(config)
+}
+
+/// Forest keyword arguments shared by `create_model` and `create_factorial_model`.
+struct ForestArgs<'a> {
+    feature_names: Option<Vec<String>>,
+    ci_spread: &'a str,
+    ci_distribution: &'a str,
+    split_strategy: &'a str,
+    feature_sample_weights: Option<Vec<f64>>,
+    constant_outcome: &'a str,
+    max_depth: usize,
+    n_jobs: Option<usize>,
+    gain_balance_penalty: f64,
+    outcome_transform: &'a str,
+    degenerate_leaf: &'a str,
+    post_treatment_threshold: Option<f64>,
+    min_leaf_treated: usize,
+    min_leaf_control: usize,
+    seed: Option<u64>,
+    subsample_ratio: f64,
+    effect_floor: Option<f64>,
+    effect_ceil: Option<f64>,
+    treatment_kind: TreatmentKind,
+    use_propensity: bool,
+    n_split_candidates: usize,
+    max_features: MaxFeaturesArg<'a>,
+    min_gain: f64,
+    forest_config: Option<&'a PyDict>,
+    relative_gain_tol: f64,
+}
+
+impl ForestArgs<'_> {
+    fn build(self) -> PyResult<CausalForest> {
+        let base = CausalForestConfig {
+            max_depth: self.max_depth,
+            max_features: self.max_features.resolve()?,
+            seed: self.seed,
+            subsample_ratio: self.subsample_ratio,
+            ..CausalForestConfig::default()
+        };
+        let mut forest = resolve_forest_config(base, self.forest_config)?.build();
+        forest.interval_spread = match self.ci_spread {
+            "tree_std" => IntervalSpread::TreeStd,
+            "standard_error" => IntervalSpread::StandardError,
+            "tree_percentile" => IntervalSpread::TreePercentile,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown ci_spread: {}. Supported values are 'tree_std', 'standard_error', 'tree_percentile'",
+                    self.ci_spread
+                )))
+            }
+        };
+        forest.interval_distribution = match self.ci_distribution {
+            "normal" => IntervalDistribution::Normal,
+            "t" => IntervalDistribution::StudentT,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown ci_distribution: {}. Supported values are 'normal', 't'",
+                    self.ci_distribution
+                )))
+            }
+        };
+        forest.split_strategy = match self.split_strategy {
+            "best_random" => SplitStrategy::BestRandom,
+            "extra_random" => SplitStrategy::ExtraRandom,
+            "quantile" => SplitStrategy::Quantile(self.n_split_candidates),
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown split_strategy: {}. Supported values are 'best_random', 'extra_random', 'quantile'",
+                    self.split_strategy
+                )))
+            }
+        };
+        forest.n_split_candidates = self.n_split_candidates;
+        forest.feature_sample_weights = self.feature_sample_weights;
+        forest.feature_names = self.feature_names;
+        forest.post_treatment_threshold = self.post_treatment_threshold;
+        forest.min_leaf_treated = self.min_leaf_treated;
+        forest.min_leaf_control = self.min_leaf_control;
+        forest.effect_floor = self.effect_floor;
+        forest.effect_ceil = self.effect_ceil;
+        forest.treatment_kind = self.treatment_kind;
+        forest.use_propensity = self.use_propensity;
+        forest.n_jobs = self.n_jobs;
+        forest.gain_balance_penalty = self.gain_balance_penalty;
+        forest.min_gain = self.min_gain;
+        forest.relative_gain_tol = self.relative_gain_tol;
+        forest.outcome_transform = match self.outcome_transform {
+            "none" => OutcomeTransform::None,
+            "log" => OutcomeTransform::Log,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown outcome_transform: {}. Supported values are 'none', 'log'",
+                    self.outcome_transform
+                )))
+            }
+        };
+        forest.constant_outcome = match self.constant_outcome {
+            "zero" => ConstantOutcome::ZeroEffect,
+            "error" => ConstantOutcome::Error,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown constant_outcome: {}. Supported values are 'zero', 'error'",
+                    self.constant_outcome
+                )))
+            }
+        };
+        forest.degenerate_leaf = match self.degenerate_leaf {
+            "zero" => DegenerateLeafPolicy::ZeroEffect,
+            "parent" => DegenerateLeafPolicy::ParentEffect,
+            "nan" => DegenerateLeafPolicy::NaN,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown degenerate_leaf: {}. Supported values are 'zero', 'parent', 'nan'",
+                    self.degenerate_leaf
+                )))
+            }
+        };
+        Ok(forest)
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (
+    features,
+    treatment,
+    outcome,
+    method = "forest",
+    feature_names = None,
+    ci_spread = "tree_std",
+    ci_distribution = "normal",
+    split_strategy = "best_random",
+    feature_sample_weights = None,
+    constant_outcome = "zero",
+    max_depth = 5,
+    n_jobs = None,
+    gain_balance_penalty = 1.0,
+    outcome_transform = "none",
+    pre_covariate = None,
+    degenerate_leaf = "zero",
+    post_treatment_threshold = Some(0.5),
+    min_leaf_treated = 0,
+    min_leaf_control = 0,
+    seed = None,
+    subsample_ratio = 1.0,
+    baseline = None,
+    effect_floor = None,
+    effect_ceil = None,
+    treatment_kind = "auto",
+    use_propensity = false,
+    n_split_candidates = 10,
+    max_features = MaxFeaturesArg::Name("sqrt"),
+    min_gain = 0.0,
+    sample_weight = None,
+    ridge_lambda = None,
+    forest_config = None,
+    relative_gain_tol = 0.0,
+    n_arms = None
+))]
+#[allow(clippy::too_many_arguments)]
+fn create_model(
+    py: Python,
+    features: Py<PyArray2<f64>>,
+    treatment: Py<PyArray1<f64>>,
+    outcome: Py<PyArray1<f64>>,
+    method: &str,
+    feature_names: Option<Vec<String>>,
+    ci_spread: &str,
+    ci_distribution: &str,
+    split_strategy: &str,
+    feature_sample_weights: Option<Vec<f64>>,
+    constant_outcome: &str,
+    max_depth: usize,
+    n_jobs: Option<usize>,
+    gain_balance_penalty: f64,
+    outcome_transform: &str,
+    pre_covariate: Option<PyReadonlyArray1<f64>>,
+    degenerate_leaf: &str,
+    post_treatment_threshold: Option<f64>,
+    min_leaf_treated: usize,
+    min_leaf_control: usize,
+    seed: Option<u64>,
+    subsample_ratio: f64,
+    baseline: Option<PyReadonlyArray1<f64>>,
+    effect_floor: Option<f64>,
+    effect_ceil: Option<f64>,
+    treatment_kind: &str,
+    use_propensity: bool,
+    n_split_candidates: usize,
+    max_features: MaxFeaturesArg,
+    min_gain: f64,
+    sample_weight: Option<PyReadonlyArray1<f64>>,
+    ridge_lambda: Option<f64>,
+    forest_config: Option<&PyDict>,
+    relative_gain_tol: f64,
+    n_arms: Option<usize>,
+) -> PyResult<Model> {
+    let treatment_kind = match treatment_kind {
+        "binary" => TreatmentKind::Binary,
+        "continuous" => TreatmentKind::Continuous,
+        "categorical" => {
+            // Arms 0..=max unless given; the forest rejects non-integer arms
+            let n_arms = n_arms.unwrap_or_else(|| {
+                let t = unsafe { treatment.as_ref(py).as_array() };
+                let max = t.iter().cloned().fold(0.0, f64::max);
+                if max.is_finite() {
+                    max as usize + 1
+                } else {
+                    0
+                }
+            });
+            TreatmentKind::Categorical(n_arms)
+        }
+        "auto" => {
+            let t = unsafe { treatment.as_ref(py).as_array() };
+            let near = |v: f64, target: f64| (v - target).abs() <= 1e-9;
+            if t.iter().all(|&v| near(v, 0.0) || near(v, 1.0)) {
+                TreatmentKind::Binary
+            } else {
+                TreatmentKind::Continuous
+            }
+        }
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "Unknown treatment_kind: {}. Supported values are 'auto', 'binary', 'continuous', 'categorical'",
+                treatment_kind
+            )))
+        }
+    };
+    if n_arms.is_some() && !matches!(treatment_kind, TreatmentKind::Categorical(_)) {
+        return Err(PyValueError::new_err(
+            "n_arms is only available for treatment_kind='categorical'",
+        ));
+    }
+    if matches!(treatment_kind, TreatmentKind::Categorical(_)) && method != "forest" {
+        return Err(PyValueError::new_err(
+            "treatment_kind='categorical' is only available for method='forest'",
+        ));
+    }
+    if ridge_lambda.is_some() && method != "linear" {
+        return Err(PyValueError::new_err(
+            "ridge_lambda is only available for method='linear'",
+        ));
+    }
+    if forest_config.is_some() && method != "forest" {
+        return Err(PyValueError::new_err(
+            "forest_config is only available for method='forest'",
+        ));
+    }
+    let mut causal_method = match method {
+        "forest" => CausalMethod::Forest(
+            ForestArgs {
+                feature_names: feature_names.clone(),
+                ci_spread,
+                ci_distribution,
+                split_strategy,
+                feature_sample_weights,
+                constant_outcome,
+                max_depth,
+                n_jobs,
+                gain_balance_penalty,
+                outcome_transform,
+                degenerate_leaf,
+                post_treatment_threshold,
+                min_leaf_treated,
+                min_leaf_control,
+                seed,
+                subsample_ratio,
+                effect_floor,
+                effect_ceil,
+                treatment_kind,
+                use_propensity,
+                n_split_candidates,
+                max_features,
+                min_gain,
+                forest_config,
+                relative_gain_tol,
+            }
+            .build()?,
+        ),
+        _ if use_propensity => {
+            return Err(PyValueError::new_err(
+                "use_propensity is only available for method='forest'",
+            ))
+        }
+        "linear" => CausalMethod::Linear(LinearCausalModel {
+            treatment_kind,
+            ..LinearCausalModel::ridge(ridge_lambda.unwrap_or(0.0))
+        }),
+        "t-learner" | "s-learner" | "aipw" if treatment_kind == TreatmentKind::Continuous => {
+            return Err(PyValueError::new_err(format!(
+                "method='{}' requires a binary treatment",
+                method
+            )))
+        }
+        "t-learner" => CausalMethod::TLearner(TLearner::new(LinearRegressor::default())),
+        "s-learner" => CausalMethod::SLearner(SLearner::new(LinearRegressor::default())),
+        "aipw" => CausalMethod::Aipw(DoublyRobustModel::new(LinearRegressor::default())),
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "Unknown method: {}. Supported methods are 'forest', 'linear', 't-learner', \
+                 's-learner', 'aipw'",
+                method
+            )))
+        }
+    };
+
+    // The outcome the model is actually fit on, when pre_covariate or baseline adjusts it
+    let mut fit_outcome = None;
+    unsafe {
+        let (x_view, t_view, y_view) = (
+            features.as_ref(py).as_array(),
+            treatment.as_ref(py).as_array(),
+            outcome.as_ref(py).as_array(),
+        );
+        if sample_weight.is_some() && (pre_covariate.is_some() || baseline.is_some()) {
+            return Err(PyValueError::new_err(
+                "sample_weight cannot be combined with pre_covariate or baseline",
+            ));
+        }
+        match (&mut causal_method, pre_covariate, baseline) {
+            (_, Some(_), Some(_)) => {
+                return Err(PyValueError::new_err(
+                    "pre_covariate and baseline cannot be combined",
+                ))
+            }
+            (CausalMethod::Forest(forest), Some(pre), None) => {
+                forest.fit_cuped(x_view, t_view, y_view, pre.as_array())?;
+                fit_outcome = Some(cuped_adjust(y_view, pre.as_array())?.0);
+            }
+            (CausalMethod::Forest(forest), None, Some(baseline)) => {
+                forest.fit_on_residuals(x_view, t_view, y_view, baseline.as_array())?;
+                fit_outcome = Some(&y_view - &baseline.as_array());
+            }
+            (_, Some(_), None) => {
+                return Err(PyValueError::new_err(
+                    "pre_covariate is only available for method='forest'",
+                ))
+            }
+            (_, None, Some(_)) => {
+                return Err(PyValueError::new_err(
+                    "baseline is only available for method='forest'",
+                ))
+            }
+            (method, None, None) => match sample_weight {
+                Some(ref w) => {
+                    method
+                        .as_trait_mut()
+                        .fit_weighted(x_view, t_view, y_view, w.as_array())?
+                }
+                None => method.as_trait_mut().fit(x_view, t_view, y_view)?,
+            },
+        }
+    }
+
+    if let CausalMethod::Forest(forest) = &causal_method {
+        if forest.outcome_is_constant {
+            PyErr::warn(
+                py,
+                py.get_type::<pyo3::exceptions::PyUserWarning>(),
+                "Outcome is constant; all treatment effects are zero and no trees were grown",
+                1,
+            )?;
+        }
+        if !forest.post_treatment_suspects.is_empty() {
+            let labels = forest.feature_labels();
+            let suspects: Vec<String> = forest
+                .post_treatment_suspects
+                .iter()
+                .map(|s| {
+                    format!(
+                        "{} ({:+.2} SD)",
+                        labels[s.feature_idx], s.standardized_difference
+                    )
+                })
+                .collect();
+            PyErr::warn(
+                py,
+                py.get_type::<pyo3::exceptions::PyUserWarning>(),
+                &format!(
+                    "Features differ strongly between treatment arms and may be post-treatment \
+                     (mediators or colliders) or strong confounders: {}",
+                    suspects.join(", ")
+                ),
+                1,
+            )?;
+        }
+        if let Some(warning) = forest.fit_report.as_ref().and_then(|r| r.warning.as_ref()) {
+            PyErr::warn(
+                py,
+                py.get_type::<pyo3::exceptions::PyUserWarning>(),
+                warning,
+                1,
+            )?;
+        }
+    }
+
+    Ok(Model {
+        method: causal_method,
+        x: features,
+        t: treatment,
+        y: match fit_outcome {
+            Some(adjusted) => adjusted.to_pyarray(py).to_owned(),
+            None => outcome,
+        },
+        sample_weight: sample_weight.map(|w| w.as_array().to_pyarray(py).to_owned()),
+        feature_names,
+        confidence_level: 0.95,
+        subgroups: None,
+    })
+}
+
+/// Forests for a 2x2 factorial design (two simultaneous binary treatments).
+#[pyclass]
+#[derive(Clone)]
+struct FactorialModel {
+    forest: FactorialForest,
+}
+
+#[pymethods]
+impl FactorialModel {
+    /// Per-unit main effects of each treatment and their interaction, as numpy arrays
+    /// under `effect_a`, `effect_b` and `interaction`.
+    fn estimate_effects(&self, py: Python, x: PyReadonlyArray2<f64>) -> PyResult<PyObject> {
+        let effects = self.forest.predict(x.as_array())?;
+        let dict = PyDict::new(py);
+        dict.set_item("effect_a", effects.effect_a.to_pyarray(py))?;
+        dict.set_item("effect_b", effects.effect_b.to_pyarray(py))?;
+        dict.set_item("interaction", effects.interaction.to_pyarray(py))?;
+        Ok(dict.to_object(py))
+    }
+}
+
+/// Factorial model over two binary treatment columns; accepts the forest keyword
+/// arguments of `create_model`, which configure all four component forests.
+#[pyfunction]
+#[pyo3(signature = (
+    features,
+    treatment,
+    outcome,
+    feature_names = None,
+    ci_spread = "tree_std",
+    ci_distribution = "normal",
+    split_strategy = "best_random",
+    feature_sample_weights = None,
+    constant_outcome = "zero",
+    max_depth = 5,
+    n_jobs = None,
+    gain_balance_penalty = 1.0,
+    outcome_transform = "none",
+    degenerate_leaf = "zero",
+    post_treatment_threshold = Some(0.5),
+    min_leaf_treated = 0,
+    min_leaf_control = 0,
+    seed = None,
+    subsample_ratio = 1.0,
+    effect_floor = None,
+    effect_ceil = None,
+    use_propensity = false,
+    n_split_candidates = 10,
+    max_features = MaxFeaturesArg::Name("sqrt"),
+    min_gain = 0.0,
+    forest_config = None,
+    relative_gain_tol = 0.0
+))]
+#[allow(clippy::too_many_arguments)]
+fn create_factorial_model(
+    features: PyReadonlyArray2<f64>,
+    treatment: PyReadonlyArray2<f64>,
+    outcome: PyReadonlyArray1<f64>,
+    feature_names: Option<Vec<String>>,
+    ci_spread: &str,
+    ci_distribution: &str,
+    split_strategy: &str,
+    feature_sample_weights: Option<Vec<f64>>,
+    constant_outcome: &str,
+    max_depth: usize,
+    n_jobs: Option<usize>,
+    gain_balance_penalty: f64,
+    outcome_transform: &str,
+    degenerate_leaf: &str,
+    post_treatment_threshold: Option<f64>,
+    min_leaf_treated: usize,
+    min_leaf_control: usize,
+    seed: Option<u64>,
+    subsample_ratio: f64,
+    effect_floor: Option<f64>,
+    effect_ceil: Option<f64>,
+    use_propensity: bool,
+    n_split_candidates: usize,
+    max_features: MaxFeaturesArg,
+    min_gain: f64,
+    forest_config: Option<&PyDict>,
+    relative_gain_tol: f64,
+) -> PyResult<FactorialModel> {
+    let template = ForestArgs {
+        feature_names,
+        ci_spread,
+        ci_distribution,
+        split_strategy,
+        feature_sample_weights,
+        constant_outcome,
+        max_depth,
+        n_jobs,
+        gain_balance_penalty,
+        outcome_transform,
+        degenerate_leaf,
+        post_treatment_threshold,
+        min_leaf_treated,
+        min_leaf_control,
+        seed,
+        subsample_ratio,
+        effect_floor,
+        effect_ceil,
+        treatment_kind: TreatmentKind::Binary,
+        use_propensity,
+        n_split_candidates,
+        max_features,
+        min_gain,
+        forest_config,
+        relative_gain_tol,
+    }
+    .build()?;
+    let mut forest = FactorialForest::new(template);
+    forest.fit(
+        features.as_array(),
+        treatment.as_array(),
+        outcome.as_array(),
+    )?;
+    Ok(FactorialModel { forest })
+}
+
+/// Loads a model written by `Model.save`. The `create_model` preprocessing is not
+/// saved, so the returned model expects already-processed features, e.g. from the
+/// original wrapper's `transform`. Without training rows, plots and checks that
+/// predict on the training data (`effect_dist`, `validate`) raise a `ValueError`.
+#[pyfunction]
+fn load_model(py: Python, path: &str) -> PyResult<Model> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+    let doc: ModelDocument =
+        serde_json::from_str(&json).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    if doc.format_version != MODEL_FORMAT_VERSION {
+        return Err(PyValueError::new_err(format!(
+            "Unsupported model format_version {} (expected {})",
+            doc.format_version, MODEL_FORMAT_VERSION
+        )));
+    }
+    let n_features = doc.feature_names.as_ref().map_or(0, |names| names.len());
+    Ok(Model {
+        method: doc.model,
+        x: ndarray::Array2::<f64>::zeros((0, n_features))
+            .to_pyarray(py)
+            .to_owned(),
+        t: ndarray::Array1::<f64>::zeros(0).to_pyarray(py).to_owned(),
+        y: ndarray::Array1::<f64>::zeros(0).to_pyarray(py).to_owned(),
+        sample_weight: None,
+        feature_names: doc.feature_names,
+        confidence_level: doc.confidence_level,
+        subgroups: None,
+    })
+}
+
+#[pyfunction]
+#[pyo3(signature = (model, plot = "graph"))]
+fn plot_model(py: Python, model: Model, plot: &str) -> PyResult<PyObject> {
+    let visual = model.get_visual(py, plot)?;
+    let json_str = visual.to_json();
+    let json_module = py.import("json")?;
+    let dict = json_module.call_method1("loads", (json_str,))?;
+    Ok(dict.to_object(py))
+}
+
+#[pyfunction]
+fn data_summary(
+    py: Python,
+    treatment: PyReadonlyArray1<f64>,
+    outcome: PyReadonlyArray1<f64>,
+) -> PyResult<PyObject> {
+    let summary = core_data_summary(treatment.as_array(), outcome.as_array())?;
+    to_py_object(py, &summary)
+}
+
+#[pyfunction]
+#[pyo3(signature = (n_treated, n_control, outcome_std, power = 0.8, alpha = 0.05))]
+fn min_detectable_effect(
+    n_treated: usize,
+    n_control: usize,
+    outcome_std: f64,
+    power: f64,
+    alpha: f64,
+) -> PyResult<f64> {
+    Ok(core_min_detectable_effect(
+        n_treated,
+        n_control,
+        outcome_std,
+        power,
+        alpha,
+    )?)
+}
+
+/// Drops the nuisance fits (propensity scores, outcome regressions) that `validate`
+/// and `refute` reuse across refits, returning how many were cached.
+#[pyfunction]
+fn clear_cache() -> usize {
+    core_clear_cache()
+}
+
+fn to_py_object<T: serde::Serialize>(py: Python, value: &T) -> PyResult<PyObject> {
+    let json_str = serde_json::to_string(value)
+        .map_err(|e| PyValueError::new_err(format!("Serialization failed: {}", e)))?;
+    let json_module = py.import("json")?;
+    let obj = json_module.call_method1("loads", (json_str,))?;
+    Ok(obj.to_object(py))
+}
+
+/// Page colors to pair with an ECharts theme. Custom theme names get a transparent
+/// background and inherited text color so the host page shows through.
+struct ChartTheme {
+    /// JS string literal of the theme name, passed to `echarts.init`.
+    name_js: String,
+    background: &'static str,
+    text: &'static str,
+}
+
+fn chart_theme(name: &str) -> ChartTheme {
+    let (background, text) = match name {
+        "dark" => ("#1a1a2e", "#fff"),
+        "light" => ("#fff", "#222"),
+        _ => ("transparent", "inherit"),
+    };
+    ChartTheme {
+        name_js: serde_json::to_string(name).unwrap_or_else(|_| "'dark'".to_string()),
+        background,
+        text,
+    }
+}
+
+fn render_preview(py: Python, visual: &VisualOutput, theme: &str) -> PyResult<()> {
+    let json_data = visual.to_json();
+    let theme = chart_theme(theme);
+    let html_template = format!(
+        r#"
+<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="UTF-8">
+    <title>CausalFlow Preview: {}</title>
+    <script src="https://cdn.jsdelivr.net/npm/echarts@5.4.3/dist/echarts.min.js"></script>
+    <style>
+        body {{ font-family: sans-serif; background: {background}; color: {text}; margin: 0; padding: 20px; }}
+        #chart {{ width: 100%; height: 600px; }}
+        h1 {{ color: #4fc3f7; text-align: center; }}
+    </style>
+</head>
+<body>
+    <h1>CausalFlow: {}</h1>
     <div id="chart"></div>
     <script>
         const rawData = {};
-        const chart = echarts.init(document.getElementById('chart'), 'dark');
+        const chart = echarts.init(document.getElementById('chart'), {theme});
         
         let option = {{}};
         if (rawData.visual_type === 'causal_graph') {{
@@ -476,13 +2536,52 @@ fn render_preview(py: Python, visual: &VisualOutput) -> PyResult<()> {
                 xAxis: {{ type: 'value' }},
                 series: [{{ data: rawData.data.values, type: 'bar', itemStyle: {{ color: '#81c784' }} }}]
             }};
+        }} else if (rawData.visual_type === 'importance_stability') {{
+            const d = rawData.data;
+            option = {{
+                yAxis: {{ type: 'category', data: d.labels }},
+                xAxis: {{ type: 'value' }},
+                series: [
+                    {{ data: d.values, type: 'bar', itemStyle: {{ color: '#81c784' }} }},
+                    {{
+                        type: 'custom',
+                        data: d.values.map((v, i) => [i, v - d.errors[i], v + d.errors[i]]),
+                        renderItem: (params, api) => {{
+                            const lo = api.coord([api.value(1), api.value(0)]);
+                            const hi = api.coord([api.value(2), api.value(0)]);
+                            return {{ type: 'line', shape: {{ x1: lo[0], y1: lo[1], x2: hi[0], y2: hi[1] }}, style: {{ stroke: '{text}', lineWidth: 2 }} }};
+                        }}
+                    }}
+                ]
+            }};
+        }} else if (rawData.visual_type === 'partial_dependence') {{
+            const d = rawData.data;
+            option = {{
+                tooltip: {{ trigger: 'axis' }},
+                xAxis: {{ type: 'value', name: d.feature_name, scale: true }},
+                yAxis: {{ type: 'value', name: 'Effect' }},
+                series: [{{ data: d.grid.map((g, i) => [g, d.effects[i]]), type: 'line', itemStyle: {{ color: '#ff7043' }} }}]
+            }};
+        }} else if (rawData.visual_type === 'variance_shares') {{
+            option = {{
+                tooltip: {{ trigger: 'item', formatter: '{{b}}: {{d}}%' }},
+                series: [{{
+                    type: 'pie', radius: '60%',
+                    data: rawData.data.labels.map((l, i) => ({{ name: l, value: rawData.data.values[i] }}))
+                }}]
+            }};
         }}
         chart.setOption(option);
     </script>
 </body>
 </html>
 "#,
-        visual.title, visual.title, json_data
+        visual.title,
+        visual.title,
+        json_data,
+        background = theme.background,
+        text = theme.text,
+        theme = theme.name_js,
     );
 
     let tempfile = py.import("tempfile")?;
@@ -504,35 +2603,46 @@ fn render_preview(py: Python, visual: &VisualOutput) -> PyResult<()> {
     Ok(())
 }
 
-fn render_html_fragment(visual: &VisualOutput) -> String {
-    let json_data = visual.to_json();
-    let div_id = format!("causal-plot-{}", uuid_gen());
-    format!(r#"
-<div id="{}" style="width: 100%; height: 500px; min-height: 400px; background: #1a1a2e; border-radius: 8px; padding: 10px;"></div>
-<script>
-(function() {{
-    const render = () => {{
-        const chartDom = document.getElementById('{}');
-        if (!chartDom) return;
-        
-        // Ensure ECharts is loaded
-        if (typeof echarts === 'undefined') {{
-            if (!window._echartsLoading) {{
+/// Loads ECharts on demand so standalone fragments work in notebooks and chat UIs.
+const ECHARTS_LOADER_JS: &str = r#"        // Ensure ECharts is loaded
+        if (typeof echarts === 'undefined') {
+            if (!window._echartsLoading) {
                 window._echartsLoading = true;
                 const script = document.createElement('script');
                 script.src = 'https://cdn.jsdelivr.net/npm/echarts@5.4.3/dist/echarts.min.js';
-                script.onload = () => {{
+                script.onload = () => {
                     window._echartsLoaded = true;
                     document.dispatchEvent(new Event('echarts-ready'));
-                }};
+                };
                 document.head.appendChild(script);
-            }}
+            }
             document.addEventListener('echarts-ready', render);
             return;
-        }}
+        }
+"#;
 
+fn render_html_fragment(visual: &VisualOutput, theme: &str) -> String {
+    render_chart_fragment(visual, true, theme)
+}
+
+/// Chart `<div>` plus its render script. Without the loader the page must already
+/// include ECharts (as `render_report_page` does).
+fn render_chart_fragment(visual: &VisualOutput, with_loader: bool, theme: &str) -> String {
+    let json_data = visual.to_json();
+    let theme = chart_theme(theme);
+    let loader = if with_loader { ECHARTS_LOADER_JS } else { "" };
+    let div_id = format!("causal-plot-{}", uuid_gen());
+    format!(r#"
+<div id="{}" style="width: 100%; height: 500px; min-height: 400px; background: {background}; border-radius: 8px; padding: 10px;"></div>
+<script>
+(function() {{
+    const render = () => {{
+        const chartDom = document.getElementById('{}');
+        if (!chartDom) return;
+        
+{}
         const rawData = {};
-        const chart = echarts.init(chartDom, 'dark');
+        const chart = echarts.init(chartDom, {theme});
         
         let option = {{}};
         if (rawData.visual_type === 'causal_graph') {{
@@ -565,6 +2675,43 @@ fn render_html_fragment(visual: &VisualOutput) -> String {
                 xAxis: {{ type: 'value' }},
                 series: [{{ data: rawData.data.values, type: 'bar', itemStyle: {{ color: '#81c784' }} }}]
             }};
+        }} else if (rawData.visual_type === 'importance_stability') {{
+            const d = rawData.data;
+            option = {{
+                title: {{ text: rawData.title, left: 'center', textStyle: {{ color: '#4fc3f7' }} }},
+                yAxis: {{ type: 'category', data: d.labels }},
+                xAxis: {{ type: 'value' }},
+                series: [
+                    {{ data: d.values, type: 'bar', itemStyle: {{ color: '#81c784' }} }},
+                    {{
+                        type: 'custom',
+                        data: d.values.map((v, i) => [i, v - d.errors[i], v + d.errors[i]]),
+                        renderItem: (params, api) => {{
+                            const lo = api.coord([api.value(1), api.value(0)]);
+                            const hi = api.coord([api.value(2), api.value(0)]);
+                            return {{ type: 'line', shape: {{ x1: lo[0], y1: lo[1], x2: hi[0], y2: hi[1] }}, style: {{ stroke: '{text}', lineWidth: 2 }} }};
+                        }}
+                    }}
+                ]
+            }};
+        }} else if (rawData.visual_type === 'partial_dependence') {{
+            const d = rawData.data;
+            option = {{
+                title: {{ text: rawData.title, left: 'center', textStyle: {{ color: '#4fc3f7' }} }},
+                tooltip: {{ trigger: 'axis' }},
+                xAxis: {{ type: 'value', name: d.feature_name, scale: true }},
+                yAxis: {{ type: 'value', name: 'Effect' }},
+                series: [{{ data: d.grid.map((g, i) => [g, d.effects[i]]), type: 'line', itemStyle: {{ color: '#ff7043' }} }}]
+            }};
+        }} else if (rawData.visual_type === 'variance_shares') {{
+            option = {{
+                title: {{ text: rawData.title, left: 'center', textStyle: {{ color: '#4fc3f7' }} }},
+                tooltip: {{ trigger: 'item', formatter: '{{b}}: {{d}}%' }},
+                series: [{{
+                    type: 'pie', radius: '60%',
+                    data: rawData.data.labels.map((l, i) => ({{ name: l, value: rawData.data.values[i] }}))
+                }}]
+            }};
         }}
         chart.setOption(option);
         
@@ -574,25 +2721,92 @@ fn render_html_fragment(visual: &VisualOutput) -> String {
     render();
 }})();
 </script>
-"#, div_id, div_id, json_data)
+"#,
+        div_id,
+        div_id,
+        loader,
+        json_data,
+        background = theme.background,
+        text = theme.text,
+        theme = theme.name_js,
+    )
+}
+
+fn render_report_page(sections: &str, theme: &str) -> String {
+    let theme = chart_theme(theme);
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="UTF-8">
+    <title>CausalFlow Report</title>
+    <script src="https://cdn.jsdelivr.net/npm/echarts@5.4.3/dist/echarts.min.js"></script>
+    <style>
+        body {{ font-family: sans-serif; background: {background}; color: {text}; margin: 0; padding: 20px; }}
+        h1, h2 {{ color: #4fc3f7; }}
+        section {{ margin-bottom: 40px; }}
+        table {{ border-collapse: collapse; }}
+        td {{ border: 1px solid #444; padding: 4px 12px; }}
+    </style>
+</head>
+<body>
+<h1>CausalFlow Report</h1>
+{}
+</body>
+</html>
+"#,
+        sections,
+        background = theme.background,
+        text = theme.text,
+    )
+}
+
+/// Two-column key/value table of a flat JSON object.
+fn html_table(value: &serde_json::Value) -> String {
+    let mut html = String::from("<table>\n");
+    if let Some(fields) = value.as_object() {
+        for (key, val) in fields {
+            let shown = match val {
+                serde_json::Value::String(text) => text.clone(),
+                serde_json::Value::Null => "-".to_string(),
+                other => other.to_string(),
+            };
+            html.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>\n", key, shown));
+        }
+    }
+    html.push_str("</table>\n");
+    html
 }
 
 fn uuid_gen() -> String {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
+    use std::sync::atomic::{AtomicU64, Ordering};
     use std::time::SystemTime;
+    // The counter keeps ids unique when several fragments render within one clock tick
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
     let mut s = DefaultHasher::new();
     SystemTime::now().hash(&mut s);
+    COUNTER.fetch_add(1, Ordering::Relaxed).hash(&mut s);
     format!("{:x}", s.finish())
 }
 
 #[pymodule]
 fn _causalflow(_py: Python, m: &PyModule) -> PyResult<()> {
+    init_thread_pool_from_env()?;
     m.add_function(wrap_pyfunction!(analyze_flow, m)?)?;
     m.add_function(wrap_pyfunction!(create_model, m)?)?;
+    m.add_function(wrap_pyfunction!(create_factorial_model, m)?)?;
     m.add_function(wrap_pyfunction!(plot_model, m)?)?;
+    m.add_function(wrap_pyfunction!(load_model, m)?)?;
+    m.add_function(wrap_pyfunction!(data_summary, m)?)?;
+    m.add_function(wrap_pyfunction!(min_detectable_effect, m)?)?;
+    m.add_function(wrap_pyfunction!(clear_cache, m)?)?;
     m.add_class::<Model>()?;
+    m.add_class::<FactorialModel>()?;
     m.add_class::<InferenceResult>()?;
     m.add_class::<ValidationResult>()?;
+    m.add("DEFAULT_PLACEBO_RATIO_THRESHOLD", DEFAULT_PLACEBO_RATIO_THRESHOLD)?;
+    m.add("DEFAULT_PLACEBO_ABSOLUTE_THRESHOLD", DEFAULT_PLACEBO_ABSOLUTE_THRESHOLD)?;
     Ok(())
 }
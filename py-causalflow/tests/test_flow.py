@@ -1,3 +1,4 @@
+import json
 import pandas as pd
 import numpy as np
 import pytest
@@ -62,3 +63,1696 @@ def test_invalid_method():
     y = [1]
     with pytest.raises(ValueError, match="Unknown method"):
         causalflow.create_model(x, t, y, method='unknown_algo')
+
+def test_graph_text_exports():
+    # DOT and Mermaid exports should contain every named node
+    df = pd.DataFrame({'age': [20, 30, 40, 50], 'income': [1, 2, 3, 4]})
+    t = [0, 1, 0, 1]
+    y = [1, 3, 2, 4]
+
+    model = causalflow.create_model(df, t, y, method='linear')
+
+    dot = model.to_dot()
+    assert dot.startswith("digraph")
+    assert '"age"' in dot and '"Treatment" -> "Outcome"' in dot
+
+    mermaid = model.to_graph_mermaid()
+    assert mermaid.startswith("graph LR")
+    assert "classDef treatment" in mermaid
+
+def test_to_dot_weights_edges_by_link_weight():
+    np.random.seed(131)
+    X = pd.DataFrame({'age': np.random.rand(200), 'income': np.random.rand(200)})
+    T = np.random.randint(0, 2, 200).astype(float)
+    Y = 2.0 * X['age'].values * T + np.random.normal(scale=0.1, size=200)
+
+    model = causalflow.create_model(X, T, Y)
+    dot = model.to_dot()
+    with pytest.warns(DeprecationWarning, match="to_dot"):
+        assert dot == model.to_graph_dot()
+    assert '"Treatment" -> "Outcome" [label="1.000", penwidth=5.00]' in dot
+    assert 'fillcolor=' in dot
+    assert model.to_dot('importance') is None
+
+def test_forest_interval_spread():
+    # Both spreads produce intervals around each prediction; bad names are rejected
+    rng = np.random.default_rng(0)
+    x = rng.normal(size=(200, 3))
+    t = rng.integers(0, 2, size=200).astype(np.float64)
+    y = x[:, 0] * t + rng.normal(size=200)
+
+    std_model = causalflow.create_model(x, t, y, ci_spread='tree_std')
+    se_model = causalflow.create_model(x, t, y, ci_spread='standard_error')
+
+    for model in (std_model, se_model):
+        res = model.estimate_effects(x)
+        for (lo, hi), p in zip(res.confidence_intervals, res.predictions):
+            assert lo <= p <= hi
+
+    with pytest.raises(ValueError, match="Unknown ci_spread"):
+        causalflow.create_model(x, t, y, ci_spread='bogus')
+
+def test_feature_sample_weights_validation():
+    x = np.random.default_rng(1).normal(size=(50, 4))
+    t = np.array([0, 1] * 25, dtype=np.float64)
+    y = x[:, 0] + t
+
+    model = causalflow.create_model(x, t, y, feature_sample_weights=[4.0, 1.0, 1.0, 0.0])
+    assert model.estimate_effects(x) is not None
+
+    with pytest.raises(ValueError, match="feature_sample_weights"):
+        causalflow.create_model(x, t, y, feature_sample_weights=[1.0, 1.0])
+    with pytest.raises(ValueError, match="non-negative"):
+        causalflow.create_model(x, t, y, feature_sample_weights=[1.0, -1.0, 1.0, 1.0])
+
+def test_tree_predictions_shape():
+    rng = np.random.default_rng(2)
+    x = rng.normal(size=(40, 3))
+    t = np.array([0, 1] * 20, dtype=np.float64)
+    y = x[:, 0] * t
+
+    model = causalflow.create_model(x, t, y)
+    per_tree = model.tree_predictions(x)
+    assert per_tree.shape == (40, 10)
+
+    res = model.estimate_effects(x)
+    assert np.allclose(per_tree.mean(axis=1), res.predictions)
+
+def test_data_summary():
+    t = np.array([0, 0, 1, 1], dtype=np.float64)
+    y = np.array([1, 3, 5, 7], dtype=np.float64)
+
+    summary = causalflow.data_summary(t, y)
+    assert summary['n_samples'] == 4
+    assert summary['treatment_prevalence'] == 0.5
+    assert summary['treated_outcome_mean'] == 6.0
+    assert summary['control_outcome_mean'] == 2.0
+
+def test_add_trees_grows_forest():
+    rng = np.random.default_rng(3)
+    x = rng.normal(size=(60, 4))
+    t = np.array([0, 1] * 30, dtype=np.float64)
+    y = x[:, 0] * t + rng.normal(size=60)
+
+    model = causalflow.create_model(x, t, y)
+    model.add_trees(5)
+    assert model.tree_predictions(x).shape == (60, 15)
+
+    importance = model.estimate_effects(x).feature_importance
+    assert abs(sum(importance) - 1.0) < 1e-9 or sum(importance) == 0.0
+
+    # A calibration fit to the old ensemble is dropped when trees are added
+    model.calibrate(x, t, y)
+    model.add_trees(5)
+    raw = model.tree_predictions(x).mean(axis=1)
+    assert np.allclose(model.estimate_effects(x).predictions, raw)
+
+def test_set_confidence_level():
+    rng = np.random.default_rng(4)
+    x = rng.normal(size=(80, 3))
+    t = np.array([0, 1] * 40, dtype=np.float64)
+    y = x[:, 0] * t + rng.normal(size=80)
+
+    model = causalflow.create_model(x, t, y)
+    wide = model.estimate_effects(x)
+    model.set_confidence_level(0.5)
+    narrow = model.estimate_effects(x)
+
+    assert narrow.confidence_level == 0.5
+    widths = lambda r: [hi - lo for lo, hi in r.confidence_intervals]
+    assert all(n <= w + 1e-12 for n, w in zip(widths(narrow), widths(wide)))
+
+    with pytest.raises(ValueError):
+        model.set_confidence_level(1.5)
+
+@pytest.mark.parametrize("method", ["linear", "aipw"])
+def test_confidence_level_sets_interval_width(method):
+    rng = np.random.default_rng(6)
+    x = rng.normal(size=(400, 2))
+    t = rng.integers(0, 2, 400).astype(np.float64)
+    y = 1.5 * t + x[:, 0] + rng.normal(size=400)
+
+    model = causalflow.create_model(x, t, y, method=method)
+    width = lambda r: r.confidence_intervals[0][1] - r.confidence_intervals[0][0]
+    at_95 = width(model.estimate_effects(x))
+    model.set_confidence_level(0.9)
+    at_90 = width(model.estimate_effects(x))
+    model.set_confidence_level(0.5)
+    at_50 = width(model.estimate_effects(x))
+
+    # Ratios of normal quantiles; with ~400 rows the t quantiles agree to well under 1%
+    assert at_95 / at_90 == pytest.approx(1.959964 / 1.644854, rel=0.01)
+    assert at_95 / at_50 == pytest.approx(1.959964 / 0.674490, rel=0.01)
+
+def test_conditional_ate_with_expression():
+    rng = np.random.default_rng(5)
+    df = pd.DataFrame({'age': rng.integers(20, 80, size=120), 'score': rng.normal(size=120)})
+    t = np.array([0, 1] * 60, dtype=np.float64)
+    y = (df['age'] > 50).to_numpy() * 2.0 * t + rng.normal(size=120)
+
+    model = causalflow.create_model(df, t, y)
+    res = model.conditional_ate(df, "age > 50")
+    assert res['n'] == int((df['age'] > 50).sum())
+    assert res['ci_lower'] <= res['ate'] <= res['ci_upper']
+
+def test_model_spec_reuse():
+    spec = causalflow.ModelSpec(
+        method='linear',
+        treatment='arm',
+        outcome='sales',
+        treatment_encoding={'control': 0.0, 'promo': 1.0},
+    )
+    df = pd.DataFrame({
+        'region_size': [1.0, 2.0, 3.0, 4.0],
+        'arm': ['control', 'promo', 'control', 'promo'],
+        'sales': [10.0, 14.0, 11.0, 15.0],
+    })
+
+    model = causalflow.create_model(df, spec=spec)
+    assert model.feature_names_out_ == ['region_size']
+    # Adjusted for region_size (sales rise 0.5 per unit), the promo lift is 3.5
+    assert abs(model.estimate_effects(df[['region_size']]).mean_effect - 3.5) < 1e-5
+
+    with pytest.raises(ValueError, match="treatment_encoding"):
+        causalflow.create_model(df.assign(arm=['control', 'other', 'control', 'promo']), spec=spec)
+
+    # Explicit keyword arguments win over the spec
+    with pytest.raises(ValueError, match="Unknown method"):
+        causalflow.create_model(df, spec=spec, method='bogus')
+
+def test_constant_outcome():
+    X = np.random.rand(50, 2)
+    T = np.random.randint(0, 2, 50).astype(float)
+    Y = np.full(50, 3.0)
+
+    with pytest.warns(UserWarning, match="constant"):
+        model = causalflow.create_model(X, T, Y)
+    results = model.estimate_effects(X)
+    assert np.allclose(results.predictions, 0.0)
+
+    with pytest.raises(RuntimeError, match="constant"):
+        causalflow.create_model(X, T, Y, constant_outcome="error")
+
+def test_effect_variance_shares():
+    np.random.seed(0)
+    X = np.random.rand(200, 3)
+    T = np.random.randint(0, 2, 200).astype(float)
+    Y = 2.0 * X[:, 0] * T + np.random.normal(0, 0.1, 200)
+
+    X = pd.DataFrame(X, columns=['driver', 'noise_a', 'noise_b'])
+    model = causalflow.create_model(X, T, Y)
+    shares = model.effect_variance_shares(X)
+    assert set(shares) == {'driver', 'noise_a', 'noise_b'}
+    assert abs(sum(shares.values()) - 1.0) < 1e-6
+    assert max(shares, key=shares.get) == 'driver'
+
+    assert '"visual_type": "variance_shares"' in model.to_visual_tag('variance_shares')
+
+def test_partial_dependence_plot():
+    np.random.seed(71)
+    X = pd.DataFrame({'dose': np.random.rand(400), 'noise': np.random.rand(400)})
+    T = np.random.randint(0, 2, 400).astype(float)
+    Y = 3.0 * X['dose'].values * T + np.random.normal(0, 0.1, 400)
+
+    model = causalflow.create_model(X, T, Y, max_depth=6)
+    tag = model.to_visual_tag('pdp', feature='dose')
+    data = json.loads(tag.split('\n', 1)[1].rsplit('\n', 1)[0])
+    assert data['visual_type'] == 'partial_dependence'
+    assert data['data']['feature_name'] == 'dose'
+    assert data['data']['grid'] == sorted(data['data']['grid'])
+    assert data['data']['effects'][-1] > data['data']['effects'][0]
+    assert 'partial_dependence' in model.to_html('pdp', feature='dose')
+
+    with pytest.raises(ValueError, match="requires a feature"):
+        model.to_visual_tag('pdp')
+    with pytest.raises(ValueError, match="Unknown feature"):
+        model.to_visual_tag('pdp', feature='missing')
+
+def test_effect_dist_bins_and_range():
+    np.random.seed(113)
+    X = pd.DataFrame({'a': np.random.rand(300), 'b': np.random.rand(300)})
+    T = np.random.randint(0, 2, 300).astype(float)
+    Y = 2.0 * X['a'].values * T + np.random.normal(0, 0.1, 300)
+
+    results = causalflow.create_model(X, T, Y).estimate_effects(X)
+    data = results.to_dict('effect_dist', n_bins=25)['data']
+    assert len(data['bins']) == 25
+    assert sum(data['counts']) == 300
+
+    clamped = results.to_dict('effect_dist', n_bins=4, range=(0.5, 1.5))['data']
+    assert clamped['bins'] == [0.5, 0.75, 1.0, 1.25]
+    assert sum(clamped['counts']) == 300
+
+    model = causalflow.create_model(X, T, Y, method='linear')
+    tag = model.to_visual_tag('effect_dist', n_bins=30)
+    constant = json.loads(tag.split('\n', 1)[1].rsplit('\n', 1)[0])['data']
+    assert constant['bins'] == [pytest.approx(model.estimate_effects(X).mean_effect)]
+    assert constant['counts'] == [300]
+
+    with pytest.raises(ValueError, match="n_bins"):
+        results.to_dict('effect_dist', n_bins=0)
+    with pytest.raises(ValueError, match="range"):
+        model.to_html('effect_dist', range=(1.0, 0.0))
+
+def test_deep_tree_does_not_overflow():
+    # A single strongly ordered feature with tiny leaves keeps splitting off a few
+    # rows at a time, producing very deep, chain-like trees.
+    n = 4000
+    X = np.arange(n, dtype=float).reshape(-1, 1) ** 3
+    T = (np.arange(n) % 2).astype(float)
+    Y = X[:, 0] * T
+
+    model = causalflow.create_model(X, T, Y, max_depth=200, use_mice=False)
+    results = model.estimate_effects(X)
+    assert len(results.predictions) == n
+
+    structure = model.export_structure()
+    depth, stack = 0, [(tree, 0) for tree in structure['trees']]
+    while stack:
+        node, level = stack.pop()
+        depth = max(depth, level)
+        if 'left' in node:
+            stack += [(node['left'], level + 1), (node['right'], level + 1)]
+    assert depth > 10
+
+def test_config_context_manager():
+    X = np.random.rand(60, 2)
+    T = np.random.randint(0, 2, 60).astype(float)
+    Y = X[:, 0] * T
+
+    assert causalflow.get_config()['n_jobs'] is None
+    with causalflow.config(n_jobs=1, seed=7):
+        assert causalflow.get_config() == {'n_jobs': 1, 'seed': 7}
+        model = causalflow.create_model(X, T, Y)
+        assert model._processor.random_state == 7
+    assert causalflow.get_config() == {'n_jobs': None, 'seed': None}
+
+    with pytest.raises(ValueError, match="Unknown config options"):
+        with causalflow.config(threads=2):
+            pass
+
+def test_isotonic_calibration():
+    np.random.seed(1)
+    X = np.random.rand(400, 2)
+    T = np.random.randint(0, 2, 400).astype(float)
+    Y = 3.0 * X[:, 0] * T + np.random.normal(0, 0.1, 400)
+
+    model = causalflow.create_model(X, T, Y)
+    model.calibrate(X, T, Y)
+    results = model.estimate_effects(X)
+
+    raw_order = np.argsort(model.tree_predictions(X).mean(axis=1))
+    calibrated = results.predictions
+    # The calibration map is monotone, so it preserves the ranking of raw predictions
+    assert np.all(np.diff(calibrated[raw_order]) >= -1e-9)
+    assert all(lo <= hi for lo, hi in results.confidence_intervals)
+
+    # Subgroup effects and the heterogeneity score use the calibrated effects too
+    everyone = model.conditional_ate(X, np.ones(400, dtype=bool))
+    assert everyone['ate'] == pytest.approx(results.mean_effect)
+    assert everyone['ci_lower'] <= everyone['ate'] <= everyone['ci_upper']
+    assert model.weighted_ate(X, np.ones(400))['ate'] == pytest.approx(results.mean_effect)
+    gain = model.heterogeneity_gain(X, T, Y)
+    e = T.mean()
+    y_star = Y * (T - e) / (e * (1 - e))
+    assert gain['mse_heterogeneous'] == pytest.approx(np.mean((y_star - calibrated) ** 2))
+
+def test_inference_result_json_round_trip():
+    X = np.random.rand(50, 2)
+    T = np.random.randint(0, 2, 50).astype(float)
+    Y = X[:, 0] * T
+
+    X = pd.DataFrame(X, columns=['a', 'b'])
+    model = causalflow.create_model(X, T, Y)
+    results = model.estimate_effects(X)
+    doc = json.loads(results.to_json())
+    assert doc['schema_version'] == 1
+    assert doc['feature_names'] == ['a', 'b']
+    assert len(doc['predictions']) == 50
+
+    restored = causalflow.InferenceResult.from_json(results.to_json())
+    assert np.allclose(restored.predictions, results.predictions)
+    assert restored.mean_effect == results.mean_effect
+    assert restored.confidence_intervals == results.confidence_intervals
+
+def test_cluster_bootstrap_ci():
+    np.random.seed(3)
+    df = pd.DataFrame({
+        'x1': np.random.rand(120),
+        'x2': np.random.rand(120),
+        'school': np.repeat([f's{i}' for i in range(12)], 10),
+    })
+    T = np.random.randint(0, 2, 120).astype(float)
+    Y = df['x1'].values * T
+
+    model = causalflow.create_model(df[['x1', 'x2']], T, Y)
+    results = model.estimate_effects(df, cluster_col='school')
+    lo, hi = results.ate_confidence_interval
+    assert lo <= results.mean_effect <= hi
+
+    assert model.estimate_effects(df[['x1', 'x2']]).ate_confidence_interval is None
+
+    with pytest.raises(ValueError, match="at least 5 clusters"):
+        model.estimate_effects(df.assign(school=np.repeat(['a', 'b'], 60)), cluster_col='school')
+
+def test_importance_percent_with_other_bucket():
+    np.random.seed(4)
+    X = np.random.rand(200, 6)
+    T = np.random.randint(0, 2, 200).astype(float)
+    Y = 3.0 * X[:, 0] * T
+
+    results = causalflow.create_model(X, T, Y).estimate_effects(X)
+    data = results.to_dict(min_percent=10.0)['data']
+    assert abs(sum(data['values']) - 100.0) < 1e-6
+    kept = [v for label, v in zip(data['labels'], data['values']) if label != 'Other']
+    assert all(v >= 10.0 for v in kept)
+
+    # Default output is unchanged
+    assert results.to_dict()['data']['values'] == results.feature_importance
+
+def test_percentile_intervals_can_be_asymmetric():
+    np.random.seed(5)
+    X = np.random.rand(300, 2)
+    T = np.random.randint(0, 2, 300).astype(float)
+    # Heavy right tail in the true effect makes per-tree predictions skewed
+    Y = np.exp(4.0 * X[:, 0]) * T + np.random.normal(0, 0.1, 300)
+
+    model = causalflow.create_model(X, T, Y, ci_spread="tree_percentile")
+    results = model.estimate_effects(X)
+    preds = results.predictions
+    cis = np.array(results.confidence_intervals)
+    lower_gap = preds - cis[:, 0]
+    upper_gap = cis[:, 1] - preds
+    assert np.max(np.abs(upper_gap - lower_gap)) > 1e-3
+
+    # The symmetric construction stays symmetric
+    sym_results = causalflow.create_model(X, T, Y).estimate_effects(X)
+    sym = np.array(sym_results.confidence_intervals)
+    assert np.allclose(sym.mean(axis=1), sym_results.predictions)
+
+def test_fit_report_warns_on_small_arm():
+    X = np.random.rand(200, 2)
+    T = np.zeros(200)
+    T[:20] = 1.0
+    Y = X[:, 0] * T
+
+    with pytest.warns(UserWarning, match="Smaller treatment arm"):
+        model = causalflow.create_model(X, T, Y)
+    report = model.fit_report()
+    assert report['n_treated'] == 20
+    assert report['n_control'] == 180
+    assert report['warning'] is not None
+
+def test_importance_bootstrap():
+    np.random.seed(6)
+    X = pd.DataFrame(np.random.rand(150, 3), columns=['a', 'b', 'c'])
+    T = np.random.randint(0, 2, 150).astype(float)
+    Y = 2.0 * X['a'].values * T
+
+    model = causalflow.create_model(X, T, Y)
+    stability = model.importance_bootstrap(n_boot=5, seed=0)
+    assert set(stability) == {'a', 'b', 'c'}
+    assert abs(sum(v['mean'] for v in stability.values()) - 1.0) < 1e-6
+    assert all(v['std'] >= 0.0 for v in stability.values())
+
+    assert 'importance_stability' in model.to_visual_tag('importance_stability')
+
+def test_summary_reports_null_comparison():
+    np.random.seed(7)
+    X = np.random.rand(200, 2)
+    T = np.random.randint(0, 2, 200).astype(float)
+    Y = 5.0 * T + np.random.normal(0, 0.1, 200)
+    clusters = np.repeat(np.arange(20), 10)
+
+    model = causalflow.create_model(X, T, Y)
+    assert "Zero lies" not in model.estimate_effects(X).summary()
+    summary = model.estimate_effects(X, cluster_col=clusters).summary()
+    assert "Zero lies outside" in summary
+
+def test_num_threads_env_ceiling():
+    import os
+    import subprocess
+    import sys
+
+    script = (
+        "import numpy as np, causalflow\n"
+        "X = np.random.rand(40, 2); T = np.random.randint(0, 2, 40).astype(float)\n"
+        "causalflow.create_model(X, T, X[:, 0] * T, n_jobs=8)\n"
+    )
+    ok = subprocess.run(
+        [sys.executable, "-c", script],
+        env={**os.environ, "CAUSALFLOW_NUM_THREADS": "2"},
+        capture_output=True,
+    )
+    assert ok.returncode == 0, ok.stderr
+
+    bad = subprocess.run(
+        [sys.executable, "-c", "import causalflow"],
+        env={**os.environ, "CAUSALFLOW_NUM_THREADS": "many"},
+        capture_output=True,
+        text=True,
+    )
+    assert bad.returncode != 0
+    assert "CAUSALFLOW_NUM_THREADS" in bad.stderr
+
+def test_gain_balance_penalty_changes_splits():
+    np.random.seed(8)
+    n = 2000
+    X = np.random.rand(n, 1)
+    T = np.random.randint(0, 2, n).astype(float)
+    # Effect only in the top 3% tail of the single feature
+    Y = np.where(X[:, 0] > 0.97, 10.0, 0.0) * T
+
+    def region_effect(penalty):
+        model = causalflow.create_model(X, T, Y, max_depth=1, use_mice=False, gain_balance_penalty=penalty)
+        preds = model.estimate_effects(X).predictions
+        region = (X[:, 0] > 0.85) & (X[:, 0] < 0.95)
+        return preds[region].mean()
+
+    # Without a balance term the root isolates the tail, leaving the region at ~0;
+    # a strong penalty forces a central split that pools the region with the tail.
+    assert region_effect(0.0) < region_effect(4.0)
+
+def test_model_fingerprint():
+    X = np.random.rand(80, 2)
+    T = np.random.randint(0, 2, 80).astype(float)
+    Y = X[:, 0] * T
+
+    model = causalflow.create_model(X, T, Y)
+    fp = model.fingerprint()
+    assert fp == model.fingerprint()
+
+    model.set_confidence_level(0.9)  # model-level setting, forest unchanged
+    assert model.fingerprint() == fp
+
+    model.add_trees(2)
+    assert model.fingerprint() != fp
+
+def test_min_detectable_effect():
+    # Textbook value: 2.8 * sigma * sqrt(1/n1 + 1/n0) for 80% power at alpha=0.05
+    mde = causalflow.min_detectable_effect(100, 100, 1.0)
+    assert abs(mde - 2.8016 * np.sqrt(2 / 100)) < 1e-3
+
+    assert causalflow.min_detectable_effect(400, 400, 1.0) < mde
+
+    with pytest.raises(ValueError):
+        causalflow.min_detectable_effect(0, 100, 1.0)
+
+def test_save_report(tmp_path):
+    X = pd.DataFrame(np.random.rand(80, 2), columns=['age', 'income'])
+    T = np.random.randint(0, 2, 80).astype(float)
+    Y = X['age'].values * T
+
+    model = causalflow.create_model(X, T, Y)
+    path = tmp_path / "report.html"
+    model.save_report(str(path))
+    html = path.read_text()
+    assert html.count("echarts.min.js") == 1
+    assert "Causal Structure Graph" in html
+    assert "Data Summary" in html
+    assert "n_treated" in html
+
+def test_log_outcome_transform():
+    np.random.seed(9)
+    X = np.random.rand(300, 2)
+    T = np.random.randint(0, 2, 300).astype(float)
+    # Treatment multiplies the outcome by 1.2 (+20%)
+    Y = np.exp(1.0 + X[:, 0]) * np.where(T == 1, 1.2, 1.0)
+
+    model = causalflow.create_model(X, T, Y, outcome_transform="log")
+    results = model.estimate_effects(X)
+    assert results.effect_scale == "relative_change"
+    assert abs(results.mean_effect - 0.2) < 0.05
+    assert "relative changes" in results.summary()
+
+    with pytest.raises(ValueError, match="strictly positive"):
+        causalflow.create_model(X, T, Y - 10.0, outcome_transform="log")
+
+def test_prediction_columns_aligned_by_name():
+    np.random.seed(10)
+    X = pd.DataFrame({'a': np.random.rand(100), 'b': np.random.rand(100)})
+    T = np.random.randint(0, 2, 100).astype(float)
+    Y = 3.0 * X['a'].values * T
+
+    model = causalflow.create_model(X, T, Y, use_mice=False)
+    expected = model.tree_predictions(X)
+    shuffled = model.tree_predictions(X[['b', 'a']])
+    assert np.allclose(expected, shuffled)
+
+    with pytest.raises(ValueError, match="missing training columns"):
+        model.estimate_effects(X[['a']])
+
+    with pytest.warns(UserWarning, match="training order"):
+        model.estimate_effects(X.values)
+
+    with pytest.raises(ValueError, match="columns"):
+        model.estimate_effects(np.random.rand(5, 3))
+
+def test_rules_to_frame():
+    np.random.seed(11)
+    X = pd.DataFrame({'age': np.random.rand(300) * 80, 'income': np.random.rand(300)})
+    T = np.random.randint(0, 2, 300).astype(float)
+    Y = np.where(X['age'] > 50, 4.0, 0.0) * T
+
+    model = causalflow.create_model(X, T, Y)
+    rules = model.rules_to_frame(top_k=5)
+    assert list(rules.columns) == ['condition', 'mean_effect', 'support', 'frequency']
+    assert len(rules) <= 5
+    assert rules['mean_effect'].is_monotonic_decreasing
+    assert 'age' in rules['condition'].iloc[0]
+
+def test_cuped_reduces_outcome_variance():
+    np.random.seed(7)
+    pre = np.random.normal(size=400)
+    X = pd.DataFrame({'a': np.random.rand(400), 'b': np.random.rand(400), 'pre': pre})
+    T = np.random.randint(0, 2, 400).astype(float)
+    Y = 3.0 * pre + 0.5 * T + np.random.normal(scale=0.3, size=400)
+
+    model = causalflow.create_model(X, T, Y, pre_covariate='pre')
+    report = model.cuped_report()
+    assert report['theta'] == pytest.approx(3.0, abs=0.3)
+    assert report['variance_reduction'] > 0.8
+    assert 'pre' not in model.feature_names_out_
+
+    # Added trees grow on the CUPED-adjusted outcome, like the original ones
+    seeded = causalflow.create_model(X, T, Y, pre_covariate='pre', seed=3)
+    r = seeded.cuped_report()
+    direct = causalflow.create_model(X.drop(columns='pre'), T,
+                                     Y - r['theta'] * (pre - r['covariate_mean']), seed=3)
+    seeded.add_trees(5)
+    direct.add_trees(5)
+    np.testing.assert_array_equal(seeded.tree_predictions(X), direct.tree_predictions(X.drop(columns='pre')))
+
+    assert causalflow.create_model(X, T, Y).cuped_report() is None
+
+    with pytest.raises(ValueError, match="only available"):
+        causalflow.create_model(X, T, Y, method='linear', pre_covariate='pre')
+
+def test_degenerate_leaf_policy():
+    np.random.seed(5)
+    X = pd.DataFrame({'a': np.random.rand(200), 'b': np.random.rand(200)})
+    # Treatment only below a = 0.5 so leaves above it have no treated units
+    T = ((X['a'] < 0.5) & (np.random.rand(200) < 0.5)).astype(float).values
+    Y = 2.0 * T + np.random.normal(scale=0.1, size=200)
+
+    nan_model = causalflow.create_model(X, T, Y, degenerate_leaf='nan', max_depth=8)
+    assert np.isnan(nan_model.tree_predictions(X)).any()
+
+    parent_model = causalflow.create_model(X, T, Y, degenerate_leaf='parent', max_depth=8)
+    assert not np.isnan(parent_model.tree_predictions(X)).any()
+
+    with pytest.raises(ValueError, match="degenerate_leaf"):
+        causalflow.create_model(X, T, Y, degenerate_leaf='drop')
+
+def test_effect_relative_to_reference_row():
+    np.random.seed(3)
+    X = pd.DataFrame({'a': np.random.rand(200), 'b': np.random.rand(200)})
+    T = np.random.randint(0, 2, 200).astype(float)
+    Y = 2.0 * X['a'].values * T
+
+    model = causalflow.create_model(X, T, Y)
+    effects = model.estimate_effects(X).predictions
+    baseline = model.estimate_effects(X.iloc[[0]]).predictions[0]
+
+    relative = model.effect_relative_to(X, reference_row=X.iloc[0])
+    assert np.allclose(relative, np.asarray(effects) - baseline)
+    assert relative[0] == pytest.approx(0.0)
+
+    assert len(model.effect_relative_to(X)) == len(X)
+    with pytest.raises(ValueError):
+        model.effect_relative_to(X, reference_row=[0.5])
+
+def test_heterogeneity_gain():
+    np.random.seed(21)
+    X = pd.DataFrame({'a': np.random.rand(600), 'b': np.random.rand(600)})
+    T = np.random.randint(0, 2, 600).astype(float)
+    Y = np.where(X['a'] > 0.5, 4.0, -4.0) * T + np.random.normal(scale=0.1, size=600)
+
+    model = causalflow.create_model(X[:400], T[:400], Y[:400])
+    gain = model.heterogeneity_gain(X[400:], T[400:], Y[400:])
+    assert set(gain) == {'heterogeneity_gain', 'constant_effect', 'mse_constant', 'mse_heterogeneous'}
+    assert gain['heterogeneity_gain'] > 0.0
+    assert gain['mse_heterogeneous'] < gain['mse_constant']
+
+def test_factorial_treatments():
+    np.random.seed(13)
+    X = pd.DataFrame({'a': np.random.rand(800), 'b': np.random.rand(800)})
+    T = pd.DataFrame({
+        'email': np.random.randint(0, 2, 800).astype(float),
+        'discount': np.random.randint(0, 2, 800).astype(float),
+    })
+    Y = 1.0 * T['email'] + 2.0 * T['discount'] + 3.0 * T['email'] * T['discount']
+
+    model = causalflow.create_model(X, T, Y.values)
+    effects = model.estimate_effects(X)
+    assert list(effects.columns) == ['effect_email', 'effect_discount', 'interaction']
+    assert effects['interaction'].mean() == pytest.approx(3.0, abs=0.5)
+    assert effects['effect_email'].mean() == pytest.approx(2.5, abs=0.5)
+
+    unnamed = causalflow.create_model(X, T.values, Y.values)
+    assert 'effect_a' in unnamed.estimate_effects(X).columns
+
+    # Forest keyword arguments configure the component forests
+    seeded = [
+        causalflow.create_model(X, T, Y.values, seed=3, min_leaf_treated=5, forest_config={'n_estimators': 4})
+        for _ in range(2)
+    ]
+    assert seeded[0].estimate_effects(X).equals(seeded[1].estimate_effects(X))
+    with pytest.raises(ValueError, match="split_strategy"):
+        causalflow.create_model(X, T, Y.values, split_strategy='bogus')
+
+    for extra in ({'sample_weight': np.ones(800)}, {'baseline': np.zeros(800)}, {'pre_covariate': 'a'}):
+        with pytest.raises(ValueError, match="factorial"):
+            causalflow.create_model(X, T, Y.values, **extra)
+
+def test_monotone_segment_effects():
+    np.random.seed(17)
+    X = pd.DataFrame({'a': np.random.rand(300), 'b': np.random.rand(300)})
+    X['tier'] = np.where(X['a'] < 0.5, 'gold', 'silver')
+    T = np.random.randint(0, 2, 300).astype(float)
+    Y = np.where(X['a'] < 0.5, 3.0, 1.0) * T
+
+    model = causalflow.create_model(X[['a', 'b']], T, Y)
+    res = model.monotone_segment_effects(X, 'tier', order=['gold', 'silver'])
+    table = res['segments']
+    assert list(table['segment']) == ['gold', 'silver']
+    assert table['monotone_effect'].is_monotonic_increasing
+    assert res['max_adjustment'] > 0.0
+    assert table['monotone_effect'].iloc[0] == pytest.approx(table['monotone_effect'].iloc[1])
+
+    with pytest.raises(ValueError, match="not in the ordering"):
+        model.monotone_segment_effects(X, 'tier', order=['gold'])
+
+def test_validate_negative_control():
+    np.random.seed(29)
+    X = pd.DataFrame({'a': np.random.rand(400), 'b': np.random.rand(400)})
+    T = np.random.randint(0, 2, 400).astype(float)
+    Y = 2.0 * T + np.random.normal(scale=0.5, size=400)
+
+    model = causalflow.create_model(X, T, Y)
+    unaffected = np.random.normal(size=400)
+    assert "Negative control passed" in model.validate(negative_control=unaffected).message
+
+    leaked = 5.0 * T + np.random.normal(scale=0.1, size=400)
+    res = model.validate(negative_control=leaked)
+    assert not res.is_robust
+    assert "Negative control outcome shows an effect" in res.message
+
+    with pytest.raises(ValueError):
+        model.validate(negative_control=unaffected[:10])
+
+def test_effect_at_percentiles():
+    np.random.seed(31)
+    X = pd.DataFrame({'income': np.random.rand(400) * 100, 'age': np.random.rand(400)})
+    T = np.random.randint(0, 2, 400).astype(float)
+    Y = np.where(X['income'] > 90, 5.0, 0.0) * T
+
+    model = causalflow.create_model(X, T, Y)
+    bands = model.effect_at_percentiles(X, 'income', percentiles=[50, 90, 100])
+    assert list(bands.columns) == ['percentile', 'feature_value', 'mean_effect']
+    assert list(bands['percentile']) == [50, 90, 100]
+    assert bands['mean_effect'].iloc[-1] > bands['mean_effect'].iloc[0]
+    assert bands['feature_value'].is_monotonic_increasing
+
+    with pytest.raises(ValueError):
+        model.effect_at_percentiles(X, 'income', percentiles=[90, 50])
+
+def test_to_dataframe_keeps_row_identifiers():
+    np.random.seed(37)
+    X = pd.DataFrame({'a': np.random.rand(100), 'b': np.random.rand(100)},
+                     index=[f"cust_{i}" for i in range(100)])
+    T = np.random.randint(0, 2, 100).astype(float)
+    Y = 2.0 * X['a'].values * T
+
+    model = causalflow.create_model(X, T, Y)
+    subset = X[X['a'] > 0.5].sample(frac=1.0, random_state=0)
+    frame = model.estimate_effects(subset).to_dataframe()
+    assert list(frame.columns) == ['effect', 'ci_lower', 'ci_upper']
+    assert list(frame.index) == list(subset.index)
+
+    with_ids = subset.assign(user_id=np.arange(len(subset)) + 1000)
+    frame = model.estimate_effects(with_ids, id_col='user_id').to_dataframe()
+    assert list(frame.index) == list(with_ids['user_id'])
+
+    unindexed = model.estimate_effects(X.values).to_dataframe()
+    assert list(unindexed.index) == list(range(100))
+
+def test_post_treatment_feature_warning():
+    np.random.seed(41)
+    T = np.random.randint(0, 2, 300).astype(float)
+    X = pd.DataFrame({'age': np.random.rand(300), 'clicks': 3.0 * T + np.random.rand(300)})
+    Y = 2.0 * T + X['age'].values
+
+    with pytest.warns(UserWarning, match=r"post-treatment.*clicks"):
+        causalflow.create_model(X, T, Y)
+
+    import warnings
+    with warnings.catch_warnings():
+        warnings.simplefilter("error")
+        causalflow.create_model(X[['age']], T, Y)
+        causalflow.create_model(X, T, Y, post_treatment_threshold=None)
+
+def test_predict_multi_ci_matches_single_level():
+    np.random.seed(43)
+    X = pd.DataFrame({'a': np.random.rand(200), 'b': np.random.rand(200)})
+    T = np.random.randint(0, 2, 200).astype(float)
+    Y = 2.0 * X['a'].values * T + np.random.normal(scale=0.1, size=200)
+
+    model = causalflow.create_model(X, T, Y)
+    bands = model.predict_multi_ci(X, levels=[0.9, 0.95])
+    assert set(bands) == {'predictions', 0.9, 0.95}
+    assert np.all(bands[0.95]['lower'] <= bands[0.9]['lower'])
+    assert np.all(bands[0.95]['upper'] >= bands[0.9]['upper'])
+
+    res = model.estimate_effects(X)
+    assert np.allclose(bands[0.95]['lower'], [lo for lo, _ in res.confidence_intervals])
+    assert np.allclose(bands['predictions'], res.predictions)
+
+def test_chart_theme():
+    X = pd.DataFrame({'a': np.random.rand(100), 'b': np.random.rand(100)})
+    T = np.random.randint(0, 2, 100).astype(float)
+    Y = 2.0 * X['a'].values * T
+
+    model = causalflow.create_model(X, T, Y)
+    assert "echarts.init(chartDom, \"dark\")" in model.to_html("importance")
+    light = model.to_html("importance", theme="light")
+    assert "echarts.init(chartDom, \"light\")" in light
+    assert "background: #fff" in light
+    assert "background: transparent" in model.to_html("importance", theme="vintage")
+    assert "background: #fff" in model.to_report(["importance"], theme="light")
+
+def test_tree_correlation():
+    np.random.seed(47)
+    X = pd.DataFrame(np.random.rand(300, 4), columns=['a', 'b', 'c', 'd'])
+    T = np.random.randint(0, 2, 300).astype(float)
+    Y = 2.0 * X['a'].values * T + np.random.normal(scale=0.1, size=300)
+
+    best = causalflow.create_model(X, T, Y).tree_correlation(X)
+    extra = causalflow.create_model(X, T, Y, split_strategy='extra_random').tree_correlation(X)
+    assert -1.0 <= extra <= 1.0
+    assert 0.0 < best <= 1.0
+
+def test_split_candidates_and_quantile_strategy():
+    np.random.seed(101)
+    X = pd.DataFrame({'a': np.random.rand(400), 'b': np.random.rand(400)})
+    T = np.random.randint(0, 2, 400).astype(float)
+    Y = np.where(X['a'] > 0.5, 3.0, 1.0) * T + np.random.normal(scale=0.2, size=400)
+
+    for kwargs in [{'n_split_candidates': 25}, {'split_strategy': 'quantile'},
+                   {'split_strategy': 'quantile', 'n_split_candidates': 3}]:
+        effects = causalflow.create_model(X, T, Y, seed=1, **kwargs).estimate_effects(X)
+        assert abs(effects.mean_effect - 2.0) < 0.3
+        high = X['a'].values > 0.6
+        assert effects.predictions[high].mean() > effects.predictions[~high].mean()
+
+    with pytest.raises(ValueError, match="candidate threshold"):
+        causalflow.create_model(X, T, Y, n_split_candidates=0)
+    with pytest.raises(ValueError, match="candidate threshold"):
+        causalflow.create_model(X, T, Y, split_strategy='quantile', n_split_candidates=0)
+
+def test_max_features_options():
+    np.random.seed(103)
+    X = pd.DataFrame(np.random.rand(300, 4), columns=['a', 'b', 'c', 'd'])
+    T = np.random.randint(0, 2, 300).astype(float)
+    Y = 2.0 * X['a'].values * T + np.random.normal(scale=0.1, size=300)
+
+    for max_features in ["sqrt", "log2", 0.75, 1.0, 3, 10]:
+        effects = causalflow.create_model(X, T, Y, seed=2, max_features=max_features).estimate_effects(X)
+        assert abs(effects.mean_effect - 1.0) < 0.3
+
+    for bad in [0, 0.0, 1.5, "all"]:
+        with pytest.raises(ValueError, match="max_features"):
+            causalflow.create_model(X, T, Y, max_features=bad)
+
+def test_min_gain_prunes_weak_splits():
+    np.random.seed(107)
+    X = pd.DataFrame({'a': np.random.rand(300), 'b': np.random.rand(300)})
+    T = np.random.randint(0, 2, 300).astype(float)
+    Y = np.where(X['a'] > 0.5, 3.0, 1.0) * T + np.random.normal(scale=0.2, size=300)
+
+    default = causalflow.create_model(X, T, Y, seed=3).estimate_effects(X)
+    explicit = causalflow.create_model(X, T, Y, seed=3, min_gain=0.0).estimate_effects(X)
+    assert np.allclose(default.predictions, explicit.predictions)
+
+    stumps = causalflow.create_model(X, T, Y, seed=3, min_gain=1e6).estimate_effects(X)
+    assert np.ptp(stumps.predictions) < 1e-9
+    assert np.ptp(default.predictions) > 1.0
+
+    with pytest.raises(ValueError, match="min_gain"):
+        causalflow.create_model(X, T, Y, min_gain=-1.0)
+
+def test_predictions_match_tree_average():
+    np.random.seed(109)
+    X = pd.DataFrame(np.random.rand(2000, 3), columns=['a', 'b', 'c'])
+    T = np.random.randint(0, 2, 2000).astype(float)
+    Y = 2.0 * X['a'].values * T + np.random.normal(scale=0.1, size=2000)
+
+    model = causalflow.create_model(X, T, Y, seed=4)
+    first = model.estimate_effects(X).predictions
+    assert np.array_equal(first, model.estimate_effects(X).predictions)
+    assert np.allclose(first, model.tree_predictions(X).mean(axis=1))
+
+def test_explain_one():
+    np.random.seed(53)
+    X = pd.DataFrame({'a': np.random.rand(200), 'b': np.random.rand(200)})
+    T = np.random.randint(0, 2, 200).astype(float)
+    Y = 2.0 * X['a'].values * T
+
+    model = causalflow.create_model(X, T, Y)
+    leaves = model.explain_one(X.iloc[0])
+    assert len(leaves) == 10
+    assert all(size > 0 for _, size in leaves)
+    assert np.mean([effect for effect, _ in leaves]) == pytest.approx(model.tree_predictions(X.iloc[[0]]).mean())
+
+def test_export_structure():
+    np.random.seed(127)
+    X = pd.DataFrame({'dose': np.random.rand(300), 'noise': np.random.rand(300)})
+    T = np.random.randint(0, 2, 300).astype(float)
+    Y = np.where(X['dose'] > 0.5, 3.0, 1.0) * T + np.random.normal(scale=0.1, size=300)
+
+    model = causalflow.create_model(X, T, Y, max_depth=3)
+    structure = model.export_structure()
+    assert structure['format_version'] == 1
+    assert structure['feature_names'] == ['dose', 'noise']
+    assert len(structure['trees']) == 10
+
+    def walk(node):
+        if 'left' not in node:
+            assert node['size'] > 0
+            return [node]
+        assert node['feature'] == structure['feature_names'][node['feature_idx']]
+        assert node['size'] == node['left']['size'] + node['right']['size']
+        return walk(node['left']) + walk(node['right'])
+
+    leaves = walk(structure['trees'][0])
+    row = X.iloc[0]
+    node = structure['trees'][0]
+    while 'left' in node:
+        node = node['left'] if row.iloc[node['feature_idx']] <= node['threshold'] else node['right']
+    assert node in leaves
+    assert node['treatment_effect'] == pytest.approx(model.tree_predictions(X.iloc[[0]])[0, 0])
+
+    with pytest.raises(ValueError, match="export_structure"):
+        causalflow.create_model(X, T, Y, method='linear').export_structure()
+
+def test_missing_split_feature_follows_majority_branch():
+    np.random.seed(137)
+    X = pd.DataFrame({'a': np.random.rand(1000), 'b': np.random.rand(1000)})
+    T = np.random.randint(0, 2, 1000).astype(float)
+    Y = np.where(X['a'] > 0.8, 5.0, 1.0) * T + np.random.normal(scale=0.1, size=1000)
+
+    model = causalflow.create_model(X, T, Y, max_depth=3, seed=5)
+    rows = np.array([[np.nan, 0.5], [0.1, 0.5], [0.95, 0.5]])
+    # The fitted Rust model sees NaN directly; the wrapper would impute it
+    missing, majority, minority = model._model.estimate_effects(rows).predictions
+    assert np.isfinite(missing)
+    assert abs(missing - majority) < abs(missing - minority)
+
+    structure = model.export_structure()
+    assert all('missing_goes_left' in tree for tree in structure['trees'] if 'left' in tree)
+
+def test_min_leaf_per_arm_reduces_degenerate_leaves():
+    np.random.seed(59)
+    X = pd.DataFrame({'a': np.random.rand(600), 'b': np.random.rand(600)})
+    T = (np.random.rand(600) < 0.1).astype(float)
+    Y = 2.0 * T + np.random.normal(scale=0.1, size=600)
+
+    loose = causalflow.create_model(X, T, Y, degenerate_leaf='nan', max_depth=8)
+    strict = causalflow.create_model(X, T, Y, degenerate_leaf='nan', max_depth=8,
+                                     min_leaf_treated=5, min_leaf_control=5)
+    n_loose = np.isnan(loose.tree_predictions(X)).sum()
+    n_strict = np.isnan(strict.tree_predictions(X)).sum()
+    assert n_strict < n_loose
+
+def test_effect_profiles():
+    np.random.seed(61)
+    X = pd.DataFrame({'age': np.random.rand(400), 'member': np.random.randint(0, 2, 400).astype(float)})
+    T = np.random.randint(0, 2, 400).astype(float)
+    Y = 3.0 * X['age'].values * T
+
+    model = causalflow.create_model(X, T, Y)
+    profiles = model.effect_profiles(X, n_bins=4)
+    assert list(profiles.columns) == ['feature_name', 'bin', 'mean_effect', 'count']
+    age = profiles[profiles['feature_name'] == 'age']
+    assert len(age) == 4
+    assert age['mean_effect'].iloc[-1] > age['mean_effect'].iloc[0]
+    assert len(profiles[profiles['feature_name'] == 'member']) == 2
+    assert (profiles.groupby('feature_name')['count'].sum() == 400).all()
+
+def test_linear_model_adjusts_for_confounders():
+    rng = np.random.default_rng(29)
+    X = pd.DataFrame({'risk': rng.random(2000), 'noise': rng.random(2000)})
+    T = (rng.random(2000) < X['risk']).astype(float)
+    Y = 2.0 * T + 5.0 * X['risk'] + rng.normal(scale=0.1, size=2000)
+
+    naive = Y[T == 1].mean() - Y[T == 0].mean()
+    assert naive > 3.0
+
+    res = causalflow.create_model(X, T, Y, method='linear').estimate_effects(X)
+    assert abs(res.mean_effect - 2.0) < 0.05
+    lo, hi = res.confidence_intervals[0]
+    assert lo < 2.0 < hi
+    assert res.feature_importance[0] > 0.9
+
+def test_oob_effects_cover_training_rows():
+    np.random.seed(53)
+    X = pd.DataFrame({'a': np.random.rand(400), 'b': np.random.rand(400)})
+    T = np.random.randint(0, 2, 400).astype(float)
+    Y = 2.0 * (X['a'].values > 0.5) * T + np.random.normal(scale=0.2, size=400)
+
+    model = causalflow.create_model(X, T, Y, subsample_ratio=0.5, seed=3)
+    oob = model.estimate_oob_effects()
+    assert len(oob.predictions) == 400
+    # A row sampled by all 10 trees has no out-of-bag estimate
+    assert np.isfinite(oob.predictions).mean() > 0.95
+    high = X['a'].values > 0.5
+    assert np.nanmean(oob.predictions[high]) > np.nanmean(oob.predictions[~high]) + 1.0
+
+    full = causalflow.create_model(X, T, Y)
+    with pytest.raises(ValueError, match="subsample_ratio"):
+        full.estimate_oob_effects()
+
+@pytest.mark.parametrize("method", ["t-learner", "s-learner", "aipw"])
+def test_meta_learners(method, tmp_path):
+    rng = np.random.default_rng(41)
+    X = pd.DataFrame({'a': rng.random(1000), 'b': rng.random(1000)})
+    T = rng.integers(0, 2, 1000).astype(float)
+    Y = X['b'] + T * (1.0 + 2.0 * X['a']) + rng.normal(scale=0.1, size=1000)
+
+    model = causalflow.create_model(X, T, Y, method=method)
+    res = model.estimate_effects(X)
+    assert len(res.predictions) == 1000
+    assert abs(res.mean_effect - 2.0) < 0.1
+
+    path = tmp_path / "meta.json"
+    model.save(str(path))
+    assert json.loads(path.read_text())['model']['method'] == method
+    loaded = causalflow.load_model(str(path))
+    assert np.allclose(loaded.estimate_effects(model.transform(X)).predictions,
+                       res.predictions)
+
+def test_t_learner_cate_varies_with_covariates():
+    rng = np.random.default_rng(43)
+    X = pd.DataFrame({'a': rng.random(1000)})
+    T = rng.integers(0, 2, 1000).astype(float)
+    Y = T * 3.0 * X['a'] + rng.normal(scale=0.1, size=1000)
+
+    res = causalflow.create_model(X, T, Y, method='t-learner').estimate_effects(X)
+    assert np.corrcoef(X['a'], res.predictions)[0, 1] > 0.99
+
+    with pytest.raises(ValueError, match="binary"):
+        causalflow.create_model(X, T * 2.5, Y, method='t-learner')
+
+def test_permutation_importance_finds_effect_modifier():
+    rng = np.random.default_rng(61)
+    X = pd.DataFrame({'driver': rng.random(600), 'noise': rng.random(600)})
+    T = rng.integers(0, 2, 600).astype(float)
+    Y = 3.0 * (X['driver'] > 0.5) * T + rng.normal(scale=0.2, size=600)
+
+    model = causalflow.create_model(X, T, Y, seed=5)
+    perm = model.feature_importance(method="permutation", n_repeats=5, seed=1)
+    assert list(perm['feature_name']) == ['driver', 'noise']
+    assert perm['importance'][0] > perm['importance'][1]
+    assert (perm['std'] >= 0).all()
+
+    gain = model.feature_importance()
+    assert abs(gain['importance'].sum() - 1.0) < 1e-9
+    assert gain['std'].isna().all()
+
+    with pytest.raises(ValueError, match="importance method"):
+        model.feature_importance(method="shap")
+
+def test_propensity_weighting_reduces_confounding_bias():
+    rng = np.random.default_rng(67)
+    X = pd.DataFrame({'risk': rng.random(3000), 'noise': rng.random(3000)})
+    T = (rng.random(3000) < 0.1 + 0.8 * X['risk']).astype(float)
+    Y = 2.0 * T + 5.0 * X['risk'] + rng.normal(scale=0.1, size=3000)
+
+    plain = causalflow.create_model(X, T, Y, max_depth=2, seed=1).estimate_effects(X)
+    weighted = causalflow.create_model(X, T, Y, max_depth=2, seed=1,
+                                       use_propensity=True).estimate_effects(X)
+    assert abs(weighted.mean_effect - 2.0) < abs(plain.mean_effect - 2.0)
+    assert abs(weighted.mean_effect - 2.0) < 0.3
+
+    with pytest.raises(ValueError, match="use_propensity"):
+        causalflow.create_model(X, T, Y, method='linear', use_propensity=True)
+    with pytest.raises(ValueError, match="binary"):
+        causalflow.create_model(X, T * 3.0, Y, use_propensity=True)
+
+def test_sample_weight_reweights_effects():
+    np.random.seed(139)
+    X = pd.DataFrame({'a': np.random.rand(600), 'b': np.random.rand(600)})
+    T = np.random.randint(0, 2, 600).astype(float)
+    Y = np.where(X['a'] > 0.5, 3.0, 1.0) * T + np.random.normal(scale=0.1, size=600)
+    # Upweighting the high-effect half pulls the population ATE toward 3
+    w = np.where(X['a'] > 0.5, 9.0, 1.0)
+
+    linear = causalflow.create_model(X[['b']], T, Y, method='linear', sample_weight=w)
+    assert abs(linear.estimate_effects(X[['b']]).mean_effect - 2.8) < 0.15
+    unweighted = causalflow.create_model(X[['b']], T, Y, method='linear')
+    assert abs(unweighted.estimate_effects(X[['b']]).mean_effect - 2.0) < 0.15
+
+    same = causalflow.create_model(X, T, Y, seed=6, sample_weight=np.ones(600))
+    plain = causalflow.create_model(X, T, Y, seed=6)
+    assert np.allclose(same.estimate_effects(X).predictions, plain.estimate_effects(X).predictions)
+
+    with pytest.raises(ValueError, match="non-negative"):
+        causalflow.create_model(X, T, Y, sample_weight=-w)
+    with pytest.raises(ValueError, match="sample_weight has"):
+        causalflow.create_model(X, T, Y, sample_weight=w[:10])
+    with pytest.raises(ValueError, match="sample weights"):
+        causalflow.create_model(X, T, Y, method='t-learner', sample_weight=w)
+
+def test_sample_weight_carries_into_refits():
+    np.random.seed(139)
+    X = pd.DataFrame({'a': np.random.rand(600), 'b': np.random.rand(600)})
+    T = np.random.randint(0, 2, 600).astype(float)
+    Y = np.where(X['a'] > 0.5, 3.0, 1.0) * T + np.random.normal(scale=0.1, size=600)
+    w = np.where(X['a'] > 0.5, 9.0, 1.0)
+
+    # Unweighted refits would land near 2.0 and flag the weighted ATE of 2.8
+    linear = causalflow.create_model(X[['b']], T, Y, method='linear', sample_weight=w)
+    assert linear.refute(seed=1).is_robust
+    folds = linear.validate(n_folds=3).fold_effects
+    assert all(abs(effect - 2.8) < 0.3 for effect in folds)
+
+    forest = causalflow.create_model(X, T, Y, seed=6, sample_weight=w)
+    report = forest.fit_report()
+    assert report['effective_n_treated'] < report['n_treated']
+    assert report['effective_n_control'] < report['n_control']
+    forest.add_trees(20)
+    assert abs(forest.estimate_effects(X).mean_effect - 2.0) < 0.3
+
+def test_subgroup_effects():
+    np.random.seed(149)
+    X = pd.DataFrame({'age': np.random.uniform(20, 80, 600), 'b': np.random.rand(600)})
+    T = np.random.randint(0, 2, 600).astype(float)
+    Y = np.where(X['age'] > 50, 3.0, 1.0) * T + np.random.normal(scale=0.1, size=600)
+
+    model = causalflow.create_model(X, T, Y, max_depth=4)
+    table = model.subgroup_effects('age', [50])
+    assert list(table.columns) == ['lower', 'upper', 'mean_effect', 'count']
+    assert table['count'].sum() == 600
+    assert table['count'].iloc[0] == (X['age'] <= 50).sum()
+    assert table['mean_effect'].iloc[1] > table['mean_effect'].iloc[0] + 1.0
+    assert model.subgroup_effects(0, [50])['count'].tolist() == table['count'].tolist()
+
+    results = model.estimate_effects(X)
+    assert "[Subgroup Effects by age (training data)]" in results.summary()
+    rows = results.subgroup_effects(X['age'].to_numpy(), [40.0, 60.0], feature='age')
+    assert [r['count'] for r in rows] == [(X['age'] <= 40).sum(),
+                                          ((X['age'] > 40) & (X['age'] <= 60)).sum(),
+                                          (X['age'] > 60).sum()]
+    assert rows[0]['lower'] is None and rows[2]['upper'] is None
+    assert "[Subgroup Effects by age]" in results.summary()
+
+    with pytest.raises(ValueError, match="increasing"):
+        model.subgroup_effects('age', [60, 40])
+    with pytest.raises(ValueError, match="Unknown feature"):
+        model.subgroup_effects('height', [1.0])
+
+def test_save_and_load_linear_model(tmp_path):
+    X = pd.DataFrame({'a': np.random.rand(100), 'b': np.random.rand(100)})
+    T = np.random.randint(0, 2, 100).astype(float)
+    Y = 1.5 * T + np.random.rand(100)
+
+    model = causalflow.create_model(X, T, Y, method='linear')
+    path = tmp_path / "linear.json"
+    model.save(str(path))
+    assert json.loads(path.read_text())['model']['method'] == 'linear'
+
+    loaded = causalflow.load_model(str(path))
+    x_proc = model.transform(X)
+    assert np.allclose(loaded.estimate_effects(x_proc).predictions,
+                       model.estimate_effects(X).predictions)
+
+def test_save_and_load_forest_model(tmp_path):
+    np.random.seed(73)
+    X = pd.DataFrame({'a': np.random.rand(200), 'b': np.random.rand(200)})
+    T = np.random.randint(0, 2, 200).astype(float)
+    Y = 2.0 * X['a'].values * T + np.random.normal(scale=0.2, size=200)
+
+    model = causalflow.create_model(X, T, Y, max_depth=8)
+    path = tmp_path / "model.cfb"
+    model.save(str(path))
+    assert json.loads(path.read_text())['model']['method'] == 'forest'
+
+    loaded = causalflow.load_model(str(path))
+    x_proc = model.transform(X)
+    original = model.estimate_effects(X)
+    restored = loaded.estimate_effects(x_proc)
+    np.testing.assert_array_equal(original.predictions, restored.predictions)
+    assert original.confidence_intervals == restored.confidence_intervals
+    assert loaded.top_features(k=2) == model.top_features(k=2)
+    assert loaded.to_dot() == model.to_dot()
+    assert "Treatment" in loaded.to_graph_mermaid()
+    assert causalflow.plot_model(loaded)["visual_type"] == "causal_graph"
+    with pytest.raises(ValueError, match="Empty data"):
+        loaded.to_report()
+
+    doc = json.loads(path.read_text())
+    doc['format_version'] = 99
+    path.write_text(json.dumps(doc))
+    with pytest.raises(ValueError, match="format_version"):
+        causalflow.load_model(str(path))
+
+def test_validate_permutation_p_value():
+    np.random.seed(41)
+    X = pd.DataFrame({'a': np.random.rand(300), 'b': np.random.rand(300)})
+    T = np.random.randint(0, 2, 300).astype(float)
+    Y = 2.0 * T + np.random.normal(scale=0.5, size=300)
+
+    model = causalflow.create_model(X, T, Y)
+    res = model.validate(n_permutations=19)
+    assert res.p_value == pytest.approx(0.05)
+    assert res.is_robust
+
+    legacy = model.validate(n_permutations=0)
+    assert legacy.p_value is None
+
+@pytest.mark.parametrize("method", ["linear", "t-learner", "s-learner"])
+def test_validate_non_forest_models(method):
+    np.random.seed(43)
+    X = pd.DataFrame({'a': np.random.rand(300), 'b': np.random.rand(300)})
+    T = np.random.randint(0, 2, 300).astype(float)
+    Y = 2.0 * T + np.random.normal(scale=0.5, size=300)
+
+    model = causalflow.create_model(X, T, Y, method=method)
+    res = model.validate(n_permutations=19)
+    assert res.p_value == pytest.approx(0.05)
+    assert res.is_robust
+    assert len(res.fold_effects) == 5
+
+    with pytest.raises(ValueError, match="negative_control"):
+        model.validate(negative_control=np.random.rand(300))
+
+def test_inputs_converted_to_contiguous_float64(caplog):
+    from causalflow.preprocessing import as_float64_contiguous
+
+    fortran = np.asfortranarray(np.random.rand(50, 3))
+    with caplog.at_level("DEBUG", logger="causalflow.preprocessing"):
+        converted = as_float64_contiguous(fortran, "features")
+    assert converted.flags.c_contiguous and converted.dtype == np.float64
+    assert "Copying features" in caplog.text
+
+    ready = np.random.rand(10, 2)
+    assert as_float64_contiguous(ready) is ready
+
+    np.random.seed(43)
+    X = np.asfortranarray(np.random.randint(0, 10, size=(200, 2)))
+    T = np.random.randint(0, 2, 200)
+    Y = 2.0 * T + np.random.normal(scale=0.5, size=200)
+    model = causalflow.create_model(X, T, Y)
+    assert len(model.estimate_effects(X).predictions) == 200
+
+def test_top_features():
+    np.random.seed(47)
+    X = pd.DataFrame({'noise': np.random.rand(400), 'driver': np.random.rand(400),
+                      'other': np.random.rand(400)})
+    T = np.random.randint(0, 2, 400).astype(float)
+    Y = 4.0 * X['driver'].values * T + np.random.normal(scale=0.1, size=400)
+
+    model = causalflow.create_model(X, T, Y)
+    top = model.top_features(k=2)
+    assert len(top) == 2
+    assert top[0][0] == 'driver'
+    assert top[0][1] >= top[1][1]
+    assert len(model.top_features(k=10)) == 3
+
+def test_seed_makes_training_reproducible():
+    np.random.seed(53)
+    X = pd.DataFrame({'a': np.random.rand(300), 'b': np.random.rand(300)})
+    T = np.random.randint(0, 2, 300).astype(float)
+    Y = 3.0 * X['a'].values * T + np.random.normal(scale=0.5, size=300)
+
+    first = causalflow.create_model(X, T, Y, seed=7).estimate_effects(X)
+    second = causalflow.create_model(X, T, Y, seed=7).estimate_effects(X)
+    np.testing.assert_array_equal(first.predictions, second.predictions)
+    assert first.feature_importance == second.feature_importance
+
+    other = causalflow.create_model(X, T, Y, seed=8).estimate_effects(X)
+    assert not np.array_equal(first.predictions, other.predictions)
+    # Neighbouring seeds must not share per-tree streams
+    neighbour = causalflow.create_model(X, T, Y, seed=6).estimate_effects(X)
+    assert not np.allclose(first.predictions, neighbour.predictions)
+
+    with causalflow.config(seed=7):
+        configured = causalflow.create_model(X, T, Y).estimate_effects(X)
+    np.testing.assert_array_equal(first.predictions, configured.predictions)
+
+def test_weighted_ate_reweights_population():
+    np.random.seed(59)
+    X = pd.DataFrame({'age': np.random.rand(400) * 60 + 20, 'b': np.random.rand(400)})
+    T = np.random.randint(0, 2, 400).astype(float)
+    Y = np.where(X['age'] > 50, 4.0, 0.0) * T + np.random.normal(scale=0.3, size=400)
+
+    model = causalflow.create_model(X, T, Y)
+    uniform = model.weighted_ate(X, np.ones(400))
+    older = model.weighted_ate(X, lambda df: (df['age'] > 50).astype(float) * 3 + 1)
+    assert older['ate'] > uniform['ate']
+    assert older['ci_lower'] <= older['ate'] <= older['ci_upper']
+    assert uniform['effective_n'] == pytest.approx(400)
+    assert older['effective_n'] < 400
+
+    with pytest.raises(ValueError, match="non-negative"):
+        model.weighted_ate(X, -np.ones(400))
+
+def test_subsample_ratio():
+    np.random.seed(61)
+    X = pd.DataFrame({'a': np.random.rand(400), 'b': np.random.rand(400)})
+    T = np.random.randint(0, 2, 400).astype(float)
+    Y = 2.0 * T + np.random.normal(scale=0.5, size=400)
+
+    full = causalflow.create_model(X, T, Y, seed=3).estimate_effects(X)
+    half = causalflow.create_model(X, T, Y, seed=3, subsample_ratio=0.5).estimate_effects(X)
+    assert not np.array_equal(full.predictions, half.predictions)
+    assert abs(half.mean_effect - 2.0) < 0.5
+
+    with pytest.raises(ValueError, match="subsample_ratio"):
+        causalflow.create_model(X, T, Y, subsample_ratio=1.5)
+
+def test_fit_on_residuals_with_baseline():
+    np.random.seed(67)
+    X = pd.DataFrame({'a': np.random.rand(400), 'b': np.random.rand(400)})
+    T = np.random.randint(0, 2, 400).astype(float)
+    baseline = 10.0 * X['b'].values
+    Y = baseline + 2.0 * T + np.random.normal(scale=0.3, size=400)
+
+    model = causalflow.create_model(X, T, Y, baseline=baseline)
+    assert abs(model.estimate_effects(X).mean_effect - 2.0) < 0.5
+
+    # Refits and added trees use the residual target the model was fit on
+    adjusted = causalflow.create_model(X, T, Y, baseline=baseline, seed=3)
+    direct = causalflow.create_model(X, T, Y - baseline, seed=3)
+    adjusted.add_trees(5)
+    direct.add_trees(5)
+    np.testing.assert_array_equal(adjusted.tree_predictions(X), direct.tree_predictions(X))
+    assert (adjusted.validate(n_folds=3, n_permutations=0).fold_effects
+            == direct.validate(n_folds=3, n_permutations=0).fold_effects)
+
+    with pytest.raises(ValueError, match="baseline has"):
+        causalflow.create_model(X, T, Y, baseline=baseline[:10])
+    with pytest.raises(ValueError, match="cannot be combined"):
+        causalflow.create_model(X, T, Y, baseline=baseline, pre_covariate=baseline)
+
+def test_benchmark_predict():
+    np.random.seed(71)
+    X = pd.DataFrame({'a': np.random.rand(200), 'b': np.random.rand(200)})
+    T = np.random.randint(0, 2, 200).astype(float)
+    Y = 2.0 * T + np.random.normal(scale=0.5, size=200)
+
+    model = causalflow.create_model(X, T, Y)
+    bench = model.benchmark_predict(X, n_iters=5)
+    assert bench['n_rows'] == 200 and bench['n_iters'] == 5
+    assert 0 < bench['median_latency_ms'] <= bench['p99_latency_ms']
+    assert bench['rows_per_second'] > 0
+
+    with pytest.raises(ValueError, match="n_iters"):
+        model.benchmark_predict(X, n_iters=0)
+
+def test_validate_cross_validated_folds():
+    np.random.seed(79)
+    X = pd.DataFrame({'a': np.random.rand(400), 'b': np.random.rand(400)})
+    T = np.random.randint(0, 2, 400).astype(float)
+    Y = 2.0 * T + np.random.normal(scale=0.5, size=400)
+
+    model = causalflow.create_model(X, T, Y)
+    res = model.validate(n_folds=4, n_permutations=0)
+    assert len(res.fold_effects) == 4
+    assert all(abs(e - 2.0) < 0.6 for e in res.fold_effects)
+    assert "stable across 4 folds" in res.message
+
+    # The effect flips sign halfway through the rows, so held-out folds disagree
+    Y_drift = np.where(np.arange(400) < 200, 2.0, -2.0) * T + np.random.normal(scale=0.5, size=400)
+    drifting = causalflow.create_model(X, T, Y_drift)
+    res = drifting.validate(n_folds=4, n_permutations=0)
+    assert not res.is_robust
+    assert "vary across 4 folds" in res.message
+
+    assert model.validate(n_folds=1, n_permutations=0).fold_effects == []
+
+def test_effect_floor_clips_predictions():
+    np.random.seed(83)
+    X = pd.DataFrame({'a': np.random.rand(400), 'b': np.random.rand(400)})
+    T = np.random.randint(0, 2, 400).astype(float)
+    Y = np.where(X['a'] > 0.5, 2.0, -1.0) * T + np.random.normal(scale=0.3, size=400)
+
+    model = causalflow.create_model(X, T, Y, seed=5, effect_floor=0.0)
+    res = model.estimate_effects(X)
+    assert (res.predictions >= 0).all()
+    assert all(lo >= 0 for lo, _ in res.confidence_intervals)
+    assert res.n_clipped > 0
+    assert "were clipped to the effect bounds" in res.summary()
+
+    free = causalflow.create_model(X, T, Y, seed=5).estimate_effects(X)
+    assert free.n_clipped == 0
+    assert res.n_clipped == int((free.predictions < 0).sum())
+
+    # Subgroup and reweighted ATEs respect the bounds too
+    low = model.conditional_ate(X, "a < 0.5")
+    assert low['ate'] >= 0 and low['ci_lower'] >= 0
+    reweighted = model.weighted_ate(X, (X['a'] < 0.5).astype(float))
+    assert reweighted['ate'] >= 0 and reweighted['ci_lower'] >= 0
+
+    # The bounds change what predict reports, so they change the fingerprint
+    unbounded = causalflow.create_model(X, T, Y, seed=5)
+    loose = causalflow.create_model(X, T, Y, seed=5, effect_ceil=100.0)
+    assert unbounded.fingerprint() != loose.fingerprint()
+
+    with pytest.raises(ValueError, match="effect_floor"):
+        causalflow.create_model(X, T, Y, effect_floor=1.0, effect_ceil=0.0)
+
+@pytest.mark.parametrize("method", ["forest", "linear"])
+def test_fit_rejects_invalid_inputs(method):
+    np.random.seed(89)
+    X = pd.DataFrame({'a': np.random.rand(50), 'b': np.random.rand(50)})
+    T = np.random.randint(0, 2, 50).astype(float)
+    Y = T + np.random.rand(50)
+
+    with pytest.raises(ValueError, match="must be binary"):
+        causalflow.create_model(X, np.where(T == 1, 2.0, 0.0), Y, method=method,
+                                treatment_kind="binary")
+
+    Y_inf = Y.copy()
+    Y_inf[3] = np.inf
+    with pytest.raises(ValueError, match="NaN or Infinity"):
+        causalflow.create_model(X, T, Y_inf, method=method)
+
+@pytest.mark.parametrize("method", ["forest", "linear"])
+def test_continuous_treatment_estimates_dose_slope(method):
+    np.random.seed(97)
+    X = pd.DataFrame({'a': np.random.rand(500), 'b': np.random.rand(500)})
+    dose = np.random.rand(500) * 10
+    Y = 0.5 * dose + np.random.normal(scale=0.3, size=500)
+
+    model = causalflow.create_model(X, dose, Y, method=method)
+    assert abs(model.estimate_effects(X).mean_effect - 0.5) < 0.15
+
+    explicit = causalflow.create_model(X, dose, Y, method=method, treatment_kind="continuous")
+    assert abs(explicit.estimate_effects(X).mean_effect - 0.5) < 0.15
+
+    # Difference-in-means diagnostics would compare slopes with a treated-vs-control gap
+    with pytest.raises(ValueError, match="binary treatment"):
+        model.compare_naive_ate()
+    if method == "forest":
+        with pytest.raises(ValueError, match="calibrate requires a binary treatment"):
+            model.calibrate(X, dose, Y)
+        with pytest.raises(ValueError, match="heterogeneity_gain requires a binary treatment"):
+            model.heterogeneity_gain(X, dose, Y)
+
+    with pytest.raises(ValueError, match="treatment_kind"):
+        causalflow.create_model(X, dose, Y, treatment_kind="dose")
+
+def test_validate_time_series_rolling_origin():
+    np.random.seed(211)
+    X = pd.DataFrame({'a': np.random.rand(500), 'b': np.random.rand(500)})
+    T = np.random.randint(0, 2, 500).astype(float)
+    Y = 1.5 * T + np.random.normal(scale=0.5, size=500)
+
+    model = causalflow.create_model(X, T, Y)
+    res = model.validate(n_folds=4, is_time_series=True, n_permutations=0)
+    assert len(res.fold_effects) == 4
+    assert all(abs(e - 1.5) < 0.6 for e in res.fold_effects)
+    assert "Time-ordered (rolling-origin) validation was used" in res.message
+    assert "4 time-ordered windows" in res.message
+
+    shuffled = model.validate(n_folds=4, n_permutations=0)
+    assert "Time-ordered" not in shuffled.message
+
+def test_linear_ridge_handles_collinear_columns():
+    rng = np.random.default_rng(223)
+    a = rng.random(500)
+    X = pd.DataFrame({'a': a, 'a_copy': a + rng.normal(scale=1e-9, size=500)})
+    T = rng.integers(0, 2, 500).astype(float)
+    Y = 2.0 * T + 3.0 * a + rng.normal(scale=0.1, size=500)
+
+    res = causalflow.create_model(X, T, Y, method='linear', ridge_lambda=1.0).estimate_effects(X)
+    assert np.isfinite(res.mean_effect) and abs(res.mean_effect - 2.0) < 0.1
+    assert np.isfinite(res.confidence_intervals[0]).all()
+    # The penalty spreads the shared signal over both copies instead of blowing up
+    assert np.isfinite(res.feature_importance).all()
+    assert abs(res.feature_importance[0] - 0.5) < 0.01
+
+    with pytest.raises(ValueError, match="ridge_lambda"):
+        causalflow.create_model(X, T, Y, method='linear', ridge_lambda=-1.0)
+    with pytest.raises(ValueError, match="only available for method='linear'"):
+        causalflow.create_model(X, T, Y, ridge_lambda=1.0)
+
+def test_aipw_reports_doubly_robust_ate_and_standard_error():
+    rng = np.random.default_rng(227)
+    X = pd.DataFrame({'risk': rng.random(3000), 'noise': rng.random(3000)})
+    T = (rng.random(3000) < 0.2 + 0.6 * X['risk']).astype(float)
+    Y = 2.0 * T + 5.0 * X['risk'] + rng.normal(scale=0.5, size=3000)
+
+    naive = Y[T == 1].mean() - Y[T == 0].mean()
+    assert naive > 2.5
+
+    model = causalflow.create_model(X, T, Y, method='aipw')
+    ate = model.doubly_robust_ate()
+    assert abs(ate['ate'] - 2.0) < 0.1
+    assert ate['ci_lower'] < 2.0 < ate['ci_upper']
+    assert ate['ci_upper'] - ate['ci_lower'] == pytest.approx(2 * 1.959964 * ate['std_error'], rel=1e-4)
+
+    res = model.estimate_effects(X)
+    # Every row carries the interval of the ATE, whose width reflects the standard error
+    assert all(ci == (ate['ci_lower'], ate['ci_upper']) for ci in res.confidence_intervals)
+    assert 0.0 < ate['ci_upper'] - ate['ci_lower'] < 0.3
+    # mean_effect follows the rows passed in, like every other method; the
+    # doubly-robust ATE of the training rows stays on doubly_robust_ate()
+    subset = res.predictions[:100]
+    assert model.estimate_effects(X.iloc[:100]).mean_effect == pytest.approx(subset.mean())
+    assert res.mean_effect == pytest.approx(res.predictions.mean())
+
+    with pytest.raises(ValueError, match="method='aipw'"):
+        causalflow.create_model(X, T, Y, method='linear').doubly_robust_ate()
+
+    with pytest.raises(ValueError, match="binary"):
+        causalflow.create_model(X, T * 2.5, Y, method='aipw')
+
+def test_validate_reuses_cached_nuisance_fits():
+    rng = np.random.default_rng(229)
+    X = pd.DataFrame({'a': rng.random(300), 'b': rng.random(300)})
+    T = (rng.random(300) < 0.3 + 0.4 * X['a']).astype(float)
+    Y = 2.0 * T + X['a'] + rng.normal(scale=0.5, size=300)
+
+    model = causalflow.create_model(X, T, Y, method='aipw')
+    causalflow.clear_cache()
+    first = model.validate(n_folds=3, n_permutations=0)
+    # Three folds and one placebo shuffle each cached a propensity and two outcome models
+    n_cached = causalflow.clear_cache()
+    assert n_cached == 12
+    assert causalflow.clear_cache() == 0
+
+    model.validate(n_folds=3, n_permutations=0)
+    again = model.validate(n_folds=3, n_permutations=0)
+    assert again.fold_effects == first.fold_effects
+    # The second run only added entries for its fresh placebo shuffle
+    assert causalflow.clear_cache() == n_cached + 3
+
+def test_forest_config_dict_sets_hyperparameters():
+    np.random.seed(229)
+    X = pd.DataFrame({'a': np.random.rand(300), 'b': np.random.rand(300)})
+    T = np.random.randint(0, 2, 300).astype(float)
+    Y = 2.0 * T + np.random.normal(scale=0.3, size=300)
+
+    config = {'n_estimators': 25, 'min_leaf_size': 10, 'max_features': 1.0, 'seed': 5}
+    model = causalflow.create_model(X, T, Y, forest_config=config)
+    assert model.tree_predictions(X).shape == (300, 25)
+    again = causalflow.create_model(X, T, Y, forest_config=config)
+    assert np.allclose(again.estimate_effects(X).predictions, model.estimate_effects(X).predictions)
+
+    with pytest.raises(ValueError, match="Unknown forest_config key: n_trees"):
+        causalflow.create_model(X, T, Y, forest_config={'n_trees': 5})
+    with pytest.raises(ValueError, match="only available for method='forest'"):
+        causalflow.create_model(X, T, Y, method='linear', forest_config=config)
+
+def test_to_svg_renders_static_charts():
+    import xml.etree.ElementTree as ET
+    np.random.seed(233)
+    X = pd.DataFrame({'age': np.random.rand(300), 'income': np.random.rand(300)})
+    T = np.random.randint(0, 2, 300).astype(float)
+    Y = 2.0 * X['age'] * T + np.random.normal(scale=0.2, size=300)
+    model = causalflow.create_model(X, T, Y)
+
+    for plot_type in ["graph", "importance", "effect_dist"]:
+        svg = model.to_svg(plot_type, width=640, height=400)
+        root = ET.fromstring(svg)
+        assert root.tag == '{http://www.w3.org/2000/svg}svg'
+        assert root.get('width') == '640'
+        assert '<script' not in svg and 'cdn' not in svg
+
+    graph = model.to_svg("graph")
+    assert "Treatment" in graph and "Outcome" in graph and "age" in graph
+    assert graph.count("<rect") >= 3  # background plus treatment and outcome boxes
+    assert model.to_svg("effect_dist", n_bins=4).count('fill="#4fc3f7"') == 4
+    assert "polyline" in model.to_svg("pdp", feature="age")
+    # one bar and one +-1 SD whisker per feature
+    stability = model.to_svg("importance_stability")
+    assert stability.count('fill="#4fc3f7"') == 2
+    assert stability.count('stroke="#37474f"') == 2
+
+    with pytest.raises(ValueError, match="at least 200 x 150"):
+        model.to_svg("graph", width=0)
+    with pytest.raises(ValueError, match="got 100 x 500"):
+        model.to_svg("graph", width=100)
+    assert ET.fromstring(model.to_svg("graph", width=200, height=150)).get('height') == '150'
+
+def test_cate_quantiles_and_summary_spread():
+    np.random.seed(239)
+    X = pd.DataFrame({'a': np.random.rand(400), 'b': np.random.rand(400)})
+    T = np.random.randint(0, 2, 400).astype(float)
+    Y = np.where(X['a'] > 0.3, 2.0, -1.0) * T + np.random.normal(scale=0.2, size=400)
+
+    res = causalflow.create_model(X, T, Y).estimate_effects(X)
+    q10, q50, q90 = res.cate_quantiles([0.1, 0.5, 0.9])
+    assert np.allclose([q10, q50, q90], np.percentile(res.predictions, [10, 50, 90]))
+    assert q10 < 0.0 < q90
+
+    summary = res.summary()
+    assert "Individual effects: 10th percentile" in summary
+    positive = 100.0 * (res.predictions > 0).mean()
+    assert f"{positive:.1f}% of units have a positive effect" in summary
+
+    with pytest.raises(ValueError, match="between 0 and 1"):
+        res.cate_quantiles([1.5])
+
+def test_relative_gain_tol_stops_on_stable_nodes():
+    np.random.seed(241)
+    X = pd.DataFrame({'a': np.random.rand(600), 'b': np.random.rand(600)})
+    T = np.random.randint(0, 2, 600).astype(float)
+    Y = np.where(X['a'] > 0.5, 3.0, 1.0) * T + np.random.normal(scale=0.1, size=600)
+
+    def count_nodes(model):
+        def walk(node):
+            if 'left' not in node:
+                return 1
+            return 1 + walk(node['left']) + walk(node['right'])
+        return sum(walk(tree) for tree in model.export_structure()['trees'])
+
+    full = causalflow.create_model(X, T, Y, seed=3, max_features=1.0)
+    stopped = causalflow.create_model(X, T, Y, seed=3, max_features=1.0, relative_gain_tol=0.01)
+    assert count_nodes(stopped) < count_nodes(full) / 2
+
+    # The one clean split is still found
+    res = stopped.estimate_effects(X)
+    high = X['a'].values > 0.55
+    low = X['a'].values < 0.45
+    assert res.predictions[high].mean() - res.predictions[low].mean() > 1.5
+
+    with pytest.raises(ValueError, match="relative_gain_tol"):
+        causalflow.create_model(X, T, Y, relative_gain_tol=-0.1)
+
+def test_categorical_treatment_returns_arm_contrasts(tmp_path):
+    rng = np.random.default_rng(251)
+    X = pd.DataFrame({'a': rng.random(1500), 'b': rng.random(1500)})
+    T = rng.integers(0, 3, 1500).astype(float)
+    arm_effect = np.select([T == 1, T == 2], [1.0, np.where(X['a'] > 0.5, 3.0, -1.0)], 0.0)
+    Y = arm_effect + X['b'] + rng.normal(scale=0.2, size=1500)
+
+    model = causalflow.create_model(X, T, Y, treatment_kind='categorical', seed=1)
+    res = model.estimate_effects(X)
+    assert res.arm_effects.shape == (1500, 2)
+    assert np.allclose(res.arm_effects[:, 0], res.predictions)
+    assert abs(res.arm_effects[:, 0].mean() - 1.0) < 0.2
+    high = X['a'].values > 0.6
+    low = X['a'].values < 0.4
+    assert res.arm_effects[high, 1].mean() > 2.0
+    assert res.arm_effects[low, 1].mean() < 0.0
+
+    # mean_effect only covers arm 1 versus arm 0, so it warns on a three-arm result
+    assert res.arm_mean_effects == pytest.approx(res.arm_effects.mean(axis=0).tolist())
+    with pytest.warns(UserWarning, match="arm_mean_effects"):
+        assert res.mean_effect == pytest.approx(res.arm_mean_effects[0])
+
+    path = tmp_path / "arms.json"
+    model.save(str(path))
+    loaded = causalflow.load_model(str(path))
+    assert np.allclose(loaded.estimate_effects(model.transform(X)).arm_effects,
+                       res.arm_effects)
+    assert loaded.fingerprint() == model.fingerprint()
+
+    # Pairwise contrasts recombine the per-arm leaves: arm 2 vs arm 1 = (2 - 0) - (1 - 0)
+    contrast = model.predict_contrast(X, 1, 2)
+    assert np.allclose(contrast, res.arm_effects[:, 1] - res.arm_effects[:, 0])
+    assert np.allclose(model.predict_contrast(X, 0, 1), res.arm_effects[:, 0])
+    assert np.allclose(model.predict_contrast(X, 2, 1), -contrast)
+    with pytest.raises(ValueError, match="arms must be in"):
+        model.predict_contrast(X, 0, 3)
+    with pytest.raises(ValueError, match="categorical"):
+        causalflow.create_model(X, (T > 0).astype(float), Y).predict_contrast(X, 0, 1)
+    # One calibration map cannot cover every arm, so categorical forests refuse it
+    with pytest.raises(ValueError, match="calibrate requires a binary treatment"):
+        model.calibrate(X, T, Y)
+
+    assert causalflow.create_model(X, (T > 0).astype(float), Y).estimate_effects(X).arm_effects is None
+    with pytest.raises(ValueError, match=r"integers in \[0, 2\)"):
+        causalflow.create_model(X, T, Y, treatment_kind='categorical', n_arms=2)
+    with pytest.raises(ValueError, match="only available for method='forest'"):
+        causalflow.create_model(X, T, Y, method='linear', treatment_kind='categorical')
+
+def test_validation_result_exposes_placebo_numbers():
+    np.random.seed(47)
+    X = pd.DataFrame({'a': np.random.rand(300), 'b': np.random.rand(300)})
+    T = np.random.randint(0, 2, 300).astype(float)
+    Y = 2.0 * T + np.random.normal(scale=0.5, size=300)
+
+    model = causalflow.create_model(X, T, Y)
+    res = model.validate(n_permutations=0)
+    assert res.estimated_effect > 0
+    assert np.isfinite(res.placebo_effect)
+    assert res.robustness_score == pytest.approx(res.placebo_effect / res.estimated_effect)
+    assert "robustness score" in res.message
+    assert len(res.per_fold_effects) == 5
+    assert res.per_fold_effects == res.fold_effects
+
+    permuted = model.validate(n_permutations=19)
+    assert np.isfinite(permuted.placebo_effect)
+    assert permuted.robustness_score < 0.5
+
+def test_validate_placebo_thresholds():
+    np.random.seed(53)
+    X = pd.DataFrame({'a': np.random.rand(300), 'b': np.random.rand(300)})
+    T = np.random.randint(0, 2, 300).astype(float)
+    Y = 2.0 * T + np.random.normal(scale=0.5, size=300)
+
+    # Ratio branch: the estimated effect is non-zero
+    model = causalflow.create_model(X, T, Y)
+    assert model.validate(n_folds=1, n_permutations=0).is_robust
+    assert not model.validate(n_folds=1, n_permutations=0, placebo_ratio_threshold=0.0).is_robust
+    assert model.validate(n_folds=1, n_permutations=0, placebo_ratio_threshold=10.0).is_robust
+
+    # Absolute branch: a constant outcome gives an estimated effect of exactly zero
+    with pytest.warns(UserWarning, match="constant"):
+        flat = causalflow.create_model(X, T, np.ones(300))
+    assert flat.validate(n_folds=1, n_permutations=0).is_robust
+    assert not flat.validate(n_folds=1, n_permutations=0, absolute_threshold=0.0).is_robust
+
+    # The default call runs the permutation test, which has no use for the thresholds
+    with pytest.raises(ValueError, match="n_permutations=0"):
+        model.validate(placebo_ratio_threshold=0.5)
+    with pytest.raises(ValueError, match="n_permutations=0"):
+        model.validate(n_folds=1, absolute_threshold=1.0)
+    assert model.validate(n_folds=1, placebo_ratio_threshold=causalflow.DEFAULT_PLACEBO_RATIO_THRESHOLD).p_value is not None
+
+    with pytest.raises(ValueError, match="placebo_ratio_threshold"):
+        model.validate(placebo_ratio_threshold=-0.1)
+    with pytest.raises(ValueError, match="absolute_threshold"):
+        model.validate(absolute_threshold=float('nan'))
+
+@pytest.mark.parametrize("method", ["forest", "linear"])
+def test_refute_random_common_cause(method):
+    np.random.seed(59)
+    X = pd.DataFrame({'a': np.random.rand(300), 'b': np.random.rand(300)})
+    T = np.random.randint(0, 2, 300).astype(float)
+    Y = 2.0 * T + X['a'].values + np.random.normal(scale=0.5, size=300)
+
+    model = causalflow.create_model(X, T, Y, method=method, seed=0)
+    res = model.refute("random_common_cause", seed=7)
+    assert res.is_robust
+    assert "random common cause" in res.message
+    assert "%" in res.message
+    assert res.estimated_effect == pytest.approx(abs(model.estimate_effects(X).mean_effect))
+    assert model.refute(seed=7).message == res.message
+
+    with pytest.raises(ValueError, match="Unknown refuter"):
+        model.refute("bootstrap")